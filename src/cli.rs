@@ -0,0 +1,1001 @@
+//! Command line interface of tuckr
+//!
+//! Parses the arguments, sets up the global options and hands them over to the right command
+
+use crate::{dotfiles, fileops, hooks, secrets, symlinks};
+use clap::{CommandFactory, Parser, Subcommand};
+use owo_colors::OwoColorize;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(about, author, version, propagate_version = true)]
+struct Cli {
+    #[arg(short, long)]
+    /// Choose which dotfile profile to use
+    profile: Option<String>,
+
+    /// Look for the dotfiles directory inside of this directory (env: TUCKR_HOME)
+    #[arg(long, global = true, value_name = "dir")]
+    home: Option<PathBuf>,
+
+    /// Deploy the dotfiles to this directory instead of $HOME (env: TUCKR_TARGET)
+    #[arg(short, long, global = true, value_name = "dir")]
+    target: Option<PathBuf>,
+
+    /// Walk into the symlinked directories inside of groups instead of deploying them as a whole
+    #[arg(long, global = true)]
+    follow_symlinks: bool,
+
+    /// Don't skip junk files like .DS_Store or Thumbs.db
+    #[arg(long, global = true)]
+    no_ignore: bool,
+
+    /// Only print errors and what the command was asked to show
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Print every file that gets deployed or removed
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Get dotfiles' symlinking status (alias: s)
+    #[command(alias = "s")]
+    Status {
+        #[arg(value_name = "group")]
+        groups: Option<Vec<String>>,
+
+        /// List symlinks whose group has been removed from dotfiles/Configs
+        #[arg(long, conflicts_with = "groups")]
+        orphans: bool,
+
+        /// Print the status as a JSON object
+        #[arg(long, conflicts_with = "orphans")]
+        json: bool,
+
+        /// Only print the symlinked groups, fails if there are none
+        #[arg(long, group = "filter", conflicts_with_all = ["orphans", "json"])]
+        symlinked: bool,
+
+        /// Only print the groups that aren't symlinked, fails if there are none
+        #[arg(long, group = "filter", conflicts_with_all = ["orphans", "json"])]
+        not_symlinked: bool,
+
+        /// Only print the conflicting targets, fails if there are none
+        #[arg(long, group = "filter", conflicts_with_all = ["orphans", "json"])]
+        conflicts: bool,
+
+        /// Only print the targets that are broken symlinks, fails if there are none
+        #[arg(long, group = "filter", conflicts_with_all = ["orphans", "json"])]
+        broken_only: bool,
+
+        /// Show how many files each group has
+        #[arg(long, conflicts_with_all = ["orphans", "json", "filter"])]
+        count: bool,
+
+        /// Show the status of every profile instead of only the selected one
+        #[arg(long, conflicts_with_all = ["json", "filter"])]
+        all_profiles: bool,
+    },
+
+    /// Deploy dotfiles for the supplied groups (alias: a)
+    ///
+    /// If no groups are supplied, they can be picked from a list of the available ones
+    #[command(alias = "a")]
+    Add {
+        #[arg(value_name = "group")]
+        groups: Vec<String>,
+
+        /// Exclude certain groups from being added
+        #[arg(short, long, value_name = "group", use_value_delimiter = true)]
+        exclude: Vec<String>,
+
+        /// Only handle the groups matching these when used with `*`
+        #[arg(long, value_name = "group", use_value_delimiter = true)]
+        include: Vec<String>,
+
+        /// Override conflicting dotfiles
+        #[arg(short, long)]
+        force: bool,
+
+        /// Don't override conflicting dotfiles even if the group's group.toml sets `force = true`
+        #[arg(long, conflicts_with = "force")]
+        no_force: bool,
+
+        /// Adopt conflicting dotfiles
+        #[arg(short, long)]
+        adopt: bool,
+
+        /// Automatically answer yes to stdin prompts
+        #[arg(short = 'y', long)]
+        assume_yes: bool,
+
+        /// Remove stale symlinks for files that were deleted from the groups
+        #[arg(long)]
+        prune: bool,
+
+        /// Move overridden dotfiles into this directory instead of deleting them
+        #[arg(long, value_name = "dir", requires = "force")]
+        backup_dir: Option<PathBuf>,
+
+        /// How dotfiles are deployed to their targets
+        #[arg(short, long, value_enum, default_value_t)]
+        mode: symlinks::DeployMode,
+
+        /// Create the groups' directories instead of symlinking them and only symlink files
+        #[arg(long)]
+        create_dirs: bool,
+
+        /// Create the groups' directories up to this depth and symlink everything below it
+        #[arg(long, value_name = "N", conflicts_with = "create_dirs")]
+        depth: Option<usize>,
+
+        /// Deploy the files that need elevated privileges through sudo or doas
+        #[arg(long)]
+        sudo: bool,
+
+        /// Ask whether to overwrite, adopt, skip or diff each conflicting file
+        #[arg(short, long, conflicts_with_all = ["force", "adopt"])]
+        interactive: bool,
+
+        /// Print what was deployed as a JSON array of actions
+        #[arg(long, conflicts_with = "interactive")]
+        json: bool,
+
+        /// Show every change that will be made and ask once before making them
+        #[arg(long, conflicts_with_all = ["interactive", "json"])]
+        interactive_apply: bool,
+    },
+
+    /// Remove dotfiles for the supplied groups
+    Rm {
+        #[arg(required = true, value_name = "group")]
+        groups: Vec<String>,
+
+        /// Exclude certain groups from being removed
+        #[arg(short, long, value_name = "group", use_value_delimiter = true)]
+        exclude: Vec<String>,
+
+        /// Only handle the groups matching these when used with `*`
+        #[arg(long, value_name = "group", use_value_delimiter = true)]
+        include: Vec<String>,
+
+        /// Replace the symlinks with copies of the dotfiles they pointed to
+        #[arg(short, long)]
+        restore: bool,
+
+        /// Move what gets removed into tuckr's trash directory instead of deleting it
+        #[arg(long, conflicts_with = "restore")]
+        trash: bool,
+
+        /// Print what was removed as a JSON array of actions
+        #[arg(long)]
+        json: bool,
+
+        /// Show every change that will be made and ask once before making them
+        #[arg(long, conflicts_with = "json")]
+        interactive_apply: bool,
+
+        /// Remove the groups from every profile instead of only the selected one
+        #[arg(long)]
+        all_profiles: bool,
+    },
+
+    /// Show how the conflicting files on the targets differ from the dotfiles
+    ///
+    /// All groups are diffed if none are supplied
+    Diff {
+        #[arg(value_name = "group")]
+        groups: Vec<String>,
+
+        /// Exclude certain groups from being diffed
+        #[arg(short, long, value_name = "group", use_value_delimiter = true)]
+        exclude: Vec<String>,
+    },
+
+    /// Report symlinks created by tuckr whose dotfile no longer exists
+    Doctor,
+
+    /// Rename a group and its conditional groups and update their symlinks
+    #[command(arg_required_else_help = true)]
+    Rename { from: String, to: String },
+
+    /// Remove symlinks created by tuckr whose dotfile no longer exists
+    ///
+    /// All groups are pruned if none are supplied, including groups that were deleted
+    Prune {
+        #[arg(value_name = "group")]
+        groups: Vec<String>,
+
+        /// Exclude certain groups from being pruned
+        #[arg(short, long, value_name = "group", use_value_delimiter = true)]
+        exclude: Vec<String>,
+    },
+
+    /// Remove every symlink created by tuckr for the current profile
+    ///
+    /// The dotfiles directory itself is left untouched
+    Uninstall {
+        /// Replace the symlinks with copies of the dotfiles they pointed to
+        #[arg(short, long)]
+        restore: bool,
+
+        /// Remove the symlinks without asking first
+        #[arg(short = 'y', long)]
+        assume_yes: bool,
+    },
+
+    /// Setup groups and run their hooks
+    ///
+    /// If no groups are supplied, they can be picked from a list of the available ones
+    Set {
+        #[arg(value_name = "group")]
+        groups: Vec<String>,
+
+        /// Exclude certain groups from being added and hooked
+        #[arg(short, long, value_name = "group", use_value_delimiter = true)]
+        exclude: Vec<String>,
+
+        /// Only handle the groups matching these when used with `*`
+        #[arg(long, value_name = "group", use_value_delimiter = true)]
+        include: Vec<String>,
+
+        /// Override conflicting dotfiles
+        #[arg(short, long)]
+        force: bool,
+
+        /// Don't override conflicting dotfiles even if the group's group.toml sets `force = true`
+        #[arg(long, conflicts_with = "force")]
+        no_force: bool,
+
+        /// Adopt conflicting dotfiles
+        #[arg(short, long)]
+        adopt: bool,
+
+        /// Automatically answer yes to stdin prompts
+        #[arg(short = 'y', long)]
+        assume_yes: bool,
+
+        /// Move overridden dotfiles into this directory instead of deleting them
+        #[arg(long, value_name = "dir", requires = "force")]
+        backup_dir: Option<PathBuf>,
+
+        /// How dotfiles are deployed to their targets
+        #[arg(short, long, value_enum, default_value_t)]
+        mode: symlinks::DeployMode,
+
+        /// Create the groups' directories instead of symlinking them and only symlink files
+        #[arg(long)]
+        create_dirs: bool,
+
+        /// Create the groups' directories up to this depth and symlink everything below it
+        #[arg(long, value_name = "N", conflicts_with = "create_dirs")]
+        depth: Option<usize>,
+
+        /// Deploy the files that need elevated privileges through sudo or doas
+        #[arg(long)]
+        sudo: bool,
+
+        /// Keep going after a hook fails and exit with an error once every group ran
+        #[arg(short, long)]
+        keep_going: bool,
+
+        /// Kill hooks that run for longer than this many seconds
+        #[arg(long, value_name = "seconds")]
+        hook_timeout: Option<u64>,
+
+        /// Make hooks executable instead of failing when they aren't
+        #[arg(long)]
+        chmod_hooks: bool,
+
+        /// Show every change that will be made and ask once before making them
+        #[arg(long)]
+        interactive_apply: bool,
+
+        /// Decrypt the groups' secrets to their targets after symlinking them,
+        /// existing files are only overwritten with --force
+        #[arg(long)]
+        with_secrets: bool,
+
+        /// Read the password for the secrets from this file instead of prompting for it
+        #[arg(long, value_name = "file", requires = "with_secrets")]
+        passphrase_file: Option<PathBuf>,
+    },
+
+    /// Remove the groups' dotfiles and set them up again, running their hooks
+    Reload {
+        #[arg(required = true, value_name = "group")]
+        groups: Vec<String>,
+
+        /// Exclude certain groups from being reloaded
+        #[arg(short, long, value_name = "group", use_value_delimiter = true)]
+        exclude: Vec<String>,
+
+        /// Only handle the groups matching these when used with `*`
+        #[arg(long, value_name = "group", use_value_delimiter = true)]
+        include: Vec<String>,
+
+        /// How dotfiles are deployed to their targets
+        #[arg(short, long, value_enum, default_value_t)]
+        mode: symlinks::DeployMode,
+
+        /// Create the groups' directories instead of symlinking them and only symlink files
+        #[arg(long)]
+        create_dirs: bool,
+
+        /// Create the groups' directories up to this depth and symlink everything below it
+        #[arg(long, value_name = "N", conflicts_with = "create_dirs")]
+        depth: Option<usize>,
+
+        /// Deploy the files that need elevated privileges through sudo or doas
+        #[arg(long)]
+        sudo: bool,
+
+        /// Keep going after a hook fails and exit with an error once every group ran
+        #[arg(short, long)]
+        keep_going: bool,
+
+        /// Kill hooks that run for longer than this many seconds
+        #[arg(long, value_name = "seconds")]
+        hook_timeout: Option<u64>,
+
+        /// Make hooks executable instead of failing when they aren't
+        #[arg(long)]
+        chmod_hooks: bool,
+    },
+
+    /// Encrypt files and move them to dotfiles/Secrets (alias: e)
+    #[command(alias = "e")]
+    Encrypt {
+        group: String,
+        #[arg(required = true, value_name = "FILE")]
+        dotfiles: Vec<String>,
+
+        /// Skip the files inside of the directories that match these glob patterns
+        #[arg(short, long, value_name = "glob", use_value_delimiter = true)]
+        exclude: Vec<String>,
+
+        /// Use the contents of this key file as the key instead of prompting for a password
+        #[arg(short, long, value_name = "file")]
+        key_file: Option<PathBuf>,
+
+        /// Read the password from this file instead of prompting for it
+        #[arg(long, value_name = "file", conflicts_with = "key_file")]
+        passphrase_file: Option<PathBuf>,
+    },
+
+    /// Decrypt files (alias: d)
+    #[command(alias = "d")]
+    Decrypt {
+        #[arg(required = true, value_name = "group")]
+        groups: Vec<String>,
+        #[arg(short, long, value_name = "group", use_value_delimiter = true)]
+        exclude: Vec<String>,
+
+        /// Only handle the groups matching these when used with `*`
+        #[arg(long, value_name = "group", use_value_delimiter = true)]
+        include: Vec<String>,
+
+        /// Use the contents of this key file as the key instead of prompting for a password
+        #[arg(short, long, value_name = "file")]
+        key_file: Option<PathBuf>,
+
+        /// Decrypt into this directory keeping the structure of the group
+        #[arg(short, long, value_name = "dir")]
+        out: Option<PathBuf>,
+
+        /// Only check that every secret can be decrypted without writing anything
+        #[arg(long, conflicts_with = "out")]
+        check: bool,
+
+        /// Read the password from this file instead of prompting for it
+        #[arg(long, value_name = "file", conflicts_with = "key_file")]
+        passphrase_file: Option<PathBuf>,
+    },
+
+    /// Edit an encrypted file from dotfiles/Secrets with $EDITOR
+    #[command(arg_required_else_help = true)]
+    Edit {
+        group: String,
+        /// Path of the file relative to its group
+        file: PathBuf,
+
+        /// Read the password from this file instead of prompting for it
+        #[arg(long, value_name = "file")]
+        passphrase_file: Option<PathBuf>,
+    },
+
+    /// Re-encrypt the secrets of the supplied groups with a new password
+    Reencrypt {
+        #[arg(required = true, value_name = "group")]
+        groups: Vec<String>,
+
+        /// Exclude certain groups from being re-encrypted
+        #[arg(short, long, value_name = "group", use_value_delimiter = true)]
+        exclude: Vec<String>,
+
+        /// Use the contents of this key file as the old key instead of prompting for the old password
+        #[arg(short, long, value_name = "file")]
+        key_file: Option<PathBuf>,
+
+        /// Read the old password from this file instead of prompting for it
+        #[arg(long, value_name = "file", conflicts_with = "key_file")]
+        passphrase_file: Option<PathBuf>,
+
+        /// Use the contents of this key file as the new key instead of prompting for a new password
+        #[arg(long, value_name = "file")]
+        new_key_file: Option<PathBuf>,
+
+        /// Read the new password from this file instead of prompting for it
+        #[arg(long, value_name = "file", conflicts_with = "new_key_file")]
+        new_passphrase_file: Option<PathBuf>,
+    },
+
+    /// Copy files into groups
+    Push {
+        group: String,
+        #[arg(short = 'y', long)]
+        assume_yes: bool,
+
+        /// Move the files into the group instead of copying them
+        #[arg(short, long = "move")]
+        move_files: bool,
+
+        /// Symlink the group once the files were pushed
+        #[arg(short, long)]
+        add: bool,
+        #[arg(required = true)]
+        files: Vec<String>,
+    },
+
+    /// Remove groups from dotfiles/Configs
+    #[command(arg_required_else_help = true)]
+    Pop {
+        groups: Vec<String>,
+        #[arg(short = 'y', long)]
+        assume_yes: bool,
+
+        /// Only list the files that would be removed and the symlinks that would be left broken
+        #[arg(short, long, conflicts_with = "assume_yes")]
+        list: bool,
+
+        /// Move the groups into tuckr's trash directory instead of deleting them
+        #[arg(long)]
+        trash: bool,
+    },
+
+    /// List dotfiles groups, hooks, secrets, profiles
+    #[command(subcommand, arg_required_else_help = true)]
+    Ls(ListType),
+
+    /// Manage dotfiles profiles
+    #[command(subcommand, arg_required_else_help = true)]
+    Profile(ProfileAction),
+
+    /// Initialize dotfile directory
+    ///
+    /// Creates the files that are necessary to use Tuckr
+    Init {
+        /// Also make the dotfiles directory a git repository
+        #[arg(long)]
+        git: bool,
+    },
+
+    /// Clone a dotfiles repository from a git remote
+    Clone {
+        url: String,
+
+        /// Replace the dotfiles directory if it already exists
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Archive the dotfiles directory into a .tar.gz file
+    ///
+    /// Secrets are archived encrypted
+    Export {
+        #[arg(value_name = "out.tar.gz")]
+        out: PathBuf,
+
+        /// Only print which files would be archived
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Extract an archive made by export into the dotfiles directory
+    Import {
+        #[arg(value_name = "archive.tar.gz")]
+        archive: PathBuf,
+
+        /// Replace the dotfiles directory if it already exists
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Convert a GNU Stow repo into Tuckr
+    ///
+    /// Every top-level directory is treated as a Stow package and becomes a group
+    FromStow {
+        #[arg(short = 'y', long)]
+        assume_yes: bool,
+
+        /// Move the whole repo into a single group instead of one group per package,
+        /// top-level README and LICENSE files are left where they are
+        #[arg(long, value_name = "GROUP")]
+        single_group: Option<String>,
+
+        /// Only print which files would be moved without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Time tuckr's operations on a generated dotfiles repo
+    #[command(hide = true)]
+    Bench {
+        /// Number of groups to generate
+        #[arg(long, default_value_t = 100)]
+        groups: usize,
+
+        /// Number of files to generate per group
+        #[arg(long, default_value_t = 10)]
+        files: usize,
+    },
+
+    /// Print the absolute path of a group's directory
+    #[command(arg_required_else_help = true)]
+    Which {
+        group: String,
+
+        /// Print the group's Hooks directory instead
+        #[arg(long, conflicts_with = "secrets")]
+        hooks: bool,
+
+        /// Print the group's Secrets directory instead
+        #[arg(long)]
+        secrets: bool,
+    },
+
+    /// Return the group files belongs to
+    #[command(name = "groupis", arg_required_else_help = true)]
+    GroupIs { files: Vec<String> },
+
+    /// Print a completion script for the given shell to stdout
+    #[command(hide = true)]
+    Completions { shell: clap_complete::Shell },
+}
+
+#[derive(Debug, Subcommand)]
+enum ListType {
+    #[command(alias = "p")]
+    Profiles,
+    #[command(alias = "s")]
+    Secrets,
+    #[command(alias = "h")]
+    Hooks,
+    /// List the groups in Configs and whether they're supported and symlinked
+    #[command(alias = "g")]
+    Groups,
+    /// List the files a group deploys and where they're deployed to
+    #[command(alias = "f")]
+    Files { group: String },
+}
+
+#[derive(Debug, Subcommand)]
+enum ProfileAction {
+    /// Create a new profile from a copy of an existing one
+    Copy { from: String, to: String },
+}
+
+/// Runs the command line interface with the process' arguments
+pub fn run() -> ExitCode {
+    let mut cli = Cli::parse();
+    let config = dotfiles::Config::load();
+    cli.profile = dotfiles::resolve_profile(cli.profile, &config);
+    // informational messages would get mixed with the JSON output
+    let json_output = matches!(
+        cli.command,
+        Command::Add { json: true, .. } | Command::Rm { json: true, .. }
+    );
+    let mut ctx = dotfiles::Context {
+        verbosity: match (cli.quiet || json_output, cli.verbose) {
+            (true, _) => dotfiles::Verbosity::Quiet,
+            (_, true) => dotfiles::Verbosity::Verbose,
+            _ => dotfiles::Verbosity::Normal,
+        },
+        follow_symlinks: cli.follow_symlinks,
+        no_ignore: cli.no_ignore,
+        ..Default::default()
+    };
+
+    if let Some(target_dir) = cli
+        .target
+        .or_else(|| std::env::var_os("TUCKR_TARGET").map(PathBuf::from))
+        .filter(|dir| !dir.as_os_str().is_empty())
+    {
+        let target_dir = std::path::absolute(&target_dir).unwrap_or(target_dir);
+        if target_dir.exists() && !target_dir.is_dir() {
+            eprintln!(
+                "{}",
+                dotfiles::DotfileError::NotADir(target_dir)
+                    .to_string()
+                    .red()
+            );
+            return dotfiles::ReturnCode::NoSuchFileOrDir.into();
+        }
+        ctx.target_dir = Some(target_dir);
+    }
+
+    if let Some(home_dir) = cli
+        .home
+        .or_else(|| std::env::var_os("TUCKR_HOME").map(PathBuf::from))
+        .filter(|dir| !dir.as_os_str().is_empty())
+    {
+        ctx.dotfiles_home = Some(std::path::absolute(&home_dir).unwrap_or(home_dir));
+    }
+
+    if let Ok(dotfiles_dir) = dotfiles::get_dotfiles_path(&ctx, cli.profile.clone()) {
+        let mut custom_targets = dotfiles::load_custom_targets(&dotfiles_dir);
+        for target in config.custom_targets {
+            if !custom_targets.contains(&target) {
+                custom_targets.push(target);
+            }
+        }
+        ctx.custom_targets = custom_targets;
+    }
+    let ctx = &ctx;
+
+    rust_i18n::set_locale(sys_locale::get_locale().unwrap_or_default().as_str());
+
+    let exit_code = match cli.command {
+        Command::Set {
+            groups,
+            exclude,
+            include,
+            force,
+            no_force,
+            adopt,
+            assume_yes,
+            backup_dir,
+            mode,
+            create_dirs,
+            depth,
+            sudo,
+            keep_going,
+            hook_timeout,
+            chmod_hooks,
+            interactive_apply,
+            with_secrets,
+            passphrase_file,
+        } => fileops::pick_groups_if_empty(ctx, cli.profile.clone(), groups, true).and_then(
+            |groups| {
+                let groups = dotfiles::include_groups(
+                    ctx,
+                    cli.profile.clone(),
+                    dotfiles::DotfileType::Configs,
+                    &groups,
+                    &include,
+                );
+                let add_opts = symlinks::AddOptions {
+                    force,
+                    no_force,
+                    adopt,
+                    assume_yes,
+                    backup_dir,
+                    mode,
+                    create_dirs,
+                    depth,
+                    sudo,
+                    interactive_apply,
+                    ..Default::default()
+                };
+                let hook_opts = hooks::HookOptions {
+                    keep_going,
+                    timeout: hook_timeout.map(std::time::Duration::from_secs),
+                    fix_permissions: chmod_hooks,
+                    with_secrets,
+                    passphrase_file,
+                };
+                hooks::set_cmd(ctx, cli.profile, &groups, &exclude, &add_opts, &hook_opts)
+            },
+        ),
+
+        Command::Reload {
+            groups,
+            exclude,
+            include,
+            mode,
+            create_dirs,
+            depth,
+            sudo,
+            keep_going,
+            hook_timeout,
+            chmod_hooks,
+        } => {
+            let add_opts = symlinks::AddOptions {
+                mode,
+                create_dirs,
+                depth,
+                sudo,
+                ..Default::default()
+            };
+            let hook_opts = hooks::HookOptions {
+                keep_going,
+                timeout: hook_timeout.map(std::time::Duration::from_secs),
+                fix_permissions: chmod_hooks,
+                ..Default::default()
+            };
+            hooks::reload_cmd(
+                ctx,
+                cli.profile.clone(),
+                &dotfiles::include_groups(
+                    ctx,
+                    cli.profile,
+                    dotfiles::DotfileType::Configs,
+                    &groups,
+                    &include,
+                ),
+                &exclude,
+                &add_opts,
+                &hook_opts,
+            )
+        }
+
+        Command::Add {
+            groups,
+            exclude,
+            include,
+            force,
+            no_force,
+            adopt,
+            assume_yes,
+            prune,
+            backup_dir,
+            mode,
+            create_dirs,
+            depth,
+            interactive,
+            json,
+            sudo,
+            interactive_apply,
+        } => fileops::pick_groups_if_empty(ctx, cli.profile.clone(), groups, false).and_then(
+            |groups| {
+                let groups = dotfiles::include_groups(
+                    ctx,
+                    cli.profile.clone(),
+                    dotfiles::DotfileType::Configs,
+                    &groups,
+                    &include,
+                );
+                let add_opts = symlinks::AddOptions {
+                    force,
+                    no_force,
+                    adopt,
+                    assume_yes,
+                    prune,
+                    backup_dir,
+                    mode,
+                    create_dirs,
+                    depth,
+                    interactive,
+                    json,
+                    sudo,
+                    interactive_apply,
+                };
+                symlinks::add_cmd(ctx, cli.profile, &groups, &exclude, &add_opts)
+            },
+        ),
+
+        Command::Rm {
+            groups,
+            exclude,
+            include,
+            restore,
+            trash,
+            json,
+            interactive_apply,
+            all_profiles: true,
+        } => symlinks::remove_all_profiles_cmd(
+            ctx,
+            &groups,
+            &include,
+            &exclude,
+            restore,
+            trash,
+            json,
+            interactive_apply,
+        ),
+        Command::Rm {
+            groups,
+            exclude,
+            include,
+            restore,
+            trash,
+            json,
+            interactive_apply,
+            all_profiles: false,
+        } => symlinks::remove_cmd(
+            ctx,
+            cli.profile.clone(),
+            &dotfiles::include_groups(
+                ctx,
+                cli.profile,
+                dotfiles::DotfileType::Configs,
+                &groups,
+                &include,
+            ),
+            &exclude,
+            restore,
+            trash,
+            json,
+            interactive_apply,
+        ),
+        Command::Diff { groups, exclude } => {
+            symlinks::diff_cmd(ctx, cli.profile, &groups, &exclude)
+        }
+        Command::Doctor => symlinks::doctor_cmd(ctx, cli.profile),
+        Command::Rename { from, to } => symlinks::rename_cmd(ctx, cli.profile, &from, &to),
+        Command::Prune { groups, exclude } => {
+            symlinks::prune_cmd(ctx, cli.profile, &groups, &exclude)
+        }
+        Command::Uninstall {
+            restore,
+            assume_yes,
+        } => symlinks::uninstall_cmd(ctx, cli.profile, restore, assume_yes),
+        Command::Status {
+            groups,
+            orphans,
+            count,
+            all_profiles: true,
+            ..
+        } => symlinks::status_all_profiles_cmd(ctx, groups, orphans, count),
+        Command::Status {
+            groups,
+            orphans,
+            json,
+            symlinked,
+            not_symlinked,
+            conflicts,
+            broken_only,
+            count,
+            all_profiles: false,
+        } => {
+            let filter = match (symlinked, not_symlinked, conflicts, broken_only) {
+                (true, ..) => Some(symlinks::StatusFilter::Symlinked),
+                (_, true, ..) => Some(symlinks::StatusFilter::NotSymlinked),
+                (_, _, true, _) => Some(symlinks::StatusFilter::Conflicts),
+                (.., true) => Some(symlinks::StatusFilter::Broken),
+                _ => None,
+            };
+            symlinks::status_cmd(ctx, cli.profile, groups, orphans, json, filter, count)
+        }
+        Command::Encrypt {
+            group,
+            dotfiles,
+            exclude,
+            key_file,
+            passphrase_file,
+        } => secrets::encrypt_cmd(
+            ctx,
+            cli.profile,
+            &group,
+            &dotfiles,
+            &exclude,
+            key_file.as_deref(),
+            passphrase_file.as_deref(),
+        ),
+        Command::Decrypt {
+            groups,
+            exclude,
+            include,
+            key_file,
+            out,
+            check,
+            passphrase_file,
+        } => secrets::decrypt_cmd(
+            ctx,
+            cli.profile.clone(),
+            &dotfiles::include_groups(
+                ctx,
+                cli.profile,
+                dotfiles::DotfileType::Secrets,
+                &groups,
+                &include,
+            ),
+            &exclude,
+            key_file.as_deref(),
+            out.as_deref(),
+            check,
+            passphrase_file.as_deref(),
+        ),
+        Command::Edit {
+            group,
+            file,
+            passphrase_file,
+        } => secrets::edit_cmd(ctx, cli.profile, &group, &file, passphrase_file.as_deref()),
+        Command::Reencrypt {
+            groups,
+            exclude,
+            key_file,
+            passphrase_file,
+            new_key_file,
+            new_passphrase_file,
+        } => secrets::reencrypt_cmd(
+            ctx,
+            cli.profile,
+            &groups,
+            &exclude,
+            key_file.as_deref(),
+            passphrase_file.as_deref(),
+            new_key_file.as_deref(),
+            new_passphrase_file.as_deref(),
+        ),
+        Command::FromStow {
+            assume_yes,
+            single_group,
+            dry_run,
+        } => fileops::from_stow_cmd(ctx, cli.profile, assume_yes, single_group, dry_run),
+        Command::Clone { url, force } => fileops::clone_cmd(ctx, cli.profile, &url, force),
+        Command::Export { out, dry_run } => fileops::export_cmd(ctx, cli.profile, &out, dry_run),
+        Command::Import { archive, force } => {
+            fileops::import_cmd(ctx, cli.profile, &archive, force)
+        }
+        Command::Init { git } => fileops::init_cmd(ctx, cli.profile, git),
+
+        Command::Ls(ls_type) => match ls_type {
+            ListType::Profiles => fileops::ls_profiles_cmd(ctx),
+            ListType::Secrets => fileops::ls_secrets_cmd(ctx, cli.profile),
+            ListType::Hooks => fileops::ls_hooks_cmd(ctx, cli.profile),
+            ListType::Groups => fileops::ls_groups_cmd(ctx, cli.profile),
+            ListType::Files { group } => fileops::ls_files_cmd(ctx, cli.profile, &group),
+        },
+
+        Command::Profile(action) => match action {
+            ProfileAction::Copy { from, to } => fileops::profile_copy_cmd(ctx, from, to),
+        },
+
+        Command::Push {
+            group,
+            files,
+            assume_yes,
+            move_files,
+            add,
+        } => fileops::push_cmd(ctx, cli.profile, group, &files, assume_yes, move_files, add),
+        Command::Pop {
+            groups,
+            assume_yes,
+            list,
+            trash,
+        } => fileops::pop_cmd(ctx, cli.profile, &groups, assume_yes, list, trash),
+        Command::GroupIs { files } => fileops::groupis_cmd(ctx, cli.profile, &files),
+        Command::Which {
+            group,
+            hooks,
+            secrets,
+        } => {
+            let dtype = match (hooks, secrets) {
+                (true, _) => dotfiles::DotfileType::Hooks,
+                (_, true) => dotfiles::DotfileType::Secrets,
+                _ => dotfiles::DotfileType::Configs,
+            };
+            fileops::which_cmd(ctx, cli.profile, &group, dtype)
+        }
+        Command::Bench { groups, files } => symlinks::bench_cmd(ctx, groups, files),
+        Command::Completions { shell } => {
+            let mut cmd = Cli::command();
+            let bin_name = cmd.get_name().to_string();
+            clap_complete::generate(shell, &mut cmd, bin_name, &mut std::io::stdout());
+            Ok(())
+        }
+    };
+
+    match exit_code {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => e,
+    }
+}
@@ -5,9 +5,15 @@ use owo_colors::OwoColorize;
 use crate::dotfiles;
 use crate::fileops;
 use rust_i18n::t;
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::env;
 use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::SystemTime;
 use std::{
+    fs,
     path::{self, Component},
     process,
 };
@@ -27,6 +33,15 @@ pub const VALID_TARGETS: &[&str] = &[
     // default target_family values
     "_unix",
     "_windows",
+    // common target_arch values
+    "_x86",
+    "_x86_64",
+    "_arm",
+    "_aarch64",
+    "_riscv64",
+    "_powerpc64",
+    "_s390x",
+    "_wasm32",
 ];
 
 // Exit codes
@@ -41,6 +56,8 @@ pub enum ReturnCode {
     EncryptionFailed = 5,
     /// Failed to decrypt referenced file
     DecryptionFailed = 6,
+    /// A group's target couldn't be expanded
+    InvalidTarget = 7,
 }
 
 impl From<ReturnCode> for process::ExitCode {
@@ -49,17 +66,19 @@ impl From<ReturnCode> for process::ExitCode {
     }
 }
 
-pub fn get_dotfile_profile_from_path<T: AsRef<path::Path>>(file: T) -> Option<String> {
+pub fn get_dotfile_profile_from_path<T: AsRef<path::Path>>(
+    ctx: &Context,
+    file: T,
+) -> Option<String> {
     let file = file.as_ref();
 
-    let dotfiles_path = {
-        let home_path = dirs::home_dir().unwrap();
-        let configs_path = dirs::config_dir().unwrap();
+    let (dotfiles_path, dotfiles_path_is_home) = {
+        let (home_path, configs_path) = ctx.dotfiles_roots();
 
         if file.starts_with(&configs_path) {
-            configs_path
+            (configs_path, false)
         } else if file.starts_with(&home_path) {
-            home_path
+            (home_path, true)
         } else {
             return None;
         }
@@ -76,6 +95,13 @@ pub fn get_dotfile_profile_from_path<T: AsRef<path::Path>>(file: T) -> Option<St
 
     let (dirname, profile_name) = dotfiles_dirname.split_once('_')?;
 
+    // the dotfiles directories inside of $HOME are hidden
+    let dirname = if dotfiles_path_is_home {
+        dirname.strip_prefix('.')?
+    } else {
+        dirname
+    };
+
     if dirname != "dotfiles" {
         return None;
     }
@@ -83,21 +109,99 @@ pub fn get_dotfile_profile_from_path<T: AsRef<path::Path>>(file: T) -> Option<St
     Some(profile_name.into())
 }
 
-#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+/// Errors that come up while turning paths into dotfiles
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DotfileError {
+    /// The dotfiles directory couldn't be found, holds the message explaining how to create it
+    DotfilesDirNotFound(String),
+    /// The path isn't inside of Configs, Hooks or Secrets
+    NotUnderDotfiles(PathBuf),
+    /// The path is inside of the dotfiles but no group could be extracted from it
+    NoGroup(PathBuf),
+    /// The dotfile was expected to be a directory
+    NotADir(PathBuf),
+    /// A target path references an environment variable that is not set
+    MissingEnvVar { var: String, path: String },
+    /// A target path starts with `~user`, which isn't supported
+    OtherUserHome(String),
+    /// The home directory couldn't be determined
+    NoHomeDir,
+}
+
+impl std::fmt::Display for DotfileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DotfileError::DotfilesDirNotFound(msg) => write!(f, "{msg}"),
+            DotfileError::NotUnderDotfiles(_) => write!(f, "path does not belong to dotfiles."),
+            DotfileError::NoGroup(_) => {
+                write!(
+                    f,
+                    "{}",
+                    t!("errors.failed_to_get_group_relative_to_dotfiles_dir")
+                )
+            }
+            DotfileError::NotADir(path) => {
+                write!(f, "{}", t!("errors.not_a_dir", directory = path.display()))
+            }
+            DotfileError::MissingEnvVar { var, path } => {
+                write!(f, "{}", t!("errors.unset_variable", var = var, path = path))
+            }
+            DotfileError::OtherUserHome(path) => {
+                write!(f, "{}", t!("errors.other_user_home", path = path))
+            }
+            DotfileError::NoHomeDir => write!(f, "{}", t!("errors.no_home_dir")),
+        }
+    }
+}
+
+impl std::error::Error for DotfileError {}
+
+#[derive(Clone, Debug)]
 pub struct Dotfile {
     pub path: path::PathBuf,
     pub group_path: path::PathBuf,
     pub group_name: String,
+    pub(crate) ctx: Rc<Context>,
+    /// The target set in the group's group.toml, walked dotfiles share the one loaded for their group.
+    /// It's looked up when needed otherwise
+    group_target: Option<Rc<Option<PathBuf>>>,
 }
 
-impl TryFrom<path::PathBuf> for Dotfile {
-    type Error = String;
+// the group is derived from the path, so it's all that tells dotfiles apart
+impl PartialEq for Dotfile {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+    }
+}
+
+impl Eq for Dotfile {}
+
+impl std::hash::Hash for Dotfile {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.path.hash(state);
+    }
+}
+
+impl Dotfile {
+    /// Returns the dotfile at `value`, an error is returned if it's not inside of a group
+    pub fn new(ctx: &Context, value: path::PathBuf) -> Result<Self, DotfileError> {
+        Self::with_shared_context(Rc::new(ctx.clone()), value)
+    }
 
-    fn try_from(value: path::PathBuf) -> Result<Self, Self::Error> {
+    /// Same as `new` but shares the context with the dotfiles it was already given to
+    pub(crate) fn with_shared_context(
+        ctx: Rc<Context>,
+        value: path::PathBuf,
+    ) -> Result<Self, DotfileError> {
         /// returns the path for the group the file belongs to.
         /// an error is returned if the file does not belong to dotfiles
-        pub fn to_group_path(file_path: &path::PathBuf) -> Result<path::PathBuf, String> {
-            let dotfiles_dir = get_dotfiles_path(get_dotfile_profile_from_path(file_path))?;
+        fn to_group_path(
+            ctx: &Context,
+            file_path: &path::PathBuf,
+        ) -> Result<path::PathBuf, DotfileError> {
+            let dotfiles_dir =
+                get_dotfiles_path(ctx, get_dotfile_profile_from_path(ctx, file_path))
+                    .map_err(DotfileError::DotfilesDirNotFound)?;
             let configs_dir = dotfiles_dir.join("Configs");
             let hooks_dir = dotfiles_dir.join("Hooks");
             let secrets_dir = dotfiles_dir.join("Secrets");
@@ -109,7 +213,7 @@ impl TryFrom<path::PathBuf> for Dotfile {
             } else if file_path.starts_with(&secrets_dir) {
                 secrets_dir
             } else {
-                return Err("path does not belong to dotfiles.".into());
+                return Err(DotfileError::NotUnderDotfiles(file_path.clone()));
             };
 
             let group = if *file_path == dotfile_root_dir {
@@ -122,9 +226,7 @@ impl TryFrom<path::PathBuf> for Dotfile {
                     .next()
                     .unwrap()
                 else {
-                    return Err(
-                        t!("errors.failed_to_get_group_relative_to_dotfiles_dir").into_owned()
-                    );
+                    return Err(DotfileError::NoGroup(file_path.clone()));
                 };
 
                 dotfile_root_dir.join(group_relpath)
@@ -133,21 +235,608 @@ impl TryFrom<path::PathBuf> for Dotfile {
             Ok(group)
         }
 
-        let group_path = to_group_path(&value)?;
+        let group_path = to_group_path(&ctx, &value)?;
 
         Ok(Dotfile {
             group_name: group_path.file_name().unwrap().to_str().unwrap().into(),
             path: value,
             group_path,
+            ctx,
+            group_target: None,
+        })
+    }
+}
+
+/// Repository wide settings stored in dotfiles/tuckr.toml
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Manifest {
+    pub groups: HashMap<String, GroupManifest>,
+}
+
+/// Settings for a single group, stored under `[groups.<group>]` in tuckr.toml
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct GroupManifest {
+    /// Directories the group is deployed to instead of $HOME.
+    /// See `expand_target_path` for how they're expanded
+    pub targets: Vec<String>,
+    /// `targets` after being expanded, targets that failed to expand are left out
+    #[serde(skip)]
+    pub target_dirs: Vec<PathBuf>,
+}
+
+/// User settings stored in `tuckr/config.toml` inside the config directory
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Profile used when none is passed with `--profile`
+    pub profile: Option<String>,
+    /// Custom targets enabled in addition to the ones in the dotfiles' `targets` file
+    pub custom_targets: Vec<String>,
+}
+
+impl Config {
+    /// Returns the user's config, a missing config file is the same as an empty one
+    pub fn load() -> Config {
+        let Some(config_path) = dirs::config_dir().map(|dir| dir.join("tuckr").join("config.toml"))
+        else {
+            return Config::default();
+        };
+
+        match fs::read_to_string(&config_path) {
+            Ok(content) => toml::from_str(&content).unwrap_or_else(|err| {
+                eprintln!(
+                    "{}",
+                    t!(
+                        "errors.invalid_manifest",
+                        file = config_path.display(),
+                        err = err.to_string().trim_end()
+                    )
+                    .red()
+                );
+                Config::default()
+            }),
+            Err(_) => Config::default(),
+        }
+    }
+}
+
+/// Name of the optional file at the root of a group in Configs that holds the group's settings,
+/// it is never deployed
+pub const GROUP_CONFIG_FILE: &str = "group.toml";
+
+/// Settings for a single group, stored in `Configs/<group>/group.toml`
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct GroupConfig {
+    /// What the group is for, shown by `status`
+    pub description: Option<String>,
+    /// Groups that are added along with this one
+    pub depends: Vec<String>,
+    /// Options used for the group when they aren't passed on the command line
+    pub defaults: GroupDefaults,
+    /// Directory the group is deployed to instead of $HOME.
+    /// See `expand_target_path` for how it's expanded
+    pub target: Option<String>,
+}
+
+/// Default deploy options for a group
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct GroupDefaults {
+    /// Same as passing `--force`
+    pub force: bool,
+    /// Same as passing `--create-dirs`, only files are symlinked
+    pub only_files: bool,
+}
+
+impl GroupConfig {
+    /// Returns the settings of the group, a missing group.toml is the same as an empty one
+    pub fn load(dotfiles_dir: &path::Path, group: &str) -> GroupConfig {
+        let config_path = dotfiles_dir
+            .join("Configs")
+            .join(group)
+            .join(GROUP_CONFIG_FILE);
+
+        match fs::read_to_string(&config_path) {
+            Ok(content) => toml::from_str(&content).unwrap_or_else(|err| {
+                eprintln!(
+                    "{}",
+                    t!(
+                        "errors.invalid_manifest",
+                        file = config_path.display(),
+                        err = err.to_string().trim_end()
+                    )
+                    .red()
+                );
+                GroupConfig::default()
+            }),
+            Err(_) => GroupConfig::default(),
+        }
+    }
+
+    /// Returns the expanded `target` of the group, if it has one.
+    ///
+    /// Targets are cached and only loaded again if the group.toml has been modified
+    pub fn target_dir(ctx: &Context, dotfiles_dir: &path::Path, group: &str) -> Option<PathBuf> {
+        // relative targets depend on the target directory so it's part of the key
+        type TargetCache = HashMap<(PathBuf, PathBuf), (Option<SystemTime>, Option<PathBuf>)>;
+
+        thread_local! {
+            static CACHE: RefCell<TargetCache> = RefCell::new(HashMap::new());
+        }
+
+        let config_path = dotfiles_dir
+            .join("Configs")
+            .join(group)
+            .join(GROUP_CONFIG_FILE);
+        let modified = fs::metadata(&config_path).and_then(|m| m.modified()).ok();
+        let key = (config_path, ctx.target_dir());
+
+        CACHE.with_borrow_mut(|cache| {
+            if let Some((cached_modified, target)) = cache.get(&key) {
+                if *cached_modified == modified {
+                    return target.clone();
+                }
+            }
+
+            let target = modified.and_then(|_| {
+                let target = GroupConfig::load(dotfiles_dir, group).target?;
+                expand_target_path(ctx, &target)
+                    .inspect_err(|err| eprintln!("{}", err.to_string().red()))
+                    .ok()
+            });
+
+            cache.insert(key, (modified, target.clone()));
+            target
+        })
+    }
+}
+
+/// Returns the groups along with every group they depend on, as listed in their group.toml
+///
+/// Dependencies come before the groups that need them and `*` is returned unchanged since it
+/// already includes every group.
+/// An error is returned if a dependency doesn't exist or if groups depend on each other
+pub fn resolve_group_dependencies(
+    ctx: &Context,
+    profile: Option<String>,
+    groups: &[String],
+) -> Result<Vec<String>, String> {
+    let groups = expand_group_patterns(ctx, profile.clone(), DotfileType::Configs, groups);
+    if groups.iter().any(|group| group == "*") {
+        return Ok(groups);
+    }
+
+    let Ok(dotfiles_dir) = get_dotfiles_path(ctx, profile) else {
+        return Ok(groups);
+    };
+
+    // a dependency can also be only made of conditional groups, eg: `fonts_linux`
+    let existing_groups: Vec<String> = fs::read_dir(dotfiles_dir.join("Configs"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    let group_exists = |group: &str| {
+        existing_groups
+            .iter()
+            .any(|existing| existing == group || group_without_target(existing) == group)
+    };
+
+    // depth first search where `stack` holds the groups that are still being resolved
+    fn visit(
+        group: &str,
+        dotfiles_dir: &path::Path,
+        group_exists: &dyn Fn(&str) -> bool,
+        stack: &mut Vec<String>,
+        resolved: &mut Vec<String>,
+    ) -> Result<(), String> {
+        if resolved.iter().any(|g| g == group) {
+            return Ok(());
+        }
+
+        if let Some(start) = stack.iter().position(|g| g == group) {
+            let mut cycle = stack[start..].to_vec();
+            cycle.push(group.into());
+            return Err(t!("errors.dependency_cycle", cycle = cycle.join(" -> ")).into_owned());
+        }
+
+        stack.push(group.into());
+        for dependency in GroupConfig::load(dotfiles_dir, group).depends {
+            if !group_exists(&dependency) {
+                return Err(t!(
+                    "errors.missing_dependency",
+                    group = group,
+                    dependency = dependency
+                )
+                .into_owned());
+            }
+
+            visit(&dependency, dotfiles_dir, group_exists, stack, resolved)?;
+        }
+        stack.pop();
+
+        resolved.push(group.into());
+        Ok(())
+    }
+
+    let mut resolved = Vec::new();
+    for group in &groups {
+        visit(
+            group,
+            &dotfiles_dir,
+            &group_exists,
+            &mut Vec::new(),
+            &mut resolved,
+        )?;
+    }
+
+    Ok(resolved)
+}
+
+/// Returns the profile to use, in order of precedence:
+/// the `--profile` flag, the `TUCKR_PROFILE` environment variable and then the config file
+pub fn resolve_profile(cli_profile: Option<String>, config: &Config) -> Option<String> {
+    cli_profile
+        .or_else(|| env::var("TUCKR_PROFILE").ok())
+        .or_else(|| config.profile.clone())
+        .filter(|profile| !profile.is_empty())
+}
+
+/// Expands a target path from tuckr.toml
+///
+/// The expansions are applied in the following order:
+/// 1. a leading `~` is replaced by $HOME, `~user` is rejected
+/// 2. `$VAR`, `${VAR}`, `${VAR:-default}` and `%VAR%` are replaced by the value of the environment variable
+/// 3. paths that are still relative are made relative to the target directory, $HOME by default
+///
+/// An error is returned if a referenced environment variable is not set and has no default.
+pub fn expand_target_path(ctx: &Context, target: &str) -> Result<PathBuf, DotfileError> {
+    expand_target_path_with(ctx, target, |var| env::var(var).ok())
+}
+
+/// Same as `expand_target_path` but the variables are looked up with `lookup_var`
+fn expand_target_path_with(
+    ctx: &Context,
+    target: &str,
+    lookup_var: impl Fn(&str) -> Option<String>,
+) -> Result<PathBuf, DotfileError> {
+    let (root, rest) = match target.strip_prefix('~') {
+        Some(rest) => {
+            let (user, rest) = rest.split_at(rest.find(['/', '\\']).unwrap_or(rest.len()));
+            if !user.is_empty() {
+                return Err(DotfileError::OtherUserHome(target.into()));
+            }
+            let home_dir = dirs::home_dir().ok_or(DotfileError::NoHomeDir)?;
+            (Some(home_dir), rest.trim_start_matches(['/', '\\']))
+        }
+        None => (None, target),
+    };
+
+    let unset_var = |var: &str| DotfileError::MissingEnvVar {
+        var: var.into(),
+        path: target.into(),
+    };
+
+    let mut expanded = String::new();
+    let mut chars = rest.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '$' if rest[i + 1..].starts_with('{') => {
+                let Some(end) = rest[i..].find('}') else {
+                    return Err(unset_var(&rest[i..]));
+                };
+                let var = &rest[i + 2..i + end];
+                let (var, default) = match var.split_once(":-") {
+                    Some((var, default)) => (var, Some(default)),
+                    None => (var, None),
+                };
+
+                match (lookup_var(var), default) {
+                    (Some(value), _) if !value.is_empty() => expanded.push_str(&value),
+                    (_, Some(default)) => expanded.push_str(default),
+                    _ => return Err(unset_var(var)),
+                }
+
+                while chars.next_if(|(j, _)| *j <= i + end).is_some() {}
+            }
+
+            '$' => {
+                let var_len = rest[i + 1..]
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len() - i - 1);
+                if var_len == 0 {
+                    expanded.push(c);
+                    continue;
+                }
+
+                let var = &rest[i + 1..i + 1 + var_len];
+                match lookup_var(var) {
+                    Some(value) => expanded.push_str(&value),
+                    None => return Err(unset_var(var)),
+                }
+
+                while chars.next_if(|(j, _)| *j <= i + var_len).is_some() {}
+            }
+
+            '%' => {
+                let Some(end) = rest[i + 1..].find('%') else {
+                    expanded.push(c);
+                    continue;
+                };
+                let var = &rest[i + 1..i + 1 + end];
+                match lookup_var(var) {
+                    Some(value) => expanded.push_str(&value),
+                    None => return Err(unset_var(var)),
+                }
+
+                while chars.next_if(|(j, _)| *j <= i + 1 + end).is_some() {}
+            }
+
+            c => expanded.push(c),
+        }
+    }
+
+    Ok(match root {
+        Some(root) => root.join(expanded),
+        None => ctx.target_dir().join(expanded),
+    })
+}
+
+impl Manifest {
+    /// Returns the manifest for the dotfiles directory.
+    /// A missing manifest is the same as an empty one.
+    ///
+    /// Manifests are cached and only parsed again if the file has been modified
+    pub fn load(ctx: &Context, dotfiles_dir: &path::Path) -> Rc<Manifest> {
+        // relative targets depend on the target directory so it's part of the key
+        type ManifestCache = HashMap<(PathBuf, PathBuf), (Option<SystemTime>, Rc<Manifest>)>;
+
+        thread_local! {
+            static CACHE: RefCell<ManifestCache> = RefCell::new(HashMap::new());
+        }
+
+        let manifest_path = dotfiles_dir.join("tuckr.toml");
+        let modified = fs::metadata(&manifest_path).and_then(|m| m.modified()).ok();
+        let key = (manifest_path.clone(), ctx.target_dir());
+
+        CACHE.with_borrow_mut(|cache| {
+            if let Some((cached_modified, manifest)) = cache.get(&key) {
+                if *cached_modified == modified {
+                    return manifest.clone();
+                }
+            }
+
+            let mut manifest: Manifest = match fs::read_to_string(&manifest_path) {
+                Ok(content) => toml::from_str(&content).unwrap_or_else(|err| {
+                    eprintln!(
+                        "{}",
+                        t!(
+                            "errors.invalid_manifest",
+                            file = manifest_path.display(),
+                            err = err.to_string().trim_end()
+                        )
+                        .red()
+                    );
+                    Manifest::default()
+                }),
+                Err(_) => Manifest::default(),
+            };
+
+            for group in manifest.groups.values_mut() {
+                group.target_dirs = group
+                    .targets
+                    .iter()
+                    .filter_map(|target| {
+                        expand_target_path(ctx, target)
+                            .inspect_err(|err| eprintln!("{}", err.to_string().red()))
+                            .ok()
+                    })
+                    .collect();
+            }
+
+            let manifest = Rc::new(manifest);
+            cache.insert(key, (modified, manifest.clone()));
+            manifest
         })
     }
 }
 
+/// Checks whether any of the group's targets in its group.toml or in tuckr.toml couldn't be expanded.
+/// The reason is reported when the targets are loaded
+pub fn group_has_invalid_target(ctx: &Context, dotfiles_dir: &path::Path, group: &str) -> bool {
+    let invalid_group_target = GroupConfig::target_dir(ctx, dotfiles_dir, group).is_none()
+        && GroupConfig::load(dotfiles_dir, group).target.is_some();
+
+    invalid_group_target
+        || Manifest::load(ctx, dotfiles_dir)
+            .groups
+            .get(group)
+            .is_some_and(|group| group.target_dirs.len() != group.targets.len())
+}
+
+/// Replaces references to renamed groups in tuckr.toml and in the `depends` of every group.toml
+///
+/// The previous contents of every file that is written are pushed to `originals`
+/// so that they can be restored if the rename has to be undone
+pub fn rename_group_references(
+    dotfiles_dir: &path::Path,
+    renames: &[(String, String)],
+    originals: &mut Vec<(PathBuf, String)>,
+) -> std::io::Result<()> {
+    let renamed = |group: &str| {
+        renames
+            .iter()
+            .find(|(from, _)| from == group)
+            .map(|(_, to)| to.clone())
+    };
+
+    let group_configs = fs::read_dir(dotfiles_dir.join("Configs"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|group| group.path().join(GROUP_CONFIG_FILE));
+
+    for path in std::iter::once(dotfiles_dir.join("tuckr.toml")).chain(group_configs) {
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        // invalid files are already reported whenever they're loaded
+        let Ok(mut doc) = content.parse::<toml_edit::DocumentMut>() else {
+            continue;
+        };
+
+        let mut changed = false;
+        if let Some(groups) = doc.get_mut("groups").and_then(|g| g.as_table_like_mut()) {
+            let old_groups: Vec<_> = groups.iter().map(|(group, _)| group.to_string()).collect();
+            for group in old_groups {
+                if let Some(new_group) = renamed(&group) {
+                    let settings = groups.remove(&group).unwrap();
+                    groups.insert(&new_group, settings);
+                    changed = true;
+                }
+            }
+        }
+
+        if let Some(depends) = doc.get_mut("depends").and_then(|d| d.as_array_mut()) {
+            for dependency in depends.iter_mut() {
+                if let Some(new_group) = dependency.as_str().and_then(renamed) {
+                    let decor = dependency.decor().clone();
+                    *dependency = new_group.into();
+                    *dependency.decor_mut() = decor;
+                    changed = true;
+                }
+            }
+        }
+
+        if changed {
+            originals.push((path.clone(), content));
+            fs::write(&path, doc.to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Prefix for conditional groups that target a specific machine, eg: `alacritty_@laptop`
+pub const HOSTNAME_TARGET_PREFIX: &str = "_@";
+
+/// Prefix for conditional groups that target a custom target, eg: `git_#work`
+pub const CUSTOM_TARGET_PREFIX: &str = "_#";
+
+/// Returns what comes after `prefix` if the group is a conditional group using it
+fn group_target_with_prefix<'a>(group: &'a str, prefix: &str) -> Option<&'a str> {
+    match group.rsplit_once(prefix) {
+        Some((base_group, target)) if !base_group.is_empty() && !target.is_empty() => Some(target),
+        _ => None,
+    }
+}
+
+/// Returns the hostname the group targets if it's a host specific group
+fn group_target_hostname(group: &str) -> Option<&str> {
+    group_target_with_prefix(group, HOSTNAME_TARGET_PREFIX)
+}
+
+/// Returns the custom target the group targets if it has one
+fn group_custom_target(group: &str) -> Option<&str> {
+    group_target_with_prefix(group, CUSTOM_TARGET_PREFIX)
+}
+
+/// Returns the lowercased hostname of the current machine
+fn current_hostname() -> &'static str {
+    static HOSTNAME: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+    HOSTNAME.get_or_init(|| gethostname::gethostname().to_string_lossy().to_lowercase())
+}
+
+/// How much is printed besides errors and what commands were asked to show
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Debug)]
+pub enum Verbosity {
+    /// Only errors and the output of the command are printed
+    Quiet,
+    #[default]
+    Normal,
+    /// Every file that is deployed or removed is also printed
+    Verbose,
+}
+
+/// Options that change where the dotfiles are looked for and how they're deployed,
+/// the defaults are the same as running tuckr without any flags
+#[derive(Clone, Default, Debug)]
+pub struct Context {
+    /// Directory the dotfiles are deployed to instead of $HOME
+    pub target_dir: Option<PathBuf>,
+    /// Directory that is searched for the dotfiles directory instead of $HOME and the config directory
+    pub dotfiles_home: Option<PathBuf>,
+    /// Walk into the symlinked directories inside of groups instead of deploying them as a whole
+    pub follow_symlinks: bool,
+    /// Treat junk files like `.DS_Store` or `Thumbs.db` as any other file
+    pub no_ignore: bool,
+    pub verbosity: Verbosity,
+    /// Custom targets that are enabled, groups for any other custom target are ignored
+    pub custom_targets: Vec<String>,
+    /// XDG base directories keyed by their variable name, used instead of the environment's
+    pub xdg_base_dirs: Option<HashMap<String, PathBuf>>,
+}
+
+impl Context {
+    /// Returns the directory the dotfiles are deployed to, $HOME unless it was overridden
+    pub fn target_dir(&self) -> PathBuf {
+        self.target_dir
+            .clone()
+            .unwrap_or_else(|| dirs::home_dir().unwrap_or_default())
+    }
+
+    /// Returns the directories the dotfiles directories live in, $HOME and the config directory.
+    ///
+    /// Both are the same directory when it was overridden
+    pub fn dotfiles_roots(&self) -> (PathBuf, PathBuf) {
+        match &self.dotfiles_home {
+            Some(dir) => (dir.clone(), dir.clone()),
+            None => (
+                dirs::home_dir().unwrap_or_default(),
+                dirs::config_dir().unwrap_or_default(),
+            ),
+        }
+    }
+}
+
+/// Returns the custom targets listed in the `targets` file at the root of the dotfiles directory.
+///
+/// Targets are listed one per line, empty lines and lines starting with `#` are ignored
+pub fn load_custom_targets(dotfiles_dir: &path::Path) -> Vec<String> {
+    let Ok(content) = fs::read_to_string(dotfiles_dir.join("targets")) else {
+        return Vec::new();
+    };
+
+    let mut targets: Vec<String> = Vec::new();
+    for line in content.lines().map(str::trim) {
+        if !line.is_empty() && !line.starts_with('#') && !targets.iter().any(|t| t == line) {
+            targets.push(line.into());
+        }
+    }
+
+    targets
+}
+
 pub fn group_ends_with_target_name(group: &str) -> bool {
-    VALID_TARGETS.iter().any(|target| group.ends_with(target))
+    group_target_hostname(group).is_some()
+        || group_custom_target(group).is_some()
+        || VALID_TARGETS.iter().any(|target| group.ends_with(target))
 }
 
 pub fn group_without_target(group: &str) -> &str {
+    for (prefix, target) in [
+        (HOSTNAME_TARGET_PREFIX, group_target_hostname(group)),
+        (CUSTOM_TARGET_PREFIX, group_custom_target(group)),
+    ] {
+        if let Some(target) = target {
+            return &group[..group.len() - target.len() - prefix.len()];
+        }
+    }
+
     for target in VALID_TARGETS {
         if let Some(base_group) = group.strip_suffix(target) {
             return base_group;
@@ -157,85 +846,322 @@ pub fn group_without_target(group: &str) -> &str {
     group
 }
 
+/// Returns how specific the group's target is
+///
+/// When conditional groups of the same base group deploy a file to the same target,
+/// the one with the highest priority is deployed:
+/// base group < target_family < target_os and target_arch < custom target < hostname
+pub fn group_priority(group: &str) -> u8 {
+    if group_target_hostname(group).is_some() {
+        4
+    } else if group_custom_target(group).is_some() {
+        3
+    } else if group.ends_with("_unix") {
+        1
+    } else if group_ends_with_target_name(group) {
+        2
+    } else {
+        0
+    }
+}
+
+/// Returns true if the group or the base group of a conditional group is in the exclude list
+///
+/// eg: excluding `nvim` also excludes `nvim_linux`, but excluding `nvim_linux` does not exclude `nvim`
+pub fn group_is_excluded(group: &str, exclude: &[String]) -> bool {
+    let base_group = group_without_target(group);
+    exclude
+        .iter()
+        .any(|excluded| excluded == group || excluded == base_group)
+}
+
+/// Restricts the groups to the ones in the include list, which can have glob patterns.
+///
+/// The patterns in both lists are expanded into the groups they match first.
+/// `*` is replaced by the include list itself and any other group is only kept if it or its base group is included.
+/// An empty include list keeps every group
+pub fn include_groups(
+    ctx: &Context,
+    profile: Option<String>,
+    dtype: DotfileType,
+    groups: &[String],
+    include: &[String],
+) -> Vec<String> {
+    if include.is_empty() {
+        return groups.to_vec();
+    }
+
+    let include = expand_group_patterns(ctx, profile.clone(), dtype, include);
+    if groups.iter().any(|group| group == "*") {
+        return include;
+    }
+
+    expand_group_patterns(ctx, profile, dtype, groups)
+        .into_iter()
+        .filter(|group| {
+            include
+                .iter()
+                .any(|included| included == group || included == group_without_target(group))
+        })
+        .collect()
+}
+
 /// Returns true if a group with specified name can be used by current platform.
 /// Checks if a group should be linked on current platform. For unconditional
 /// groups, this function returns true; for conditional groups, this function
-/// returns true when group suffix matches current target_os or target_family.
-pub fn group_is_valid_target(group: &str) -> bool {
+/// returns true when group suffix matches current target_os, target_family, hostname
+/// or one of the enabled custom targets.
+pub fn group_is_valid_target(ctx: &Context, group: &str) -> bool {
+    if let Some(hostname) = group_target_hostname(group) {
+        return hostname.to_lowercase() == current_hostname();
+    }
+
+    if let Some(custom_target) = group_custom_target(group) {
+        return ctx.custom_targets.iter().any(|t| t == custom_target);
+    }
+
     // Gets the current OS and OS family
     let current_target_os = format!("_{}", env::consts::OS);
     let current_target_family = format!("_{}", env::consts::FAMILY);
+    let current_target_arch = format!("_{}", env::consts::ARCH);
 
     // returns true if a group has no suffix or its suffix matches the current OS
     if group_ends_with_target_name(group) {
-        group.ends_with(&current_target_os) || group.ends_with(&current_target_family)
+        group.ends_with(&current_target_os)
+            || group.ends_with(&current_target_family)
+            || group.ends_with(&current_target_arch)
     } else {
         true
     }
 }
 
+/// What is currently found at a dotfile's target path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TargetKind {
+    /// Nothing exists at the target path
+    Absent,
+    /// A regular file that is not managed by tuckr
+    PlainFile,
+    /// A regular directory, files inside it might still be symlinked
+    PlainDir,
+    /// A symlink pointing to the dotfile
+    SymlinkToUs,
+    /// A copy or a hardlink of the dotfile with the same contents
+    CopyOfUs,
+    /// A symlink pointing somewhere other than the dotfile
+    SymlinkElsewhere(path::PathBuf),
+    /// A symlink whose destination does not exist
+    BrokenSymlink,
+}
+
+impl TargetKind {
+    /// Returns true if something other than a broken symlink is found at the target path
+    pub fn exists(&self) -> bool {
+        !matches!(self, TargetKind::Absent | TargetKind::BrokenSymlink)
+    }
+}
+
 impl Dotfile {
     /// Returns true if the target can be used by the current platform
     pub fn is_valid_target(&self) -> bool {
-        group_is_valid_target(self.group_name.as_str())
+        group_is_valid_target(&self.ctx, self.group_name.as_str())
     }
 
     /// Checks whether the current groups is targetting the root path aka `/`
     pub fn targets_root(&self) -> bool {
-        let root_dir = get_dotfiles_path(get_dotfile_profile_from_path(&self.group_path))
-            .unwrap()
-            .join("Configs")
-            .join("Root");
-        self.group_path.starts_with(root_dir)
+        self.group_path.ends_with("Root")
+            && self
+                .group_path
+                .parent()
+                .is_some_and(|dir| dir.ends_with("Configs"))
+    }
+
+    /// Returns the dotfile's path relative to its group
+    pub fn group_relative_path(&self) -> &path::Path {
+        self.path
+            .strip_prefix(&self.group_path)
+            .unwrap_or(path::Path::new(&self.group_name))
     }
 
     /// Converts a path string from dotfiles/Configs to where they should be
-    /// deployed on $HOME
+    /// deployed on $HOME, or inside the group's target if its group.toml sets one
     pub fn to_target_path(&self) -> path::PathBuf {
-        // uses join("") so that the path appends / or \ depending on platform
-        let dotfiles_configs_path = get_dotfiles_path(get_dotfile_profile_from_path(&self.path))
-            .unwrap()
-            .join("Configs")
-            .join("");
+        let group_path = self.group_relative_path();
 
-        let group_path = {
-            let dotfiles_configs_path = dotfiles_configs_path.to_str().unwrap();
-
-            let dotfile_path = self.path.to_str().unwrap();
-            let dotfile_path = dotfile_path.strip_prefix(dotfiles_configs_path).unwrap();
+        if self.targets_root() {
+            return path::PathBuf::from(path::MAIN_SEPARATOR_STR).join(group_path);
+        }
 
-            match dotfile_path.split_once(path::MAIN_SEPARATOR) {
-                Some(path) => path.1,
-                None => dotfile_path,
-            }
+        let group_target = match &self.group_target {
+            Some(target) => target.as_ref().clone(),
+            None => self.load_group_target(),
         };
 
+        match group_target {
+            Some(target) => target.join(group_path),
+            None => home_target_path(&self.ctx, group_path),
+        }
+    }
+
+    fn load_group_target(&self) -> Option<PathBuf> {
+        self.group_path
+            .parent()
+            .and_then(path::Path::parent)
+            .and_then(|dotfiles_dir| {
+                GroupConfig::target_dir(&self.ctx, dotfiles_dir, &self.group_name)
+            })
+    }
+
+    /// Returns every path the dotfile is deployed to.
+    ///
+    /// This is the same as `to_target_path` unless the group has custom targets in tuckr.toml,
+    /// those take precedence over the target in the group's group.toml
+    pub fn to_target_paths(&self) -> Vec<path::PathBuf> {
+        let target_path = self.to_target_path();
         if self.targets_root() {
-            path::PathBuf::from(path::MAIN_SEPARATOR_STR).join(group_path)
+            return vec![target_path];
+        }
+
+        // the group is inside of dotfiles/Configs
+        let Some(dotfiles_dir) = self.group_path.parent().and_then(path::Path::parent) else {
+            return vec![target_path];
+        };
+        let manifest = Manifest::load(&self.ctx, dotfiles_dir);
+        let Some(group) = manifest.groups.get(&self.group_name) else {
+            return vec![target_path];
+        };
+
+        if group.targets.is_empty() {
+            return vec![target_path];
+        }
+
+        let relative_target = self.group_relative_path();
+
+        group
+            .target_dirs
+            .iter()
+            .map(|target_root| target_root.join(relative_target))
+            .collect()
+    }
+
+    /// Checks whether any of the dotfile's targets are outside of the target directory,
+    /// deploying to those usually requires elevated privileges
+    pub fn needs_root_privilege(&self) -> bool {
+        let target_dir = self.ctx.target_dir();
+        self.to_target_paths()
+            .iter()
+            .any(|target| !target.starts_with(&target_dir))
+    }
+
+    /// Checks whether the dotfile is a directory whose files are deployed individually,
+    /// symlinked directories are deployed as a whole unless symlinks are followed
+    pub fn is_walked_dir(&self) -> bool {
+        self.path.is_dir() && (self.ctx.follow_symlinks || !self.path.is_symlink())
+    }
+
+    /// Checks whether the dotfile is itself a symlink that resolves to `target`,
+    /// deploying it there would create a symlink loop
+    pub fn source_loops_to(&self, target: &path::Path) -> bool {
+        if !self.path.is_symlink() {
+            return false;
+        }
+
+        // a deployed target resolves to the same file as the dotfile it points to
+        if fs::read_link(target).is_ok_and(|link| link == self.path) {
+            return false;
+        }
+
+        if let (Ok(source), Ok(target)) = (fs::canonicalize(&self.path), fs::canonicalize(target)) {
+            return source == target;
+        }
+
+        // broken symlinks and loops can only be compared by where they point to
+        match (fs::read_link(&self.path), self.path.parent()) {
+            (Ok(link), Some(parent)) => parent.join(link) == target,
+            _ => false,
+        }
+    }
+
+    /// Checks what is currently found at target, which should be one of the dotfile's target paths
+    pub fn target_exists_kind(&self, target: &path::Path) -> TargetKind {
+        if target.is_symlink() {
+            return match fs::read_link(target) {
+                Ok(link) if link == self.path => TargetKind::SymlinkToUs,
+                Ok(_) if !target.exists() => TargetKind::BrokenSymlink,
+                Ok(link) => TargetKind::SymlinkElsewhere(link),
+                Err(_) => TargetKind::BrokenSymlink,
+            };
+        }
+
+        if target.is_dir() {
+            TargetKind::PlainDir
+        } else if !target.exists() {
+            TargetKind::Absent
+        } else if self.path.is_file()
+            && fileops::DeployRecord::Copies.contains(target, &self.path)
+            && fileops::files_are_equal(&self.path, target)
+        {
+            TargetKind::CopyOfUs
         } else {
-            dirs::home_dir().unwrap().join(group_path)
+            TargetKind::PlainFile
         }
     }
 
     /// Creates an iterator that walks the directory
     /// Returns none if the Dotfile is not a directory, since it would not be walkable
-    pub fn try_iter(&self) -> Result<DotfileIter, String> {
+    pub fn try_iter(&self) -> Result<DotfileIter, DotfileError> {
         if !self.path.is_dir() {
-            Err(t!("errors.not_a_dir", directory = self.path.display()).into_owned())
+            Err(DotfileError::NotADir(self.path.clone()))
         } else {
-            Ok(DotfileIter(fileops::DirWalk::new(self.path.clone())))
+            let walk = fileops::DirWalk::new(self.path.clone())
+                .follow_symlinks(self.ctx.follow_symlinks)
+                .no_ignore(self.ctx.no_ignore);
+            Ok(DotfileIter(walk, self.ctx.clone(), None))
         }
     }
 }
 
-pub struct DotfileIter(fileops::DirWalk);
+/// The last element holds the target of the group being walked so it's only loaded once per group
+pub struct DotfileIter(
+    fileops::DirWalk,
+    Rc<Context>,
+    Option<(PathBuf, Rc<Option<PathBuf>>)>,
+);
 
 impl Iterator for DotfileIter {
     type Item = Dotfile;
 
     fn next(&mut self) -> Option<Self::Item> {
         let curr_file = self.0.next()?;
-        let dotfile = Dotfile::try_from(curr_file).unwrap();
+        let mut dotfile = match Dotfile::with_shared_context(self.1.clone(), curr_file.clone()) {
+            Ok(dotfile) => dotfile,
+            Err(err) => {
+                eprintln!("{}", format!("{}: {err}", curr_file.display()).red());
+                return self.next();
+            }
+        };
+        let group_target = match &self.2 {
+            Some((group_path, group_target)) if *group_path == dotfile.group_path => {
+                group_target.clone()
+            }
+            _ => {
+                let group_target = Rc::new(dotfile.load_group_target());
+                self.2 = Some((dotfile.group_path.clone(), group_target.clone()));
+                group_target
+            }
+        };
+        dotfile.group_target = Some(group_target);
+
+        // the group's settings are not a dotfile
+        if dotfile.path == dotfile.group_path.join(GROUP_CONFIG_FILE)
+            && dotfile
+                .group_path
+                .parent()
+                .is_some_and(|dir| dir.ends_with("Configs"))
+        {
+            return self.next();
+        }
+
         Some(dotfile)
     }
 }
@@ -245,15 +1171,14 @@ impl Iterator for DotfileIter {
 /// When run on a unit test it returns a temporary directory for testing purposes.
 /// this testing directory is unique to the thread it's running on,
 /// so different unit tests cannot interact with the other's dotfiles directory
-pub fn get_dotfiles_path(profile: Option<String>) -> Result<path::PathBuf, String> {
+pub fn get_dotfiles_path(ctx: &Context, profile: Option<String>) -> Result<path::PathBuf, String> {
     let (home_dotfiles, config_dotfiles) = {
         let dotfiles_dir = match profile {
             Some(ref profile) => format!("dotfiles_{profile}"),
             None => "dotfiles".into(),
         };
 
-        let home_dotfiles = dirs::home_dir().unwrap();
-        let config_dotfiles = dirs::config_dir().unwrap();
+        let (home_dotfiles, config_dotfiles) = ctx.dotfiles_roots();
 
         (
             home_dotfiles.join(format!(".{dotfiles_dir}")),
@@ -272,27 +1197,97 @@ pub fn get_dotfiles_path(profile: Option<String>) -> Result<path::PathBuf, Strin
         Ok(config_dotfiles)
     } else if home_dotfiles.exists() {
         Ok(home_dotfiles)
+    } else if let Some(profile) = profile {
+        // a mistyped profile is more likely than a missing dotfiles directory
+        let mut message = t!("errors.profile_x_not_found", profile = profile)
+            .yellow()
+            .to_string();
+
+        let profiles = fileops::get_profiles(ctx);
+        if !profiles.is_empty() {
+            message += &format!(
+                "\n{}: {}",
+                t!("info.x_available", x = "Profiles"),
+                profiles.join(", ")
+            );
+        }
+
+        message += &format!(
+            "\n{}",
+            t!(
+                "errors.make_sure_dir_exists_or_run",
+                dir = config_dotfiles.display(),
+                cmd = format!("tuckr -p {profile} init")
+            )
+        );
+        Err(message)
     } else {
-        let init_cmd = match profile {
-            Some(profile) => format!("tuckr -p {profile} init"),
-            None => "tuckr init".into(),
-        };
         Err(format!(
             "{}\n{}",
             t!("errors.couldnt_find_dotfiles_dir").yellow(),
             t!(
                 "errors.make_sure_dir_exists_or_run",
                 dir = config_dotfiles.display(),
-                cmd = init_cmd
+                cmd = "tuckr init"
             )
         ))
     }
 }
 
-/// removes the $HOME from path
-pub fn get_target_basepath(target: &path::Path) -> Option<PathBuf> {
-    let home_dir = dirs::home_dir().unwrap();
-    Some(target.strip_prefix(home_dir).ok()?.into())
+/// XDG base directory variables and where they default to inside $HOME
+const XDG_BASE_DIRS: &[(&str, &str)] = &[
+    ("XDG_CONFIG_HOME", ".config"),
+    ("XDG_DATA_HOME", ".local/share"),
+    ("XDG_STATE_HOME", ".local/state"),
+    ("XDG_CACHE_HOME", ".cache"),
+];
+
+/// Returns the XDG base directories that are set to an absolute path along with their default location
+fn xdg_base_dirs(ctx: &Context) -> impl Iterator<Item = (PathBuf, &'static path::Path)> + '_ {
+    XDG_BASE_DIRS.iter().filter_map(|(var, default)| {
+        let dir = match &ctx.xdg_base_dirs {
+            Some(dirs) => dirs.get(*var)?.clone(),
+            None => PathBuf::from(env::var_os(var)?),
+        };
+        // the spec says relative paths are invalid and should be ignored
+        dir.is_absolute().then(|| (dir, path::Path::new(*default)))
+    })
+}
+
+/// Returns where a path relative to $HOME is deployed.
+///
+/// Paths inside the default location of an XDG base directory, eg: `.config`,
+/// are deployed to the directory its environment variable points to, eg: `$XDG_CONFIG_HOME`,
+/// unless the target directory was overridden, then everything is deployed inside of it
+pub fn home_target_path(ctx: &Context, relative: &path::Path) -> PathBuf {
+    if ctx.target_dir.is_some() {
+        return ctx.target_dir().join(relative);
+    }
+
+    for (dir, default) in xdg_base_dirs(ctx) {
+        if let Ok(rest) = relative.strip_prefix(default) {
+            return dir.join(rest);
+        }
+    }
+
+    ctx.target_dir().join(relative)
+}
+
+/// removes the target directory, $HOME by default, from path
+///
+/// paths inside an XDG base directory are made relative to its default location instead
+pub fn get_target_basepath(ctx: &Context, target: &path::Path) -> Option<PathBuf> {
+    if ctx.target_dir.is_some() {
+        return Some(target.strip_prefix(ctx.target_dir()).ok()?.into());
+    }
+
+    for (dir, default) in xdg_base_dirs(ctx) {
+        if let Ok(rest) = target.strip_prefix(&dir) {
+            return Some(default.join(rest));
+        }
+    }
+
+    Some(target.strip_prefix(ctx.target_dir()).ok()?.into())
 }
 
 #[derive(Copy, Clone)]
@@ -302,24 +1297,97 @@ pub enum DotfileType {
     Hooks,
 }
 
-/// Returns if a config has been setup for <group> on <dtype>
-pub fn dotfile_contains(profile: Option<String>, dtype: DotfileType, group: &str) -> bool {
-    let target_dir = match dtype {
-        DotfileType::Configs => "Configs",
-        DotfileType::Secrets => "Secrets",
-        DotfileType::Hooks => "Hooks",
-    };
+impl DotfileType {
+    /// Returns the name of the directory in dotfiles where this type of dotfile is stored
+    pub fn dir_name(&self) -> &'static str {
+        match self {
+            DotfileType::Configs => "Configs",
+            DotfileType::Secrets => "Secrets",
+            DotfileType::Hooks => "Hooks",
+        }
+    }
+}
 
-    let Ok(dotfiles_dir) = get_dotfiles_path(profile) else {
+/// Returns if a config has been setup for <group> on <dtype>
+pub fn dotfile_contains(
+    ctx: &Context,
+    profile: Option<String>,
+    dtype: DotfileType,
+    group: &str,
+) -> bool {
+    let Ok(dotfiles_dir) = get_dotfiles_path(ctx, profile) else {
         return false;
     };
 
-    let group_src = dotfiles_dir.join(target_dir).join(group);
+    let group_src = dotfiles_dir.join(dtype.dir_name()).join(group);
     group_src.exists()
 }
 
+/// Expands glob patterns (eg: `nvim*` or `shell-?`) into the base groups that match them.
+/// Only groups that are supported on this platform can be matched
+///
+/// `*` and groups that aren't patterns are returned unchanged,
+/// as are patterns that don't match anything so that they are reported as invalid groups
+pub fn expand_group_patterns(
+    ctx: &Context,
+    profile: Option<String>,
+    dtype: DotfileType,
+    groups: &[String],
+) -> Vec<String> {
+    // a group that shows up more than once, even through different patterns, is only kept the first time
+    let dedup = |mut groups: Vec<String>| {
+        let mut seen = std::collections::HashSet::new();
+        groups.retain(|group| seen.insert(group.clone()));
+        groups
+    };
+
+    let is_pattern = |group: &str| group != "*" && group.contains(['*', '?', '[']);
+    if !groups.iter().any(|group| is_pattern(group)) {
+        return dedup(groups.to_vec());
+    }
+
+    let existing_groups: Vec<String> = get_dotfiles_path(ctx, profile)
+        .ok()
+        .and_then(|dir| fs::read_dir(dir.join(dtype.dir_name())).ok())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        // otherwise `zsh_mac*` would expand to `zsh` and deploy `zsh_linux` instead
+        .filter(|group| group_is_valid_target(ctx, group))
+        .collect();
+
+    let mut expanded = Vec::new();
+    for group in groups {
+        let Some(pattern) = is_pattern(group)
+            .then(|| glob::Pattern::new(group).ok())
+            .flatten()
+        else {
+            expanded.push(group.clone());
+            continue;
+        };
+
+        let mut matches: Vec<_> = existing_groups
+            .iter()
+            .filter(|existing| pattern.matches(existing))
+            .map(|existing| group_without_target(existing).to_string())
+            .collect();
+
+        if matches.is_empty() {
+            expanded.push(group.clone());
+        } else {
+            matches.sort();
+            expanded.append(&mut matches);
+        }
+    }
+
+    dedup(expanded)
+}
+
 /// Returns all groups in the slice that don't have a corresponding directory in dotfiles/{Configs,Hooks,Secrets}
 pub fn check_invalid_groups(
+    ctx: &Context,
     profile: Option<String>,
     dtype: DotfileType,
     groups: &[impl AsRef<str>],
@@ -327,7 +1395,7 @@ pub fn check_invalid_groups(
     let mut invalid_groups = Vec::new();
     for group in groups {
         let group = group.as_ref();
-        if !dotfiles::dotfile_contains(profile.clone(), dtype, group) && group != "*" {
+        if !dotfiles::dotfile_contains(ctx, profile.clone(), dtype, group) && group != "*" {
             invalid_groups.push(group.into());
         }
     }
@@ -385,30 +1453,33 @@ pub fn is_valid_groupname(group: impl AsRef<str>) -> Result<(), String> {
 
 #[cfg(test)]
 mod tests {
-    use crate::dotfiles::{get_dotfiles_path, Dotfile};
+    use crate::dotfiles::{get_dotfiles_path, Context, Dotfile};
+    use std::path;
 
     #[test]
     fn dotfile_to_target_path() {
-        let group = get_dotfiles_path(None)
+        let ctx = &Context::default();
+        let group = get_dotfiles_path(ctx, None)
             .unwrap()
             .join("Configs")
             .join("zsh")
             .join(".zshrc");
 
         assert_eq!(
-            Dotfile::try_from(group).unwrap().to_target_path(),
+            Dotfile::new(ctx, group).unwrap().to_target_path(),
             dirs::home_dir().unwrap().join(".zshrc")
         );
     }
 
     #[test]
     fn dotfile_targets_root() {
-        let dotfiles_dir = super::get_dotfiles_path(None).unwrap().join("Configs");
+        let ctx = &Context::default();
+        let dotfiles_dir = super::get_dotfiles_path(ctx, None).unwrap().join("Configs");
 
-        let root_dotfile = super::Dotfile::try_from(dotfiles_dir.join("Root")).unwrap();
+        let root_dotfile = super::Dotfile::new(ctx, dotfiles_dir.join("Root")).unwrap();
         assert!(root_dotfile.targets_root());
 
-        let nonroot_dotfile = super::Dotfile::try_from(dotfiles_dir.join("Zsh")).unwrap();
+        let nonroot_dotfile = super::Dotfile::new(ctx, dotfiles_dir.join("Zsh")).unwrap();
         assert!(!nonroot_dotfile.targets_root());
     }
 
@@ -419,6 +1490,8 @@ mod tests {
                 group_name: name.to_string(),
                 path: Default::default(),
                 group_path: Default::default(),
+                ctx: Default::default(),
+                group_target: None,
             }
         }
 
@@ -431,6 +1504,14 @@ mod tests {
             (new_group("group_unix"), std::env::consts::FAMILY == "unix"),
             (new_group("group_something"), true),
             (new_group("some_random_group"), true),
+            (
+                new_group(&format!(
+                    "group_@{}",
+                    super::current_hostname().to_uppercase()
+                )),
+                true,
+            ),
+            (new_group("group_@not-this-machine"), false),
         ];
 
         for (dotfile, expected) in target_tests {
@@ -438,8 +1519,413 @@ mod tests {
         }
     }
 
+    #[test]
+    fn expand_target_paths() {
+        let ctx = &Context::default();
+        let home_dir = dirs::home_dir().unwrap();
+        // the environment is shared by every test so the variables are looked up from here instead
+        let lookup_var = |var: &str| (var == "TUCKR_TEST_TARGET").then(|| "target".to_string());
+        let try_expand = |target: &str| super::expand_target_path_with(ctx, target, lookup_var);
+
+        let expand = |target: &str| try_expand(target).unwrap();
+        assert_eq!(expand("~"), home_dir);
+        assert_eq!(expand("~/.local/app"), home_dir.join(".local/app"));
+        assert_eq!(
+            try_expand("~other/app"),
+            Err(super::DotfileError::OtherUserHome("~other/app".into()))
+        );
+        assert_eq!(
+            expand("$TUCKR_TEST_TARGET/app"),
+            home_dir.join("target/app")
+        );
+        assert_eq!(
+            expand("/opt/${TUCKR_TEST_TARGET}"),
+            path::PathBuf::from("/opt/target")
+        );
+        assert_eq!(expand("~/%TUCKR_TEST_TARGET%"), home_dir.join("target"));
+        assert_eq!(
+            expand("${TUCKR_TEST_UNSET:-default}/app"),
+            home_dir.join("default/app")
+        );
+        assert_eq!(
+            try_expand("$TUCKR_TEST_UNSET/app"),
+            Err(super::DotfileError::MissingEnvVar {
+                var: "TUCKR_TEST_UNSET".into(),
+                path: "$TUCKR_TEST_UNSET/app".into(),
+            })
+        );
+        assert!(try_expand("%TUCKR_TEST_UNSET%").is_err());
+    }
+
+    #[test]
+    fn detect_valid_arch_targets() {
+        let ctx = &Context::default();
+        let target_tests = [
+            ("bin_x86_64", std::env::consts::ARCH == "x86_64"),
+            ("bin_x86", std::env::consts::ARCH == "x86"),
+            ("bin_aarch64", std::env::consts::ARCH == "aarch64"),
+            ("bin_riscv64", std::env::consts::ARCH == "riscv64"),
+            ("bin", true),
+        ];
+
+        for (group, expected) in target_tests {
+            assert_eq!(super::group_is_valid_target(ctx, group), expected);
+        }
+
+        assert_eq!(super::group_without_target("bin_x86_64"), "bin");
+        assert_eq!(super::group_without_target("bin_aarch64"), "bin");
+    }
+
+    #[test]
+    fn dotfile_to_multiple_target_paths() {
+        let ctx = &Context::default();
+        let dotfiles_dir = get_dotfiles_path(ctx, None).unwrap();
+        std::fs::create_dir_all(&dotfiles_dir).unwrap();
+        std::fs::write(
+            dotfiles_dir.join("tuckr.toml"),
+            "[groups.snippets]\ntargets = [\"~\", \"~/.local/share/app\", \"/opt/app\"]",
+        )
+        .unwrap();
+
+        let home_dir = dirs::home_dir().unwrap();
+        let configs_dir = dotfiles_dir.join("Configs");
+
+        let snippet = Dotfile::new(ctx, configs_dir.join("snippets").join("snippet.txt")).unwrap();
+        assert_eq!(
+            snippet.to_target_paths(),
+            vec![
+                home_dir.join("snippet.txt"),
+                home_dir.join(".local/share/app/snippet.txt"),
+                path::PathBuf::from("/opt/app/snippet.txt"),
+            ]
+        );
+
+        let zshrc = Dotfile::new(ctx, configs_dir.join("zsh").join(".zshrc")).unwrap();
+        assert_eq!(zshrc.to_target_paths(), vec![home_dir.join(".zshrc")]);
+
+        std::fs::remove_dir_all(dotfiles_dir).unwrap();
+    }
+
+    #[test]
+    fn dotfile_to_group_target_path() {
+        let ctx = &Context::default();
+        let dotfiles_dir = get_dotfiles_path(ctx, None).unwrap();
+        let configs_dir = dotfiles_dir.join("Configs");
+        std::fs::create_dir_all(configs_dir.join("app")).unwrap();
+        std::fs::write(
+            configs_dir.join("app").join(super::GROUP_CONFIG_FILE),
+            "target = \"~/.var/app/org.app\"",
+        )
+        .unwrap();
+        std::fs::write(
+            dotfiles_dir.join("tuckr.toml"),
+            "[groups.app]\ntargets = [\"/opt/app\"]",
+        )
+        .unwrap();
+
+        let home_dir = dirs::home_dir().unwrap();
+
+        let config = Dotfile::new(ctx, configs_dir.join("app").join("config")).unwrap();
+        assert_eq!(
+            config.to_target_path(),
+            home_dir.join(".var/app/org.app/config")
+        );
+        // targets in tuckr.toml take precedence
+        assert_eq!(
+            config.to_target_paths(),
+            vec![path::PathBuf::from("/opt/app/config")]
+        );
+
+        let zshrc = Dotfile::new(ctx, configs_dir.join("zsh").join(".zshrc")).unwrap();
+        assert_eq!(zshrc.to_target_path(), home_dir.join(".zshrc"));
+
+        std::fs::remove_dir_all(dotfiles_dir).unwrap();
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn detect_target_kinds() {
+        use super::TargetKind;
+        use std::os::unix::fs::symlink;
+
+        let ctx = &Context::default();
+        let dotfiles_dir = get_dotfiles_path(ctx, None).unwrap();
+        let group_dir = dotfiles_dir.join("Configs").join("kinds");
+        let target_dir = dotfiles_dir.join("target");
+        std::fs::create_dir_all(&group_dir).unwrap();
+        std::fs::create_dir_all(target_dir.join("dir")).unwrap();
+
+        let dotfile = Dotfile::new(ctx, group_dir.join("file")).unwrap();
+        std::fs::write(&dotfile.path, "content").unwrap();
+        std::fs::write(target_dir.join("file"), "").unwrap();
+        symlink(&dotfile.path, target_dir.join("ours")).unwrap();
+        symlink(target_dir.join("file"), target_dir.join("elsewhere")).unwrap();
+        symlink(target_dir.join("missing"), target_dir.join("broken")).unwrap();
+
+        let kind = |name: &str| dotfile.target_exists_kind(&target_dir.join(name));
+        assert_eq!(kind("missing"), TargetKind::Absent);
+        assert_eq!(kind("file"), TargetKind::PlainFile);
+        // copies are only ours if tuckr deployed them, no matter their contents
+        for name in ["copy", "hardlink"] {
+            crate::fileops::DeployRecord::Copies
+                .remove(&target_dir.join(name))
+                .unwrap();
+        }
+        std::fs::copy(&dotfile.path, target_dir.join("copy")).unwrap();
+        assert_eq!(kind("copy"), TargetKind::PlainFile);
+        crate::fileops::DeployRecord::Copies
+            .insert(&target_dir.join("copy"), &dotfile.path)
+            .unwrap();
+        assert_eq!(kind("copy"), TargetKind::CopyOfUs);
+        std::fs::hard_link(&dotfile.path, target_dir.join("hardlink")).unwrap();
+        assert_eq!(kind("hardlink"), TargetKind::PlainFile);
+        crate::fileops::DeployRecord::Copies
+            .insert(&target_dir.join("hardlink"), &dotfile.path)
+            .unwrap();
+        assert_eq!(kind("hardlink"), TargetKind::CopyOfUs);
+        // a copy that has been modified since is no longer ours
+        std::fs::write(target_dir.join("copy"), "modified").unwrap();
+        assert_eq!(kind("copy"), TargetKind::PlainFile);
+        assert_eq!(kind("dir"), TargetKind::PlainDir);
+        assert_eq!(kind("ours"), TargetKind::SymlinkToUs);
+        assert_eq!(
+            kind("elsewhere"),
+            TargetKind::SymlinkElsewhere(target_dir.join("file"))
+        );
+        assert_eq!(kind("broken"), TargetKind::BrokenSymlink);
+
+        std::fs::remove_dir_all(dotfiles_dir).unwrap();
+    }
+
+    #[test]
+    fn strip_group_targets() {
+        assert_eq!(
+            super::group_without_target("alacritty_@laptop"),
+            "alacritty"
+        );
+        assert_eq!(super::group_without_target("alacritty_linux"), "alacritty");
+        assert_eq!(super::group_without_target("alacritty"), "alacritty");
+        assert_eq!(super::group_without_target("_@laptop"), "_@laptop");
+        assert!(super::group_ends_with_target_name("alacritty_@laptop"));
+        assert!(!super::group_ends_with_target_name("alacritty_@"));
+    }
+
+    #[test]
+    fn detect_custom_targets() {
+        assert_eq!(super::group_without_target("app_#work"), "app");
+        assert!(!super::group_is_valid_target(
+            &Context::default(),
+            "app_#work"
+        ));
+
+        let ctx = &Context {
+            custom_targets: vec!["work".into()],
+            ..Default::default()
+        };
+        assert!(super::group_is_valid_target(ctx, "app_#work"));
+        assert!(!super::group_is_valid_target(ctx, "app_#home"));
+        assert!(super::group_is_valid_target(ctx, "app"));
+    }
+
+    #[test]
+    fn conditional_group_priorities() {
+        let priority = super::group_priority;
+
+        assert!(priority("app_@laptop") > priority("app_#work"));
+        assert!(priority("app_#work") > priority("app_linux"));
+        assert_eq!(priority("app_linux"), priority("app_x86_64"));
+        assert!(priority("app_linux") > priority("app_unix"));
+        assert!(priority("app_unix") > priority("app"));
+    }
+
+    #[test]
+    fn expand_glob_groups() {
+        let ctx = &Context::default();
+        let configs_dir = get_dotfiles_path(ctx, None).unwrap().join("Configs");
+        for group in [
+            "nvim",
+            "nvim_linux",
+            "nvim-lua",
+            "shell-a",
+            "shell-bb",
+            "zsh",
+        ] {
+            std::fs::create_dir_all(configs_dir.join(group)).unwrap();
+        }
+
+        let expand = |groups: &[&str]| {
+            let groups: Vec<_> = groups.iter().map(|g| g.to_string()).collect();
+            super::expand_group_patterns(ctx, None, super::DotfileType::Configs, &groups)
+        };
+
+        assert_eq!(expand(&["nvim*"]), ["nvim", "nvim-lua"]);
+        assert_eq!(expand(&["shell-?", "zsh"]), ["shell-a", "zsh"]);
+        assert_eq!(
+            expand(&["zsh", "nvim*", "nvim", "z*"]),
+            ["zsh", "nvim", "nvim-lua"]
+        );
+        assert_eq!(expand(&["*"]), ["*"]);
+        assert_eq!(expand(&["zsh", "nvim", "zsh"]), ["zsh", "nvim"]);
+        assert_eq!(expand(&["fish*"]), ["fish*"]);
+        // conditional groups for other platforms aren't matched
+        let (unsupported, pattern) = if cfg!(target_os = "linux") {
+            ("nvim_macos", "nvim_mac*")
+        } else {
+            ("nvim_linux", "nvim_lin*")
+        };
+        std::fs::create_dir_all(configs_dir.join(unsupported)).unwrap();
+        assert_eq!(expand(&[pattern]), [pattern]);
+
+        std::fs::remove_dir_all(configs_dir.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn group_config() {
+        let ctx = &Context::default();
+        let configs_dir = get_dotfiles_path(ctx, None).unwrap().join("Configs");
+        let desktop_dir = configs_dir.join("desktop");
+        std::fs::create_dir_all(&desktop_dir).unwrap();
+        std::fs::create_dir_all(configs_dir.join("fonts")).unwrap();
+        std::fs::write(desktop_dir.join(".xinitrc"), "").unwrap();
+        std::fs::write(
+            desktop_dir.join(super::GROUP_CONFIG_FILE),
+            "description = \"Window manager\"\ndepends = [\"fonts\"]\n",
+        )
+        .unwrap();
+
+        // group.toml is never deployed
+        let files: Vec<_> = Dotfile::new(ctx, desktop_dir.clone())
+            .unwrap()
+            .try_iter()
+            .unwrap()
+            .map(|f| f.path)
+            .collect();
+        assert_eq!(files, [desktop_dir.join(".xinitrc")]);
+
+        assert_eq!(
+            super::resolve_group_dependencies(ctx, None, &["desktop".into()]).unwrap(),
+            ["fonts", "desktop"]
+        );
+
+        // fonts -> desktop -> fonts
+        std::fs::write(
+            configs_dir.join("fonts").join(super::GROUP_CONFIG_FILE),
+            "depends = [\"desktop\"]\n",
+        )
+        .unwrap();
+        assert!(super::resolve_group_dependencies(ctx, None, &["desktop".into()]).is_err());
+
+        std::fs::write(
+            configs_dir.join("fonts").join(super::GROUP_CONFIG_FILE),
+            "depends = [\"missing\"]\n",
+        )
+        .unwrap();
+        assert!(super::resolve_group_dependencies(ctx, None, &["desktop".into()]).is_err());
+
+        std::fs::remove_dir_all(configs_dir.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn xdg_base_dir_targets() {
+        let home_dir = dirs::home_dir().unwrap();
+        let state_dir = std::env::temp_dir().join("tuckr-xdg-state");
+        let relative = path::Path::new(".local/state/app/file");
+        // the directories are passed in instead of being set in the environment,
+        // which is shared by every test running in parallel
+        let with_state_home = |dir: Option<path::PathBuf>| Context {
+            xdg_base_dirs: Some(
+                dir.map(|dir| ("XDG_STATE_HOME".to_string(), dir))
+                    .into_iter()
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+
+        let ctx = &with_state_home(None);
+        assert_eq!(
+            super::home_target_path(ctx, relative),
+            home_dir.join(relative)
+        );
+
+        let ctx = &with_state_home(Some(state_dir.clone()));
+        let target = super::home_target_path(ctx, relative);
+        assert_eq!(target, state_dir.join("app/file"));
+        assert_eq!(super::get_target_basepath(ctx, &target).unwrap(), relative);
+
+        let ctx = &with_state_home(Some("relative/state".into()));
+        assert_eq!(
+            super::home_target_path(ctx, relative),
+            home_dir.join(relative)
+        );
+    }
+
+    #[test]
+    fn custom_target_dir() {
+        let target_dir = std::env::temp_dir().join("tuckr-target");
+        let relative = path::Path::new(".config/app/file");
+
+        let ctx = &Context {
+            target_dir: Some(target_dir.clone()),
+            ..Default::default()
+        };
+        let target = super::home_target_path(ctx, relative);
+        assert_eq!(target, target_dir.join(relative));
+        assert_eq!(super::get_target_basepath(ctx, &target).unwrap(), relative);
+        assert_eq!(
+            super::expand_target_path(ctx, "app").unwrap(),
+            target_dir.join("app")
+        );
+        assert!(
+            super::get_target_basepath(ctx, &dirs::home_dir().unwrap().join(relative)).is_none()
+        );
+    }
+
+    #[test]
+    fn include_matching_groups() {
+        let ctx = &Context::default();
+        let configs_dir = get_dotfiles_path(ctx, None).unwrap().join("Configs");
+        for group in ["nvim", "nvim_linux", "zsh", "shell-a", "shell-b"] {
+            std::fs::create_dir_all(configs_dir.join(group)).unwrap();
+        }
+
+        let include = ["nvim".to_string(), "shell-*".to_string()];
+        let groups = |groups: &[&str]| groups.iter().map(|g| g.to_string()).collect::<Vec<_>>();
+        let include_groups = |groups: &[String], include: &[String]| {
+            super::include_groups(ctx, None, super::DotfileType::Configs, groups, include)
+        };
+
+        // the include patterns are resolved into the groups they match
+        assert_eq!(
+            include_groups(&groups(&["*"]), &include),
+            groups(&["nvim", "shell-a", "shell-b"])
+        );
+        assert_eq!(
+            include_groups(&groups(&["nvim_linux", "zsh", "shell-a"]), &include),
+            groups(&["nvim_linux", "shell-a"])
+        );
+        assert_eq!(
+            include_groups(&groups(&["shell*", "zsh"]), &groups(&["shell-b"])),
+            groups(&["shell-b"])
+        );
+        assert_eq!(include_groups(&groups(&["zsh"]), &[]), groups(&["zsh"]));
+
+        std::fs::remove_dir_all(configs_dir.parent().unwrap().parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn exclude_conditional_groups() {
+        let exclude = ["nvim".to_string(), "zsh_linux".to_string()];
+
+        assert!(super::group_is_excluded("nvim", &exclude));
+        assert!(super::group_is_excluded("nvim_linux", &exclude));
+        assert!(super::group_is_excluded("zsh_linux", &exclude));
+        assert!(!super::group_is_excluded("zsh", &exclude));
+        assert!(!super::group_is_excluded("nvimrc", &exclude));
+    }
+
     #[test]
     fn get_profile_name_from_dotfile_path() {
+        let ctx = &Context::default();
         let no_profile_dir = dirs::config_dir().unwrap();
         let invalid_dir = dirs::config_dir()
             .unwrap()
@@ -459,14 +1945,26 @@ mod tests {
             .join("test.cfg");
 
         assert_eq!(
-            super::get_dotfile_profile_from_path(work_profile_dir),
+            super::get_dotfile_profile_from_path(ctx, work_profile_dir),
             Some("work".into())
         );
         assert_eq!(
-            super::get_dotfile_profile_from_path(home_profile_dir),
+            super::get_dotfile_profile_from_path(ctx, home_profile_dir),
             Some("my_home".into())
         );
-        assert_eq!(super::get_dotfile_profile_from_path(no_profile_dir), None,);
-        assert_eq!(super::get_dotfile_profile_from_path(invalid_dir), None,);
+        assert_eq!(
+            super::get_dotfile_profile_from_path(ctx, no_profile_dir),
+            None,
+        );
+        assert_eq!(super::get_dotfile_profile_from_path(ctx, invalid_dir), None,);
+        assert_eq!(
+            super::get_dotfile_profile_from_path(
+                ctx,
+                dirs::home_dir()
+                    .unwrap()
+                    .join(".dotfiles_laptop/Configs/Vim")
+            ),
+            Some("laptop".into())
+        );
     }
 }
@@ -2,14 +2,17 @@
 //!
 //! Contains functions to create the base directories and to convert users from stow to tuckr
 
-use crate::dotfiles::{self, ReturnCode};
+use crate::dotfiles::{self, Context, ReturnCode};
 use crate::fileops;
+use crate::symlinks;
 use owo_colors::OwoColorize;
 use rust_i18n::t;
-use std::collections::HashSet;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use std::process::ExitCode;
+use std::time::SystemTime;
 use std::{fs, path};
 use tabled::object::Segment;
 use tabled::{Alignment, Modify, Table, Tabled};
@@ -101,23 +104,58 @@ fn is_ignored_file(file: impl AsRef<Path>) -> bool {
     false
 }
 
+/// Walks through every file inside of a directory recursively
+///
+/// Junk files are skipped unless `--no-ignore` was passed and directories that can't be read
+/// are skipped with a warning instead of stopping the walk.
+/// Symlinked directories are returned without walking into them unless `follow_symlinks` is set
 pub struct DirWalk {
     queue: Vec<path::PathBuf>,
+    follow_symlinks: bool,
+    no_ignore: bool,
 }
 
 impl DirWalk {
     pub fn new(dir_path: impl AsRef<Path>) -> Self {
-        let dir_path = dir_path.as_ref();
-        let dir = match fs::read_dir(dir_path) {
-            Ok(f) => f,
-            Err(_) => panic!(
+        let mut queue = Vec::new();
+        Self::push_dir_entries(&mut queue, dir_path.as_ref());
+        Self {
+            queue,
+            follow_symlinks: false,
+            no_ignore: false,
+        }
+    }
+
+    /// Walks into symlinked directories as well, which can lead outside of the walked directory
+    pub fn follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    /// Returns junk files like `.DS_Store` or `Thumbs.db` as well
+    pub fn no_ignore(mut self, no_ignore: bool) -> Self {
+        self.no_ignore = no_ignore;
+        self
+    }
+
+    fn push_dir_entries(queue: &mut Vec<path::PathBuf>, dir: &Path) {
+        let warn = |err: std::io::Error| {
+            eprintln!(
                 "{}",
-                t!("errors.x_doesnt_exist", x = dir_path.to_str().unwrap())
-            ),
+                t!("warn.couldnt_read_dir", dir = dir.display(), err = err).yellow()
+            );
         };
 
-        Self {
-            queue: dir.map(|f| f.unwrap().path()).collect(),
+        match fs::read_dir(dir) {
+            Ok(entries) => {
+                for entry in entries {
+                    match entry {
+                        Ok(entry) => queue.push(entry.path()),
+                        Err(err) => warn(err),
+                    }
+                }
+            }
+            Err(err) => warn(err),
         }
     }
 }
@@ -128,25 +166,289 @@ impl Iterator for DirWalk {
     fn next(&mut self) -> Option<Self::Item> {
         let curr_file = self.queue.pop()?;
 
-        if is_ignored_file(&curr_file) {
+        if !self.no_ignore && is_ignored_file(&curr_file) {
             return self.next();
         }
 
-        if curr_file.is_dir() {
-            for file in fs::read_dir(&curr_file).unwrap() {
-                let file = file.unwrap();
-                self.queue.push(file.path());
-            }
+        if curr_file.is_dir() && (self.follow_symlinks || !curr_file.is_symlink()) {
+            Self::push_dir_entries(&mut self.queue, &curr_file);
         }
 
         Some(curr_file)
     }
 }
 
-/// Converts a stow directory into a tuckr directory
-pub fn from_stow_cmd(profile: Option<String>, assume_yes: bool) -> Result<(), ExitCode> {
+/// Returns true if both paths lead to the same file, either through symlinks or hardlinks
+pub fn is_same_file(a: &Path, b: &Path) -> bool {
+    #[cfg(target_family = "unix")]
+    if let (Ok(a_meta), Ok(b_meta)) = (fs::metadata(a), fs::metadata(b)) {
+        use std::os::unix::fs::MetadataExt;
+        return a_meta.dev() == b_meta.dev() && a_meta.ino() == b_meta.ino();
+    }
+
+    matches!((fs::canonicalize(a), fs::canonicalize(b)), (Ok(a), Ok(b)) if a == b)
+}
+
+/// Returns true if both paths are the same file (eg: hardlinks) or have the same contents
+pub fn files_are_equal(a: &Path, b: &Path) -> bool {
+    let (Ok(a_meta), Ok(b_meta)) = (fs::metadata(a), fs::metadata(b)) else {
+        return false;
+    };
+
+    if is_same_file(a, b) {
+        return true;
+    }
+
+    if a_meta.len() != b_meta.len() {
+        return false;
+    }
+
+    matches!((fs::read(a), fs::read(b)), (Ok(a), Ok(b)) if a == b)
+}
+
+/// Prints a unified diff of the changes needed to go from `old` to `new`
+///
+/// Binary files are only reported as being different
+pub fn print_file_diff(old: &Path, new: &Path) {
+    let read_text = |file: &Path| -> Option<String> {
+        let content = fs::read(file).ok()?;
+        if content.contains(&0) {
+            return None;
+        }
+        String::from_utf8(content).ok()
+    };
+
+    if old.is_dir() || new.is_dir() {
+        println!(
+            "{}",
+            t!("info.x_and_y_differ", x = old.display(), y = new.display()).yellow()
+        );
+        return;
+    }
+
+    let (Some(old_content), Some(new_content)) = (read_text(old), read_text(new)) else {
+        if !files_are_equal(old, new) {
+            println!(
+                "{}",
+                t!(
+                    "info.binary_x_and_y_differ",
+                    x = old.display(),
+                    y = new.display()
+                )
+                .yellow()
+            );
+        }
+        return;
+    };
+
+    let diff = similar::TextDiff::from_lines(&old_content, &new_content);
+    let diff = diff
+        .unified_diff()
+        .header(&old.display().to_string(), &new.display().to_string())
+        .to_string();
+
+    for line in diff.lines() {
+        if line.starts_with("+++") || line.starts_with("---") {
+            println!("{}", line.bold());
+        } else if line.starts_with('+') {
+            println!("{}", line.green());
+        } else if line.starts_with('-') {
+            println!("{}", line.red());
+        } else if line.starts_with("@@") {
+            println!("{}", line.cyan());
+        } else {
+            println!("{line}");
+        }
+    }
+}
+
+/// Copies a file or a whole directory tree from src to dest
+pub fn copy_recursively(src: &Path, dest: &Path) -> io::Result<()> {
+    if !src.is_dir() {
+        fs::copy(src, dest)?;
+        return Ok(());
+    }
+
+    fs::create_dir_all(dest)?;
+    for f in DirWalk::new(src).follow_symlinks(true) {
+        let dest_file = dest.join(f.strip_prefix(src).unwrap());
+
+        if f.is_dir() {
+            fs::create_dir_all(dest_file)?;
+        } else {
+            if let Some(parent) = dest_file.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(f, dest_file)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Copies a directory tree from src to dest, recreating symlinks instead of following them
+fn copy_tree(src: &Path, dest: &Path) -> io::Result<()> {
+    fs::create_dir_all(dest)?;
+
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let dest_file = dest.join(entry.file_name());
+        let file_type = entry.file_type()?;
+
+        if file_type.is_symlink() {
+            symlinks::symlink(&fs::read_link(entry.path())?, &dest_file)?;
+        } else if file_type.is_dir() {
+            copy_tree(&entry.path(), &dest_file)?;
+        } else {
+            fs::copy(entry.path(), dest_file)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Targets deployed by tuckr, recorded so that tuckr only ever removes what it deployed itself
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum DeployRecord {
+    /// Copies and hardlinks of dotfiles
+    Copies,
+    /// Symlinks to dotfiles
+    Symlinks,
+}
+
+type RecordedTargets = std::rc::Rc<HashMap<PathBuf, PathBuf>>;
+
+thread_local! {
+    static DEPLOY_RECORDS: RefCell<HashMap<DeployRecord, (Option<SystemTime>, RecordedTargets)>> =
+        RefCell::new(HashMap::new());
+}
+
+impl DeployRecord {
+    /// Returns the file the record is stored in, inside the data directory
+    fn path(self) -> PathBuf {
+        let file_name = match self {
+            DeployRecord::Copies => "deployed_copies.json",
+            DeployRecord::Symlinks => "deployed_symlinks.json",
+        };
+
+        if cfg!(test) {
+            return std::env::temp_dir()
+                .join(format!("tuckr-{}", std::thread::current().name().unwrap()))
+                .join(file_name);
+        }
+
+        dirs::data_local_dir()
+            .unwrap_or_else(|| {
+                dirs::home_dir()
+                    .unwrap_or_default()
+                    .join(".local")
+                    .join("share")
+            })
+            .join("tuckr")
+            .join(file_name)
+    }
+
+    /// Returns the recorded targets along with the dotfile each of them was deployed from.
+    ///
+    /// Records are cached and only read again if their file has been modified
+    pub fn targets(self) -> RecordedTargets {
+        let path = self.path();
+        let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+
+        DEPLOY_RECORDS.with_borrow_mut(|cache| {
+            if let Some((cached_modified, targets)) = cache.get(&self) {
+                if *cached_modified == modified {
+                    return targets.clone();
+                }
+            }
+
+            let targets: HashMap<PathBuf, PathBuf> = fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| serde_json::from_str(&content).ok())
+                .unwrap_or_default();
+            let targets = std::rc::Rc::new(targets);
+            cache.insert(self, (modified, targets.clone()));
+            targets
+        })
+    }
+
+    /// Checks whether tuckr deployed `dotfile` to `target`
+    pub fn contains(self, target: &Path, dotfile: &Path) -> bool {
+        self.targets()
+            .get(target)
+            .is_some_and(|source| source == dotfile)
+    }
+
+    /// Records that `dotfile` was deployed to `target`
+    pub fn insert(self, target: &Path, dotfile: &Path) -> io::Result<()> {
+        let mut targets = (*self.targets()).clone();
+        targets.insert(target.to_path_buf(), dotfile.to_path_buf());
+        self.write(&targets)
+    }
+
+    /// Stops keeping track of a target that has been removed
+    pub fn remove(self, target: &Path) -> io::Result<()> {
+        let mut targets = (*self.targets()).clone();
+        if targets.remove(target).is_none() {
+            return Ok(());
+        }
+        self.write(&targets)
+    }
+
+    fn write(self, targets: &HashMap<PathBuf, PathBuf>) -> io::Result<()> {
+        let path = self.path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // written to a temporary file first so that the record can't be left half written
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_string(targets)?)?;
+        fs::rename(tmp_path, path)?;
+
+        // the modification time might not change when written twice in a row
+        DEPLOY_RECORDS.with_borrow_mut(|cache| cache.remove(&self));
+        Ok(())
+    }
+}
+
+/// Returns the directory that `--trash` moves removed files into
+pub fn trash_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| {
+            dirs::home_dir()
+                .unwrap_or_default()
+                .join(".local")
+                .join("share")
+        })
+        .join("tuckr")
+        .join("trash")
+}
+
+/// Moves a file or directory into the trash directory while keeping its path relative to $HOME
+///
+/// Returns where it was moved to
+pub fn move_to_trash(ctx: &dotfiles::Context, path: &Path) -> io::Result<PathBuf> {
+    symlinks::backup_target(ctx, path, &trash_dir())
+}
+
+/// Converts a GNU Stow repo into a tuckr dotfiles directory
+///
+/// dry_run: only prints which files would be moved
+pub(crate) fn from_stow_cmd(
+    ctx: &Context,
+    profile: Option<String>,
+    assume_yes: bool,
+    single_group: Option<String>,
+    dry_run: bool,
+) -> Result<(), ExitCode> {
+    if let Some(Err(err)) = single_group.as_ref().map(dotfiles::is_valid_groupname) {
+        eprintln!("{}", err.red());
+        return Err(ExitCode::FAILURE);
+    }
+
     // assume that from_stow is always run from a no profile dotfiles dir
-    let dotfiles_dir = match dotfiles::get_dotfiles_path(profile) {
+    let dotfiles_dir = match dotfiles::get_dotfiles_path(ctx, profile) {
         Ok(path) => path,
         Err(e) => {
             eprintln!("{e}");
@@ -155,58 +457,243 @@ pub fn from_stow_cmd(profile: Option<String>, assume_yes: bool) -> Result<(), Ex
     };
 
     // --- Getting user confirmation ---
-    println!(
-        "{}",
-        t!(
-            "info.dotfiles_will_be_converted",
-            location = dotfiles_dir.display()
-        )
-        .yellow()
-    );
-    print!("{}", t!("warn.want_to_convert_dotfiles"));
-    io::stdout().flush().unwrap();
-
-    if !assume_yes {
-        let mut answer = String::new();
-        io::stdin().read_line(&mut answer).unwrap();
-        if !matches!(answer.trim().to_lowercase().as_str(), "yes" | "y") {
-            return Ok(());
+    if !dry_run {
+        println!(
+            "{}",
+            t!(
+                "info.dotfiles_will_be_converted",
+                location = dotfiles_dir.display()
+            )
+            .yellow()
+        );
+        if !assume_yes {
+            print!("{}", t!("warn.want_to_convert_dotfiles"));
+            io::stdout().flush().unwrap();
+
+            let mut answer = String::new();
+            io::stdin().read_line(&mut answer).unwrap();
+            if !matches!(answer.trim().to_lowercase().as_str(), "yes" | "y") {
+                return Ok(());
+            }
         }
     }
 
-    // --- initializing required directory ---
     let configs_path = dotfiles_dir.join("Configs");
-    fs::create_dir_all(&configs_path).expect(&t!("errors.couldnt_create_required_dir"));
+    let group_path = match &single_group {
+        Some(group) => configs_path.join(group),
+        None => configs_path.clone(),
+    };
 
-    // --- Moving dotfiles to Configs/ ---
-    let cwd = fs::read_dir(&dotfiles_dir).expect(&t!("errors.couldnt_open_curr_dir"));
+    // --- Finding the dotfiles that are moved to Configs/ ---
+    let cwd = match fs::read_dir(&dotfiles_dir) {
+        Ok(cwd) => cwd,
+        Err(err) => {
+            eprintln!(
+                "{}",
+                format!("{}: {err}", t!("errors.couldnt_open_curr_dir")).red()
+            );
+            return Err(ReturnCode::NoSuchFileOrDir.into());
+        }
+    };
 
-    for file in cwd {
-        let dir = file.unwrap();
-        if !dir.metadata().unwrap().is_dir() {
+    let mut moves = Vec::new();
+    // files whose destination is already taken, these are left where they are
+    let mut collisions = Vec::new();
+
+    for entry in cwd.flatten() {
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        if matches!(name.as_str(), "Configs" | "Hooks" | "Secrets") {
             continue;
         }
 
-        let dirname = dir.file_name().to_str().unwrap().to_owned();
-        if dirname.starts_with('.') {
-            continue;
+        if single_group.is_some() {
+            // the repo itself is the package, so its dotfiles are moved as well,
+            // only stow's and git's own files and the repo's metadata stay behind
+            let upper_name = name.to_uppercase();
+            if name.starts_with(".git")
+                || name.starts_with(".stow")
+                || upper_name.starts_with("README")
+                || upper_name.starts_with("LICENSE")
+            {
+                continue;
+            }
+        } else {
+            // every top-level directory is a stow package and becomes a group
+            if !entry.path().is_dir() || name.starts_with('.') {
+                continue;
+            }
+        }
+
+        let dest = group_path.join(&name);
+        if dest.exists() || dest.is_symlink() {
+            collisions.push((entry.path(), dest));
+        } else {
+            moves.push((entry.path(), dest));
+        }
+    }
+    moves.sort();
+    collisions.sort();
+
+    let print_collisions = || {
+        if collisions.is_empty() {
+            return;
+        }
+
+        eprintln!("{}:", t!("warn.from_stow_collisions").yellow());
+        for (from, to) in &collisions {
+            eprintln!("\t{} -> {}", from.display(), to.display());
+        }
+    };
+
+    if dry_run {
+        if !group_path.exists() {
+            println!("{}", t!("info.would_create_x", x = group_path.display()));
+        }
+
+        for (from, to) in &moves {
+            println!(
+                "{}",
+                t!(
+                    "info.would_move_x_to_y",
+                    x = from.display(),
+                    y = to.display()
+                )
+            );
         }
 
-        let path = configs_path.join(&dirname);
+        print_collisions();
+        return Ok(());
+    }
+
+    // --- initializing required directory ---
+    if let Err(err) = fs::create_dir_all(&group_path) {
+        eprintln!(
+            "{}",
+            format!(
+                "{} {}: {err}",
+                t!("errors.couldnt_create_required_dir"),
+                group_path.display()
+            )
+            .red()
+        );
+        return Err(ReturnCode::NoSetupFolder.into());
+    }
+
+    // --- Moving dotfiles to Configs/ ---
+    let mut failed = false;
+    for (from, to) in moves {
+        if let Err(err) = fs::rename(&from, to) {
+            eprintln!(
+                "{}",
+                format!(
+                    "{} {}: {err}",
+                    t!("errors.couldnt_move_files"),
+                    from.display()
+                )
+                .red()
+            );
+            failed = true;
+        }
+    }
+
+    print_collisions();
+
+    if failed {
+        return Err(ExitCode::FAILURE);
+    }
+
+    Ok(())
+}
+
+/// Returns where a new dotfiles directory for `profile` is created
+fn new_dotfiles_path(ctx: &Context, profile: Option<String>) -> PathBuf {
+    if cfg!(test) {
+        dotfiles::get_dotfiles_path(ctx, None).unwrap()
+    } else {
+        let dotfiles_dir_name = match profile {
+            Some(profile) => "dotfiles_".to_string() + profile.as_str(),
+            None => "dotfiles".to_string(),
+        };
+        ctx.dotfiles_roots().1.join(dotfiles_dir_name)
+    }
+}
+
+/// Files that are kept out of a dotfiles git repository created by `init --git`
+const STARTER_GITIGNORE: &str = "\
+# secrets are only ever committed encrypted
+*.tuckr-tmp
+
+# OS junk
+.DS_Store
+.AppleDouble
+.Trashes
+Thumbs.db
+desktop.ini
+
+# editor leftovers
+*.swp
+*~
+";
+
+/// Runs git with `args` inside `dir`
+fn git(dir: &Path, args: &[&str]) -> io::Result<bool> {
+    std::process::Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .status()
+        .map(|status| status.success())
+}
+
+/// Turns the dotfiles directory into a git repository with a starter .gitignore
+fn init_git_repo(dotfiles_dir: &Path) -> Result<(), ExitCode> {
+    let already_a_repo = dotfiles_dir.join(".git").exists();
 
-        if !dirname.ends_with("Configs")
-            && !dirname.ends_with("Hooks")
-            && !dirname.ends_with("Secrets")
-        {
-            fs::rename(dir.path(), path).expect(&t!("errors.couldnt_move_files"));
+    match git(dotfiles_dir, &["init", "-q"]) {
+        Ok(true) => (),
+        Ok(false) => {
+            eprintln!("{}", t!("errors.git_failed", cmd = "init").red());
+            return Err(ExitCode::FAILURE);
         }
+        Err(_) => {
+            println!("{}", t!("warn.git_not_found").yellow());
+            return Ok(());
+        }
+    }
+
+    let gitignore = dotfiles_dir.join(".gitignore");
+    if !gitignore.exists() {
+        if let Err(e) = fs::write(&gitignore, STARTER_GITIGNORE) {
+            eprintln!("{}", e.red());
+            return Err(ExitCode::FAILURE);
+        }
+    }
+
+    // an existing repo already has its own history
+    if already_a_repo {
+        return Ok(());
+    }
+
+    let committed = git(dotfiles_dir, &["add", ".gitignore"]).is_ok_and(|ok| ok)
+        && git(dotfiles_dir, &["commit", "-q", "-m", "Initial commit"]).is_ok_and(|ok| ok);
+
+    // the repository is still usable without the initial commit,
+    // e.g. when git has no user configured yet
+    if !committed {
+        eprintln!("{}", t!("errors.git_failed", cmd = "commit").red());
     }
 
     Ok(())
 }
 
 /// Creates the necessary files and folders for a tuckr directory if they don't exist
-pub fn init_cmd(profile: Option<String>) -> Result<(), ExitCode> {
+pub(crate) fn init_cmd(
+    ctx: &Context,
+    profile: Option<String>,
+    init_git: bool,
+) -> Result<(), ExitCode> {
     macro_rules! create_dirs {
         ($($dirname: expr),+) => {
             $(
@@ -217,15 +704,7 @@ pub fn init_cmd(profile: Option<String>) -> Result<(), ExitCode> {
         };
     }
 
-    let dotfiles_dir = if cfg!(test) {
-        dotfiles::get_dotfiles_path(None).unwrap()
-    } else {
-        let dotfiles_dir_name = match profile {
-            Some(profile) => "dotfiles_".to_string() + profile.as_str(),
-            None => "dotfiles".to_string(),
-        };
-        dirs::config_dir().unwrap().join(dotfiles_dir_name)
-    };
+    let dotfiles_dir = new_dotfiles_path(ctx, profile);
 
     create_dirs!(
         dotfiles_dir.join("Configs"),
@@ -233,6 +712,10 @@ pub fn init_cmd(profile: Option<String>) -> Result<(), ExitCode> {
         dotfiles_dir.join("Secrets")
     );
 
+    if init_git {
+        init_git_repo(&dotfiles_dir)?;
+    }
+
     println!(
         "{}",
         t!(
@@ -245,20 +728,332 @@ pub fn init_cmd(profile: Option<String>) -> Result<(), ExitCode> {
     Ok(())
 }
 
-pub fn push_cmd(
+/// Clones a remote dotfiles repository into the profile's dotfiles directory
+pub(crate) fn clone_cmd(
+    ctx: &Context,
+    profile: Option<String>,
+    url: &str,
+    force: bool,
+) -> Result<(), ExitCode> {
+    let dotfiles_dir = dotfiles_destination(ctx, profile.clone());
+
+    if dotfiles_dir.exists() && !force {
+        eprintln!(
+            "{}",
+            t!("errors.x_already_exists", x = dotfiles_dir.display()).red()
+        );
+        return Err(ExitCode::FAILURE);
+    }
+
+    replace_dotfiles_dir(&dotfiles_dir, |staging_dir| {
+        let cloned = std::process::Command::new("git")
+            .arg("clone")
+            .arg(url)
+            .arg(staging_dir)
+            .status();
+
+        match cloned {
+            Ok(status) if status.success() => Ok(()),
+            Ok(_) => {
+                eprintln!("{}", t!("errors.git_failed", cmd = "clone").red());
+                Err(ExitCode::FAILURE)
+            }
+            Err(e) => {
+                eprintln!("{}", e.red());
+                Err(ExitCode::FAILURE)
+            }
+        }
+    })?;
+
+    print_deploy_hint(ctx, profile);
+
+    Ok(())
+}
+
+/// Returns the profile's dotfiles directory if it already exists, wherever it is,
+/// otherwise returns where a new one is created
+fn dotfiles_destination(ctx: &Context, profile: Option<String>) -> PathBuf {
+    dotfiles::get_dotfiles_path(ctx, profile.clone())
+        .unwrap_or_else(|_| new_dotfiles_path(ctx, profile))
+}
+
+/// Fills a staging directory next to the dotfiles directory with `fill` and only replaces
+/// the dotfiles directory with it once it's been filled with a tuckr repo.
+///
+/// The existing dotfiles directory is left untouched if anything goes wrong
+fn replace_dotfiles_dir(
+    dotfiles_dir: &Path,
+    fill: impl FnOnce(&Path) -> Result<(), ExitCode>,
+) -> Result<(), ExitCode> {
+    let report_err = |e: io::Error| {
+        eprintln!("{}", e.red());
+        ExitCode::FAILURE
+    };
+
+    let dir_name = dotfiles_dir.file_name().unwrap().to_string_lossy();
+    // staged next to the dotfiles so that they're on the same filesystem and can be renamed
+    let staging_dir = dotfiles_dir.with_file_name(format!(".{dir_name}.tuckr-new"));
+    let old_dir = dotfiles_dir.with_file_name(format!(".{dir_name}.tuckr-old"));
+
+    if let Some(parent) = dotfiles_dir.parent() {
+        fs::create_dir_all(parent).map_err(report_err)?;
+    }
+    if staging_dir.exists() {
+        fs::remove_dir_all(&staging_dir).map_err(report_err)?;
+    }
+
+    let filled = fill(&staging_dir).and_then(|()| {
+        if staging_dir.join("Configs").is_dir() {
+            Ok(())
+        } else {
+            eprintln!(
+                "{}",
+                t!("errors.not_a_tuckr_repo", dir = staging_dir.display()).red()
+            );
+            Err(ReturnCode::NoSetupFolder.into())
+        }
+    });
+    if let Err(err) = filled {
+        _ = fs::remove_dir_all(&staging_dir);
+        return Err(err);
+    }
+
+    if !dotfiles_dir.exists() {
+        return fs::rename(&staging_dir, dotfiles_dir).map_err(report_err);
+    }
+
+    if old_dir.exists() {
+        fs::remove_dir_all(&old_dir).map_err(report_err)?;
+    }
+    fs::rename(dotfiles_dir, &old_dir).map_err(report_err)?;
+    if let Err(e) = fs::rename(&staging_dir, dotfiles_dir) {
+        _ = fs::rename(&old_dir, dotfiles_dir);
+        return Err(report_err(e));
+    }
+
+    fs::remove_dir_all(&old_dir).map_err(report_err)
+}
+
+/// Tells the user how to deploy a dotfiles directory they just got
+fn print_deploy_hint(ctx: &Context, profile: Option<String>) {
+    let set_cmd = match profile {
+        Some(profile) => format!("tuckr -p {profile} set \\*"),
+        None => "tuckr set \\*".into(),
+    };
+    if ctx.verbosity != dotfiles::Verbosity::Quiet {
+        println!("{}", t!("info.run_x_to_deploy", cmd = set_cmd).green());
+    }
+}
+
+/// Directories of the dotfiles directory that are archived by `export`
+const EXPORTED_DIRS: [&str; 3] = ["Configs", "Hooks", "Secrets"];
+/// Files at the root of the dotfiles directory that are archived along with them
+const EXPORTED_FILES: [&str; 2] = ["tuckr.toml", "targets"];
+
+/// Archives the dotfiles directory into a gzipped tarball at `out`
+///
+/// dry_run: only prints the files that would be archived
+pub(crate) fn export_cmd(
+    ctx: &Context,
+    profile: Option<String>,
+    out: &Path,
+    dry_run: bool,
+) -> Result<(), ExitCode> {
+    let dotfiles_dir = match dotfiles::get_dotfiles_path(ctx, profile) {
+        Ok(dir) => dir,
+        Err(e) => {
+            eprintln!("{e}");
+            return Err(ReturnCode::CouldntFindDotfiles.into());
+        }
+    };
+
+    let mut builder = if dry_run {
+        None
+    } else {
+        let archive = match fs::File::create(out) {
+            Ok(archive) => archive,
+            Err(e) => {
+                eprintln!("{}", format!("{}: {e}", out.display()).red());
+                return Err(ExitCode::FAILURE);
+            }
+        };
+
+        let encoder = flate2::write::GzEncoder::new(archive, flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        // symlinks inside of groups are kept as they are
+        builder.follow_symlinks(false);
+        Some(builder)
+    };
+
+    let files = EXPORTED_DIRS
+        .iter()
+        .map(|dir| dotfiles_dir.join(dir))
+        .filter(|dir| dir.is_dir())
+        .flat_map(|dir| std::iter::once(dir.clone()).chain(DirWalk::new(dir)))
+        .chain(
+            EXPORTED_FILES
+                .iter()
+                .map(|file| dotfiles_dir.join(file))
+                .filter(|file| file.is_file()),
+        );
+
+    // symlinked directories are archived as symlinks since DirWalk doesn't walk into them
+    for file in files {
+        let name = file.strip_prefix(&dotfiles_dir).unwrap();
+        let Some(builder) = builder.as_mut() else {
+            println!("{}", name.display());
+            continue;
+        };
+
+        if let Err(e) = builder.append_path_with_name(&file, name) {
+            eprintln!("{}", format!("{}: {e}", file.display()).red());
+            return Err(ExitCode::FAILURE);
+        }
+    }
+
+    if let Some(builder) = builder {
+        if let Err(e) = builder.into_inner().and_then(|encoder| encoder.finish()) {
+            eprintln!("{}", format!("{}: {e}", out.display()).red());
+            return Err(ExitCode::FAILURE);
+        }
+    }
+
+    Ok(())
+}
+
+/// Extracts an archive made by `export_cmd` into a new dotfiles directory
+///
+/// force: replaces the dotfiles directory if it already exists
+pub(crate) fn import_cmd(
+    ctx: &Context,
+    profile: Option<String>,
+    archive: &Path,
+    force: bool,
+) -> Result<(), ExitCode> {
+    let open_archive = || -> io::Result<tar::Archive<flate2::read::GzDecoder<fs::File>>> {
+        let file = fs::File::open(archive)?;
+        Ok(tar::Archive::new(flate2::read::GzDecoder::new(file)))
+    };
+
+    let report_err = |e: io::Error| {
+        eprintln!("{}", format!("{}: {e}", archive.display()).red());
+        ExitCode::FAILURE
+    };
+
+    // nothing is touched unless the archive actually holds dotfiles
+    let has_configs = open_archive()
+        .and_then(|mut archive| {
+            for entry in archive.entries()? {
+                let entry = entry?;
+                if entry.path()?.components().next()
+                    == Some(path::Component::Normal("Configs".as_ref()))
+                {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        })
+        .map_err(report_err)?;
+
+    if !has_configs {
+        eprintln!(
+            "{}",
+            t!("errors.not_a_tuckr_repo", dir = archive.display()).red()
+        );
+        return Err(ReturnCode::NoSetupFolder.into());
+    }
+
+    let dotfiles_dir = dotfiles_destination(ctx, profile.clone());
+    if dotfiles_dir.exists() && !force {
+        eprintln!(
+            "{}",
+            t!("errors.x_already_exists", x = dotfiles_dir.display()).red()
+        );
+        return Err(ExitCode::FAILURE);
+    }
+
+    // unpack refuses to write anything outside of the staging directory
+    replace_dotfiles_dir(&dotfiles_dir, |staging_dir| {
+        open_archive()
+            .and_then(|mut archive| archive.unpack(staging_dir))
+            .map_err(report_err)
+    })?;
+
+    println!(
+        "{}",
+        t!(
+            "info.dotfiles_created_at",
+            location = dotfiles_dir.display()
+        )
+        .green()
+    );
+    print_deploy_hint(ctx, profile);
+
+    Ok(())
+}
+
+/// Moves `from` to `to`, falling back to copying and removing the original
+/// when they're on different filesystems
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    fs::copy(from, to)?;
+    fs::remove_file(from)
+}
+
+/// Returns true if every file inside of `dir` is one of `files`
+fn dir_only_contains(dir: &Path, files: &HashSet<PathBuf>) -> bool {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return false;
+    };
+
+    entries.into_iter().all(|entry| {
+        entry.is_ok_and(|entry| {
+            let path = entry.path();
+            if path.is_dir() {
+                dir_only_contains(&path, files)
+            } else {
+                files.contains(&path)
+            }
+        })
+    })
+}
+
+pub(crate) fn push_cmd(
+    ctx: &Context,
     profile: Option<String>,
     group: String,
     files: &[String],
     assume_yes: bool,
+    move_files: bool,
+    add: bool,
 ) -> Result<(), ExitCode> {
-    let dotfiles_dir = match dotfiles::get_dotfiles_path(profile) {
-        Ok(dir) => dir.join("Configs").join(group),
+    let dotfiles_dir = match dotfiles::get_dotfiles_path(ctx, profile.clone()) {
+        Ok(dir) => dir.join("Configs").join(&group),
         Err(e) => {
             eprintln!("{e}");
             return Err(ReturnCode::CouldntFindDotfiles.into());
         }
     };
 
+    let transfer = |from: &Path, to: &Path| {
+        // copying a file onto itself would truncate it
+        if is_same_file(from, to) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} is {}", from.display(), to.display()),
+            ));
+        }
+
+        if move_files {
+            move_file(from, to)
+        } else {
+            fs::copy(from, to).map(|_| ())
+        }
+    };
+
     let mut any_file_failed = false;
     for file in files {
         let file = PathBuf::from(file);
@@ -269,7 +1064,21 @@ pub fn push_cmd(
         }
 
         let file = path::absolute(file).unwrap();
-        let target_file = dotfiles_dir.join(dotfiles::get_target_basepath(&file).unwrap());
+        let target_file = dotfiles_dir.join(dotfiles::get_target_basepath(ctx, &file).unwrap());
+
+        // e.g. a file that was adopted by a previous push is a symlink to its dotfile
+        if is_same_file(&file, &target_file) {
+            eprintln!(
+                "{}",
+                t!(
+                    "warn.x_already_in_group",
+                    file = file.display(),
+                    group = group
+                )
+                .yellow()
+            );
+            continue;
+        }
 
         if target_file.exists() && !assume_yes {
             print!(
@@ -292,28 +1101,92 @@ pub fn push_cmd(
         let target_dir = target_file.parent().unwrap();
         fs::create_dir_all(target_dir).unwrap();
 
-        if file.is_file() {
-            fs::copy(file, target_file).unwrap();
-            continue;
-        }
+        let mut file_failed = false;
+        // the whole directory is moved when it's being replaced by a symlink
+        // so that files skipped by the walk aren't lost
+        let moved_dir = add
+            && file.is_dir()
+            && !target_file.exists()
+            && fs::rename(&file, &target_file).is_ok();
+        if moved_dir {
+            // there's nothing left to transfer
+        } else if file.is_file() {
+            if let Err(e) = transfer(&file, &target_file) {
+                eprintln!("{}", e.red());
+                file_failed = true;
+            }
+        } else {
+            let mut dirs = Vec::new();
+            let mut transferred = HashSet::new();
+            for f in fileops::DirWalk::new(&file).follow_symlinks(true) {
+                if f.is_dir() {
+                    dirs.push(f);
+                    continue;
+                }
 
-        for f in fileops::DirWalk::new(file) {
-            if f.is_dir() {
-                continue;
+                if !f.exists() {
+                    eprintln!("{}", t!("errors.x_doesnt_exist", x = f.display()).red());
+                    file_failed = true;
+                    continue;
+                }
+
+                let file = path::absolute(f).unwrap();
+
+                let target_file =
+                    dotfiles_dir.join(dotfiles::get_target_basepath(ctx, &file).unwrap());
+
+                fs::create_dir_all(target_file.parent().unwrap()).unwrap();
+                if let Err(e) = transfer(&file, &target_file) {
+                    eprintln!("{}", e.red());
+                    file_failed = true;
+                } else if fileops::files_are_equal(&file, &target_file) {
+                    transferred.insert(file);
+                }
             }
 
-            if !f.exists() {
-                eprintln!("{}", t!("errors.x_doesnt_exist", x = f.display()).red());
-                any_file_failed = true;
-                continue;
+            // the directory is replaced by a symlink below, which can only be done
+            // if every file inside of it was pushed, including the ones the walk skips
+            if add && !move_files && !file_failed && dir_only_contains(&file, &transferred) {
+                for f in &transferred {
+                    _ = fs::remove_file(f);
+                }
             }
 
-            let file = path::absolute(f).unwrap();
+            if move_files || add {
+                // only the emptied directories are left behind, anything that
+                // couldn't be moved keeps its parents from being removed
+                for dir in dirs.iter().rev().chain([&file]) {
+                    _ = fs::remove_dir(dir);
+                }
+            }
+        }
+
+        if file_failed {
+            any_file_failed = true;
+            continue;
+        }
 
-            let target_file = dotfiles_dir.join(dotfiles::get_target_basepath(&file).unwrap());
+        // adopt the file by replacing the original with a symlink to its copy in the group
+        if add {
+            // anything left in a directory wasn't pushed so it's kept and the push fails
+            let removed = if !file.exists() {
+                Ok(())
+            } else if file.is_dir() {
+                fs::remove_dir(&file)
+            } else {
+                fs::remove_file(&file)
+            };
 
-            fs::create_dir_all(target_file.parent().unwrap()).unwrap();
-            fs::copy(file, target_file).unwrap();
+            let linked = removed
+                .and_then(|_| symlinks::symlink(&target_file, &file))
+                .and_then(|_| DeployRecord::Symlinks.insert(&file, &target_file));
+            if let Err(e) = linked {
+                eprintln!(
+                    "{}",
+                    t!("errors.failed_to_symlink_x", groupname = group, err_msg = e).red()
+                );
+                any_file_failed = true;
+            }
         }
     }
 
@@ -324,12 +1197,15 @@ pub fn push_cmd(
     }
 }
 
-pub fn pop_cmd(
+pub(crate) fn pop_cmd(
+    ctx: &Context,
     profile: Option<String>,
     groups: &[String],
     assume_yes: bool,
+    list: bool,
+    trash: bool,
 ) -> Result<(), ExitCode> {
-    let dotfiles_dir = match dotfiles::get_dotfiles_path(profile) {
+    let dotfiles_dir = match dotfiles::get_dotfiles_path(ctx, profile) {
         Ok(dir) => dir.join("Configs"),
         Err(e) => {
             eprintln!("{e}");
@@ -361,8 +1237,17 @@ pub fn pop_cmd(
         return Err(ReturnCode::NoSuchFileOrDir.into());
     }
 
+    if list {
+        print_pop_preview(ctx, &valid_groups, trash);
+        return Ok(());
+    }
+
     if !assume_yes {
-        println!("{}:", t!("info.groups_will_be_removed"));
+        if trash {
+            println!("{}:", t!("info.groups_will_be_trashed"));
+        } else {
+            println!("{}:", t!("info.groups_will_be_removed"));
+        }
         for group in groups {
             println!("\t{}", group.yellow());
         }
@@ -376,15 +1261,228 @@ pub fn pop_cmd(
         }
     }
 
-    for group_path in valid_groups {
-        fs::remove_dir_all(group_path).unwrap();
-    }
+    for group_path in valid_groups {
+        if !trash {
+            fs::remove_dir_all(group_path).unwrap();
+            continue;
+        }
+
+        match move_to_trash(ctx, &group_path) {
+            Ok(trashed) => println!(
+                "{}",
+                t!(
+                    "info.moved_x_to_y",
+                    x = group_path.display(),
+                    y = trashed.display()
+                )
+            ),
+            Err(err) => {
+                eprintln!("{}", format!("{}: {err}", group_path.display()).red());
+                return Err(ExitCode::FAILURE);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints the files deleted by popping the groups and the deployed symlinks that would be left broken
+fn print_pop_preview(ctx: &Context, group_dirs: &[PathBuf], trash: bool) {
+    let mut files = Vec::new();
+    let mut symlinks = Vec::new();
+
+    for group_dir in group_dirs {
+        let Ok(group) = dotfiles::Dotfile::new(ctx, group_dir.clone()) else {
+            continue;
+        };
+        let Ok(group_files) = group.try_iter() else {
+            continue;
+        };
+
+        for file in group_files {
+            if file.path == file.group_path {
+                continue;
+            }
+
+            if !file.path.is_dir() {
+                files.push(file.path.clone());
+            }
+
+            for target in file.to_target_paths() {
+                if file.target_exists_kind(&target) == dotfiles::TargetKind::SymlinkToUs {
+                    symlinks.push(target);
+                }
+            }
+        }
+    }
+
+    files.sort();
+    symlinks.sort();
+    symlinks.dedup();
+
+    if trash {
+        println!("{}:", t!("info.files_will_be_trashed"));
+    } else {
+        println!("{}:", t!("info.files_will_be_removed"));
+    }
+    for file in &files {
+        println!("\t{}", file.display().yellow());
+    }
+
+    if !symlinks.is_empty() {
+        println!("\n{}:", t!("warn.symlinks_will_be_broken"));
+        for symlink in &symlinks {
+            println!("\t{}", symlink.display().red());
+        }
+    }
+}
+
+/// Lists the files a group and its conditional groups deploy and where they're deployed to
+pub(crate) fn ls_files_cmd(
+    ctx: &Context,
+    profile: Option<String>,
+    group: &str,
+) -> Result<(), ExitCode> {
+    let configs_dir = match dotfiles::get_dotfiles_path(ctx, profile) {
+        Ok(dir) => dir.join("Configs"),
+        Err(err) => {
+            eprintln!("{err}");
+            return Err(ReturnCode::CouldntFindDotfiles.into());
+        }
+    };
+
+    let mut groups: Vec<_> = fs::read_dir(&configs_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|g| g == group || dotfiles::group_without_target(g) == group)
+        .collect();
+    groups.sort();
+
+    if groups.is_empty() {
+        eprintln!("{}", t!("errors.no_group", group = group).red());
+        return Err(ReturnCode::NoSuchFileOrDir.into());
+    }
+
+    for group in groups {
+        let group = dotfiles::Dotfile::new(ctx, configs_dir.join(&group)).unwrap();
+        if !group.is_valid_target() {
+            println!(
+                "{}",
+                t!(
+                    "errors.x_not_supported_on_this_platform",
+                    x = group.group_name
+                )
+                .yellow()
+            );
+            continue;
+        }
+
+        let mut files: Vec<_> = group
+            .try_iter()
+            .unwrap()
+            .filter(|f| !f.path.is_dir())
+            .collect();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+
+        for file in files {
+            for target in file.to_target_paths() {
+                println!("{} -> {}", file.path.display(), target.display());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns every group sorted by name along with whether it's supported on this system and whether it's symlinked
+fn list_groups(
+    ctx: &Context,
+    profile: Option<String>,
+) -> Result<Vec<(String, bool, bool)>, ExitCode> {
+    let configs_dir = match dotfiles::get_dotfiles_path(ctx, profile.clone()) {
+        Ok(dir) => dir.join("Configs"),
+        Err(err) => {
+            eprintln!("{err}");
+            return Err(ReturnCode::CouldntFindDotfiles.into());
+        }
+    };
+
+    let mut groups: Vec<_> = fs::read_dir(&configs_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+
+    if groups.is_empty() {
+        eprintln!("{}", t!("errors.no_x_setup_yet", x = "groups").yellow());
+        return Err(ReturnCode::NoSetupFolder.into());
+    }
+    groups.sort();
+
+    let report = match symlinks::status(ctx, profile, None) {
+        Ok(report) => report,
+        Err(err) => {
+            eprintln!("{}", err.red());
+            return Err(ReturnCode::CouldntFindDotfiles.into());
+        }
+    };
+
+    Ok(groups
+        .into_iter()
+        .map(|group| {
+            let supported = dotfiles::group_is_valid_target(ctx, &group);
+            let symlinked = report.symlinked.contains(&group);
+            (group, supported, symlinked)
+        })
+        .collect())
+}
+
+/// Lists every group in Configs along with whether it's used on this platform and symlinked
+pub(crate) fn ls_groups_cmd(ctx: &Context, profile: Option<String>) -> Result<(), ExitCode> {
+    let groups = list_groups(ctx, profile)?;
+
+    #[derive(Tabled)]
+    struct ListRow<'a> {
+        #[tabled(rename = "Group")]
+        group: String,
+        #[tabled(rename = "Supported")]
+        supported: &'a str,
+        #[tabled(rename = "Symlinked")]
+        symlinked: &'a str,
+    }
+
+    let true_symbol = "✓".green().to_string();
+    let false_symbol = "✗".red().to_string();
+    let symbol = |value: bool| if value { &true_symbol } else { &false_symbol };
+
+    let rows: Vec<_> = groups
+        .into_iter()
+        .map(|(group, supported, symlinked)| ListRow {
+            supported: symbol(supported),
+            symlinked: symbol(symlinked),
+            group,
+        })
+        .collect();
+
+    use tabled::{Margin, Style};
+
+    let mut groups_list = Table::new(rows);
+    groups_list
+        .with(Style::rounded())
+        .with(Margin::new(4, 4, 1, 1))
+        .with(Modify::new(Segment::new(1.., 1..)).with(Alignment::center()));
+    println!("{groups_list}");
 
     Ok(())
 }
 
-pub fn ls_hooks_cmd(profile: Option<String>) -> Result<(), ExitCode> {
-    let dir = match dotfiles::get_dotfiles_path(profile) {
+pub(crate) fn ls_hooks_cmd(ctx: &Context, profile: Option<String>) -> Result<(), ExitCode> {
+    let dir = match dotfiles::get_dotfiles_path(ctx, profile) {
         Ok(dir) => dir.join("Hooks"),
         Err(err) => {
             eprintln!("{err}");
@@ -454,44 +1552,69 @@ pub fn ls_hooks_cmd(profile: Option<String>) -> Result<(), ExitCode> {
     Ok(())
 }
 
-// todo: make ls-secrets command prettier
-pub fn ls_secrets_cmd(profile: Option<String>) -> Result<(), ExitCode> {
-    let secrets_dir = dotfiles::get_dotfiles_path(profile)
-        .unwrap()
-        .join("Secrets");
+/// Lists every secret along with its group and the path it was encrypted from
+pub(crate) fn ls_secrets_cmd(ctx: &Context, profile: Option<String>) -> Result<(), ExitCode> {
+    let secrets_dir = match dotfiles::get_dotfiles_path(ctx, profile) {
+        Ok(dir) => dir.join("Secrets"),
+        Err(err) => {
+            eprintln!("{err}");
+            return Err(ReturnCode::CouldntFindDotfiles.into());
+        }
+    };
 
-    let Ok(secrets) = secrets_dir.read_dir() else {
+    if !secrets_dir.is_dir() {
         eprintln!("{}", t!("errors.no_dir_setup_for_x", x = "Secrets").red());
         return Err(ReturnCode::NoSetupFolder.into());
-    };
+    }
+
+    #[derive(Tabled)]
+    struct ListRow {
+        #[tabled(rename = "Group")]
+        group: String,
+        #[tabled(rename = "Target")]
+        target: String,
+    }
 
-    let secrets: Vec<_> = secrets.collect();
+    let mut rows: Vec<_> = DirWalk::new(secrets_dir)
+        .filter(|secret| !secret.is_dir())
+        .filter_map(|secret| dotfiles::Dotfile::new(ctx, secret).ok())
+        .map(|secret| ListRow {
+            target: secret.to_target_path().display().to_string(),
+            group: secret.group_name,
+        })
+        .collect();
 
-    if secrets.is_empty() {
+    if rows.is_empty() {
         eprintln!("{}", t!("errors.no_x_setup_yet", x = "secrets").yellow());
         return Err(ExitCode::FAILURE);
     }
 
-    println!("{}:", t!("info.x_available", x = "Secrets"));
-    for secret in secrets {
-        let secret = secret.unwrap();
-        println!("\t{}", secret.file_name().to_str().unwrap());
-    }
+    rows.sort_by(|a, b| (&a.group, &a.target).cmp(&(&b.group, &b.target)));
+
+    use tabled::{Margin, Style};
+
+    let mut secrets_list = Table::new(rows);
+    secrets_list
+        .with(Style::rounded())
+        .with(Margin::new(4, 4, 1, 1));
+    println!("{secrets_list}");
 
     Ok(())
 }
 
-pub fn ls_profiles_cmd() -> Result<(), ExitCode> {
-    let home_dir = dirs::home_dir().unwrap();
-    let config_dir = dirs::config_dir().unwrap();
-
-    fn get_profiles_from_dir(dir: PathBuf) -> HashSet<String> {
+/// Returns the name of every profile that has a dotfiles directory, sorted by name
+///
+/// The default profile doesn't have a name so it's not included
+pub fn get_profiles(ctx: &Context) -> Vec<String> {
+    fn get_profiles_from_dir(ctx: &Context, dir: Option<PathBuf>) -> HashSet<String> {
         let mut available_profiles = HashSet::new();
 
-        for file in dir.read_dir().unwrap() {
-            let file = file.unwrap();
+        let Some(entries) = dir.and_then(|dir| dir.read_dir().ok()) else {
+            return available_profiles;
+        };
 
-            let Some(profile) = dotfiles::get_dotfile_profile_from_path(file.path()) else {
+        for file in entries.flatten() {
+            let Some(profile) = dotfiles::get_dotfile_profile_from_path(ctx, file.path()) else {
                 continue;
             };
 
@@ -501,10 +1624,17 @@ pub fn ls_profiles_cmd() -> Result<(), ExitCode> {
         available_profiles
     }
 
-    let home_profiles = get_profiles_from_dir(home_dir);
-    let config_profiles = get_profiles_from_dir(config_dir);
+    let (home_dir, config_dir) = ctx.dotfiles_roots();
+    let home_profiles = get_profiles_from_dir(ctx, Some(home_dir));
+    let config_profiles = get_profiles_from_dir(ctx, Some(config_dir));
+
+    let mut profiles: Vec<_> = home_profiles.union(&config_profiles).cloned().collect();
+    profiles.sort();
+    profiles
+}
 
-    let profiles: HashSet<_> = home_profiles.union(&config_profiles).collect();
+pub(crate) fn ls_profiles_cmd(ctx: &Context) -> Result<(), ExitCode> {
+    let profiles = get_profiles(ctx);
     if profiles.is_empty() {
         println!("{}", t!("errors.no_x_setup_yet", x = "profiles").yellow());
         return Ok(());
@@ -518,17 +1648,189 @@ pub fn ls_profiles_cmd() -> Result<(), ExitCode> {
     Ok(())
 }
 
-pub fn groupis_cmd(profile: Option<String>, files: &[String]) -> Result<(), ExitCode> {
-    let dotfiles_dir = match dotfiles::get_dotfiles_path(profile) {
+/// Profile names end up in directory names, so only a safe set of characters is allowed
+fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Creates a new profile from a copy of an existing one
+pub(crate) fn profile_copy_cmd(ctx: &Context, from: String, to: String) -> Result<(), ExitCode> {
+    if !is_valid_profile_name(&to) {
+        eprintln!("{}", t!("errors.invalid_profile_name", name = to).red());
+        return Err(ExitCode::FAILURE);
+    }
+
+    let src = match dotfiles::get_dotfiles_path(ctx, Some(from)) {
+        Ok(dir) => dir,
+        Err(e) => {
+            eprintln!("{e}");
+            return Err(ReturnCode::CouldntFindDotfiles.into());
+        }
+    };
+
+    if let Ok(dest) = dotfiles::get_dotfiles_path(ctx, Some(to.clone())) {
+        eprintln!(
+            "{}",
+            t!("errors.x_already_exists", x = dest.display()).red()
+        );
+        return Err(ExitCode::FAILURE);
+    }
+
+    // the new profile lives next to the one it was copied from
+    let dest = src.with_file_name(match src.file_name().unwrap().to_str() {
+        Some(name) if name.starts_with('.') => format!(".dotfiles_{to}"),
+        _ => format!("dotfiles_{to}"),
+    });
+
+    if let Err(e) = copy_tree(&src, &dest) {
+        eprintln!("{}", e.red());
+        return Err(ExitCode::FAILURE);
+    }
+
+    println!(
+        "{}",
+        t!("info.dotfiles_created_at", location = dest.display()).green()
+    );
+
+    Ok(())
+}
+
+/// Returns the groups as is, or lets the user pick them from a list if none were supplied
+///
+/// with_hooks: whether groups that only have hooks should also be listed
+pub fn pick_groups_if_empty(
+    ctx: &Context,
+    profile: Option<String>,
+    groups: Vec<String>,
+    with_hooks: bool,
+) -> Result<Vec<String>, ExitCode> {
+    use std::io::IsTerminal;
+
+    if !groups.is_empty() {
+        return Ok(groups);
+    }
+
+    if !io::stdin().is_terminal() {
+        eprintln!("{}", t!("errors.no_groups_supplied").red());
+        return Err(ExitCode::FAILURE);
+    }
+
+    let dotfiles_dir = match dotfiles::get_dotfiles_path(ctx, profile) {
+        Ok(dir) => dir,
+        Err(e) => {
+            eprintln!("{e}");
+            return Err(ReturnCode::CouldntFindDotfiles.into());
+        }
+    };
+
+    let mut group_dirs = vec![dotfiles_dir.join("Configs")];
+    if with_hooks {
+        group_dirs.push(dotfiles_dir.join("Hooks"));
+    }
+
+    let mut available_groups: Vec<_> = group_dirs
+        .iter()
+        .filter_map(|dir| dir.read_dir().ok())
+        .flatten()
+        .flatten()
+        .filter(|f| f.path().is_dir())
+        .filter_map(|f| f.file_name().into_string().ok())
+        .filter(|group| dotfiles::group_is_valid_target(ctx, group))
+        .map(|group| dotfiles::group_without_target(&group).to_string())
+        .collect();
+
+    available_groups.sort();
+    available_groups.dedup();
+
+    if available_groups.is_empty() {
+        eprintln!("{}", t!("errors.no_x_setup_yet", x = "groups").yellow());
+        return Err(ReturnCode::NoSetupFolder.into());
+    }
+
+    println!("{}:", t!("info.x_available", x = "Groups"));
+    for (idx, group) in available_groups.iter().enumerate() {
+        println!("\t{}) {}", idx + 1, group.green());
+    }
+
+    print!("\n{} ", t!("info.pick_groups"));
+    io::stdout().flush().unwrap();
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer).unwrap();
+
+    let mut picked_groups = Vec::new();
+    for choice in answer.split([' ', ',']).filter(|c| !c.trim().is_empty()) {
+        let choice = choice.trim();
+        if choice == "*" {
+            return Ok(available_groups);
+        }
+
+        match choice.parse::<usize>() {
+            Ok(idx) if (1..=available_groups.len()).contains(&idx) => {
+                picked_groups.push(available_groups[idx - 1].clone());
+            }
+            _ => {
+                eprintln!("{}", t!("errors.invalid_choice", choice = choice).red());
+                return Err(ExitCode::FAILURE);
+            }
+        }
+    }
+
+    // a group that was picked more than once is only kept the first time, in the order it was picked
+    let mut seen = HashSet::new();
+    picked_groups.retain(|group| seen.insert(group.clone()));
+    Ok(picked_groups)
+}
+
+/// Prints the absolute path of a group's directory inside of Configs, Hooks or Secrets
+pub(crate) fn which_cmd(
+    ctx: &Context,
+    profile: Option<String>,
+    group: &str,
+    dtype: dotfiles::DotfileType,
+) -> Result<(), ExitCode> {
+    let dotfiles_dir = match dotfiles::get_dotfiles_path(ctx, profile) {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("{e}");
+            return Err(ReturnCode::CouldntFindDotfiles.into());
+        }
+    };
+
+    let group_dir = dotfiles_dir.join(dtype.dir_name()).join(group);
+    if !group_dir.is_dir() {
+        eprintln!("{}", t!("errors.x_doesnt_exist", x = group).red());
+        return Err(ReturnCode::NoSuchFileOrDir.into());
+    }
+
+    println!(
+        "{}",
+        path::absolute(&group_dir).unwrap_or(group_dir).display()
+    );
+    Ok(())
+}
+
+pub(crate) fn groupis_cmd(
+    ctx: &Context,
+    profile: Option<String>,
+    files: &[String],
+) -> Result<(), ExitCode> {
+    let dotfiles_dir = match dotfiles::get_dotfiles_path(ctx, profile) {
         Ok(path) => path,
         Err(e) => {
             eprintln!("{e}");
             return Err(ReturnCode::NoSetupFolder.into());
         }
-    }
-    .join("Configs");
+    };
+    let configs_dir = dotfiles_dir.join("Configs");
+    let manifest = dotfiles::Manifest::load(ctx, &dotfiles_dir);
+    let default_target = ctx.target_dir();
 
-    let groups: Vec<_> = dotfiles_dir
+    let mut groups: Vec<_> = configs_dir
         .read_dir()
         .unwrap()
         .filter_map(|f| {
@@ -540,56 +1842,76 @@ pub fn groupis_cmd(profile: Option<String>, files: &[String]) -> Result<(), Exit
             }
         })
         .collect();
+    groups.sort();
 
-    'next_file: for file in files {
-        let mut file_path = match PathBuf::from(file).canonicalize() {
+    // directories each group's files are deployed to
+    let group_targets = |group: &str| -> Vec<PathBuf> {
+        if group == "Root" {
+            return vec![PathBuf::from(path::MAIN_SEPARATOR_STR)];
+        }
+
+        match manifest.groups.get(group) {
+            Some(group) if !group.targets.is_empty() => group.target_dirs.clone(),
+            _ => match dotfiles::GroupConfig::target_dir(ctx, &dotfiles_dir, group) {
+                Some(target) => vec![target],
+                None => vec![default_target.clone()],
+            },
+        }
+    };
+
+    let mut all_found = true;
+
+    for file in files {
+        let file_path = match path::absolute(file) {
             Ok(fp) => fp,
             Err(err) => {
                 eprintln!("{err}");
+                all_found = false;
                 continue;
             }
         };
 
-        if !file_path.exists() {
-            eprintln!("{}", t!("errors.x_doesnt_exist", x = file).red());
-            continue;
-        }
-
-        if let Ok(dotfile) = dotfiles::Dotfile::try_from(file_path.clone()) {
-            println!("{}", dotfile.group_name);
+        // files inside of the dotfiles directory belong to the group they're in
+        if let Ok(dotfile) = dotfiles::Dotfile::new(ctx, file_path.clone()) {
+            println!("{}\t{}", dotfile.group_name, dotfile.path.display());
             continue;
         }
 
-        while !file_path.is_symlink() {
-            // continuosly go up a directory trying to find where the symlink is
-            if !file_path.pop() {
-                eprintln!("{}", t!("errors.not_a_tuckr_dotfile", file = file).red());
-                continue 'next_file;
-            }
-        }
-
-        let basepath = dotfiles::get_target_basepath(&file_path).unwrap();
-
+        // any group that has the file is able to provide it, whether it's deployed or not
+        let mut found = false;
         for group in &groups {
-            let dotfile_path = dotfiles_dir.join(group).join(&basepath);
-
-            if !dotfile_path.exists() {
-                continue;
-            }
+            for target_dir in group_targets(group) {
+                let basepath = if target_dir == default_target {
+                    dotfiles::get_target_basepath(ctx, &file_path)
+                } else {
+                    file_path.strip_prefix(&target_dir).ok().map(PathBuf::from)
+                };
+                let Some(basepath) = basepath else {
+                    continue;
+                };
 
-            let dotfile = match dotfiles::Dotfile::try_from(dotfile_path) {
-                Ok(dotfile) => dotfile,
-                Err(err) => {
-                    eprintln!("{err}");
+                let dotfile_path = configs_dir.join(group).join(&basepath);
+                if basepath.as_os_str().is_empty() || !dotfile_path.exists() {
                     continue;
                 }
-            };
 
-            println!("{}", dotfile.group_name);
+                println!("{group}\t{}", dotfile_path.display());
+                found = true;
+                break;
+            }
+        }
+
+        if !found {
+            eprintln!("{}", t!("errors.not_a_tuckr_dotfile", file = file).red());
+            all_found = false;
         }
     }
 
-    Ok(())
+    if all_found {
+        Ok(())
+    } else {
+        Err(ExitCode::FAILURE)
+    }
 }
 
 #[cfg(test)]
@@ -600,22 +1922,27 @@ mod tests {
     struct FileopsTest {
         dotfiles_dir: PathBuf,
         target_dir: PathBuf,
+        ctx: Context,
     }
 
     impl FileopsTest {
         fn start() -> Self {
-            let dotfiles_dir = dotfiles::get_dotfiles_path(None).unwrap();
+            let thread_name = std::thread::current().name().unwrap().to_owned();
+            // stands in for the user's home so that tests never touch it
+            let ctx = Context {
+                target_dir: Some(std::env::temp_dir().join(format!("tuckr-home-{thread_name}"))),
+                ..Default::default()
+            };
+            let dotfiles_dir = dotfiles::get_dotfiles_path(&ctx, None).unwrap();
             fs::create_dir_all(dotfiles_dir.join("Configs")).unwrap();
 
-            let target_dir = dirs::home_dir().unwrap().join(format!(
-                "tuckr_test-{}",
-                std::thread::current().name().unwrap()
-            ));
+            let target_dir = ctx.target_dir().join(format!("tuckr_test-{thread_name}"));
             fs::create_dir_all(&target_dir).unwrap();
 
             Self {
                 dotfiles_dir,
                 target_dir,
+                ctx,
             }
         }
     }
@@ -626,33 +1953,35 @@ mod tests {
                 fs::remove_dir_all(&self.dotfiles_dir).unwrap();
             }
 
-            if self.target_dir.exists() {
-                fs::remove_dir_all(&self.target_dir).unwrap();
-            }
+            _ = fs::remove_dir_all(self.ctx.target_dir());
         }
     }
 
     #[test]
     fn push_files() {
         let ft = FileopsTest::start();
+        let ctx = &ft.ctx;
 
         let file_path = ft.target_dir.join("test");
         let mut file = fs::File::create(&file_path).unwrap();
-        file.write("this is a test".as_bytes()).unwrap();
+        file.write_all("this is a test".as_bytes()).unwrap();
 
         let pushed_file = ft
             .dotfiles_dir
             .join("Configs")
             .join("test")
-            .join(dotfiles::get_target_basepath(&file_path).unwrap());
+            .join(dotfiles::get_target_basepath(ctx, &file_path).unwrap());
 
         assert!(!pushed_file.exists());
 
         super::push_cmd(
+            ctx,
             None,
             "test".into(),
             &[file_path.to_str().unwrap().to_string()],
             true,
+            false,
+            false,
         )
         .unwrap();
 
@@ -661,12 +1990,15 @@ mod tests {
             pushed_file.exists() && first_pushed_content == fs::read_to_string(&file_path).unwrap()
         );
 
-        file.write("something something".as_bytes()).unwrap();
+        file.write_all("something something".as_bytes()).unwrap();
         super::push_cmd(
+            ctx,
             None,
             "test".into(),
             &[file_path.to_str().unwrap().to_string()],
             true,
+            false,
+            false,
         )
         .unwrap();
 
@@ -679,16 +2011,17 @@ mod tests {
     #[test]
     fn push_directories() {
         let ft = FileopsTest::start();
+        let ctx = &ft.ctx;
 
         let dir1 = ft.target_dir.join("dir1");
         fs::create_dir_all(&dir1).unwrap();
         let mut file1 = fs::File::create(dir1.join("file")).unwrap();
-        file1.write("test".as_bytes()).unwrap();
+        file1.write_all("test".as_bytes()).unwrap();
 
         let dir2 = ft.target_dir.join("dir2");
         fs::create_dir_all(&dir2).unwrap();
         let mut file2 = fs::File::create(dir2.join("file")).unwrap();
-        file2.write("test".as_bytes()).unwrap();
+        file2.write_all("test".as_bytes()).unwrap();
 
         // never used because it is empty
         fs::create_dir_all(ft.target_dir.join("dir3")).unwrap();
@@ -697,15 +2030,18 @@ mod tests {
             .dotfiles_dir
             .join("Configs")
             .join("test")
-            .join(dotfiles::get_target_basepath(&ft.target_dir).unwrap());
+            .join(dotfiles::get_target_basepath(ctx, &ft.target_dir).unwrap());
 
         assert!(!group_dir.exists());
 
         super::push_cmd(
+            ctx,
             None,
             "test".into(),
             &[ft.target_dir.to_str().unwrap().to_owned()],
             true,
+            false,
+            false,
         )
         .unwrap();
 
@@ -713,36 +2049,257 @@ mod tests {
         assert!(group_dir.exists() && fs::read_dir(group_dir).unwrap().count() == 2);
     }
 
+    #[test]
+    fn push_move_files() {
+        let ft = FileopsTest::start();
+        let ctx = &ft.ctx;
+
+        let dir = ft.target_dir.join("dir");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("file"), "test").unwrap();
+
+        let group_dir = ft
+            .dotfiles_dir
+            .join("Configs")
+            .join("test")
+            .join(dotfiles::get_target_basepath(ctx, &dir).unwrap());
+
+        super::push_cmd(
+            ctx,
+            None,
+            "test".into(),
+            &[dir.to_str().unwrap().to_owned()],
+            true,
+            true,
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(fs::read_to_string(group_dir.join("file")).unwrap(), "test");
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn push_and_adopt_files() {
+        let ft = FileopsTest::start();
+        let ctx = &ft.ctx;
+
+        fs::create_dir_all(&ft.target_dir).unwrap();
+        let file = ft.target_dir.join("file");
+        fs::write(&file, "test").unwrap();
+
+        super::push_cmd(
+            ctx,
+            None,
+            "test".into(),
+            &[file.to_str().unwrap().to_owned()],
+            true,
+            false,
+            true,
+        )
+        .unwrap();
+
+        let pushed_file = ft
+            .dotfiles_dir
+            .join("Configs")
+            .join("test")
+            .join(dotfiles::get_target_basepath(ctx, &file).unwrap());
+
+        assert_eq!(fs::read_link(&file).unwrap(), pushed_file);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "test");
+
+        // pushing the adopted file again leaves its dotfile alone
+        super::push_cmd(
+            ctx,
+            None,
+            "test".into(),
+            &[file.to_str().unwrap().to_owned()],
+            true,
+            false,
+            true,
+        )
+        .unwrap();
+        assert_eq!(fs::read_link(&file).unwrap(), pushed_file);
+        assert_eq!(fs::read_to_string(&pushed_file).unwrap(), "test");
+
+        // files skipped when walking a directory are moved along with it
+        let dir = ft.target_dir.join("dir");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("config"), "test").unwrap();
+        fs::write(dir.join("config~"), "backup").unwrap();
+        let push_dir = || {
+            super::push_cmd(
+                ctx,
+                None,
+                "test".into(),
+                &[dir.to_str().unwrap().to_owned()],
+                true,
+                false,
+                true,
+            )
+        };
+        push_dir().unwrap();
+        assert_eq!(fs::read_to_string(dir.join("config~")).unwrap(), "backup");
+
+        // they're kept in place when the directory can't be moved as a whole
+        fs::remove_file(&dir).unwrap();
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("config"), "test").unwrap();
+        fs::write(dir.join("config~"), "backup").unwrap();
+        assert!(push_dir().is_err());
+        assert!(!dir.is_symlink());
+        assert_eq!(fs::read_to_string(dir.join("config")).unwrap(), "test");
+        assert_eq!(fs::read_to_string(dir.join("config~")).unwrap(), "backup");
+    }
+
     #[test]
     fn pop_groups() {
         let ft = FileopsTest::start();
+        let ctx = &ft.ctx;
 
         fs::create_dir_all(&ft.target_dir).unwrap();
         let mut file = fs::File::create(ft.target_dir.join("file")).unwrap();
-        file.write("test".as_bytes()).unwrap();
+        file.write_all("test".as_bytes()).unwrap();
 
         let group_dir = ft
             .dotfiles_dir
             .join("Configs")
             .join("test")
-            .join(dotfiles::get_target_basepath(&ft.target_dir).unwrap());
+            .join(dotfiles::get_target_basepath(ctx, &ft.target_dir).unwrap());
 
         super::push_cmd(
+            ctx,
             None,
             "test".into(),
             &[ft.target_dir.to_str().unwrap().to_owned()],
             true,
+            false,
+            false,
         )
         .unwrap();
 
         assert!(group_dir.exists());
-        super::pop_cmd(None, &["test".into()], true).unwrap();
+        super::pop_cmd(ctx, None, &["test".into()], true, false, false).unwrap();
+        assert!(!group_dir.exists());
+    }
+
+    #[test]
+    fn from_stow_skips_collisions() {
+        let ft = FileopsTest::start();
+        let ctx = &ft.ctx;
+        let configs_dir = ft.dotfiles_dir.join("Configs");
+
+        for package in ["Zsh", "Nvim"] {
+            fs::create_dir_all(ft.dotfiles_dir.join(package)).unwrap();
+            fs::write(ft.dotfiles_dir.join(package).join(".rc"), "").unwrap();
+        }
+        fs::create_dir_all(configs_dir.join("Zsh")).unwrap();
+
+        from_stow_cmd(ctx, None, true, None, false).unwrap();
+
+        assert!(configs_dir.join("Nvim").join(".rc").exists());
+        assert!(!ft.dotfiles_dir.join("Nvim").exists());
+        // the existing group is left as it was
+        assert!(!configs_dir.join("Zsh").join(".rc").exists());
+        assert!(ft.dotfiles_dir.join("Zsh").join(".rc").exists());
+    }
+
+    #[test]
+    fn from_stow_single_group() {
+        let ft = FileopsTest::start();
+        let ctx = &ft.ctx;
+        for file in [".zshrc", "README.md", "LICENSE"] {
+            fs::write(ft.dotfiles_dir.join(file), "").unwrap();
+        }
+
+        assert!(from_stow_cmd(ctx, None, true, Some("../zsh".into()), false).is_err());
+        assert!(ft.dotfiles_dir.join(".zshrc").exists());
+
+        from_stow_cmd(ctx, None, true, Some("zsh".into()), false).unwrap();
+        let group_dir = ft.dotfiles_dir.join("Configs").join("zsh");
+        assert!(group_dir.join(".zshrc").exists());
+        for file in ["README.md", "LICENSE"] {
+            assert!(ft.dotfiles_dir.join(file).exists());
+            assert!(!group_dir.join(file).exists());
+        }
+    }
+
+    #[test]
+    fn failed_clone_keeps_dotfiles() {
+        let ft = FileopsTest::start();
+        let ctx = &ft.ctx;
+        let group_dir = ft.dotfiles_dir.join("Configs").join("Zsh");
+        fs::create_dir_all(&group_dir).unwrap();
+        fs::write(group_dir.join(".zshrc"), "export EDITOR=vi").unwrap();
+
+        let repo = ft.target_dir.join("repo");
+        assert!(clone_cmd(ctx, None, repo.to_str().unwrap(), true).is_err());
+        assert_eq!(
+            fs::read_to_string(group_dir.join(".zshrc")).unwrap(),
+            "export EDITOR=vi"
+        );
+
+        // the dotfiles are only replaced once the clone succeeds
+        fs::create_dir_all(repo.join("Configs").join("Git")).unwrap();
+        fs::write(repo.join("Configs").join("Git").join(".gitconfig"), "").unwrap();
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .args(["-c", "user.name=tuckr", "-c", "user.email=tuckr@localhost"])
+                .args(args)
+                .current_dir(&repo)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success());
+        };
+        git(&["init", "-q"]);
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "dotfiles"]);
+
+        clone_cmd(ctx, None, repo.to_str().unwrap(), true).unwrap();
         assert!(!group_dir.exists());
+        assert!(ft
+            .dotfiles_dir
+            .join("Configs")
+            .join("Git")
+            .join(".gitconfig")
+            .exists());
+    }
+
+    #[test]
+    fn export_and_import_dotfiles() {
+        let ft = FileopsTest::start();
+        let ctx = &ft.ctx;
+        let group_dir = ft.dotfiles_dir.join("Configs").join("Zsh");
+        fs::create_dir_all(&group_dir).unwrap();
+        fs::write(group_dir.join(".zshrc"), "export EDITOR=vi").unwrap();
+
+        let archive = ft.target_dir.join("dotfiles.tar.gz");
+        export_cmd(ctx, None, &archive, false).unwrap();
+
+        // an existing dotfiles directory is only replaced when forced
+        assert!(import_cmd(ctx, None, &archive, false).is_err());
+        fs::remove_file(group_dir.join(".zshrc")).unwrap();
+        import_cmd(ctx, None, &archive, true).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(group_dir.join(".zshrc")).unwrap(),
+            "export EDITOR=vi"
+        );
+
+        // a truncated archive leaves the existing dotfiles alone
+        let content = fs::read(&archive).unwrap();
+        fs::write(&archive, &content[..content.len() / 2]).unwrap();
+        assert!(import_cmd(ctx, None, &archive, true).is_err());
+        assert_eq!(
+            fs::read_to_string(group_dir.join(".zshrc")).unwrap(),
+            "export EDITOR=vi"
+        );
     }
 
     #[test]
     fn ignore_garbage_files() {
-        assert!(is_ignored_file("asdfadsfaf") == false);
+        assert!(!is_ignored_file("asdfadsfaf"));
 
         if cfg!(target_os = "macos") {
             assert!(is_ignored_file(".DS_Store"));
@@ -758,4 +2315,87 @@ mod tests {
             assert!(is_ignored_file("tuckr-backup~"));
         }
     }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn dir_walk_skips_unreadable_dirs() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let ft = FileopsTest::start();
+        let locked_dir = ft.target_dir.join("locked");
+        fs::create_dir_all(&locked_dir).unwrap();
+        fs::write(locked_dir.join("hidden"), "").unwrap();
+        fs::write(ft.target_dir.join("visible"), "").unwrap();
+
+        fs::set_permissions(&locked_dir, fs::Permissions::from_mode(0o000)).unwrap();
+        // root can read the directory regardless of its permissions
+        let is_readable = fs::read_dir(&locked_dir).is_ok();
+        let files: Vec<_> = DirWalk::new(&ft.target_dir).collect();
+        fs::set_permissions(&locked_dir, fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert!(files.contains(&ft.target_dir.join("visible")));
+        assert!(files.contains(&locked_dir));
+        assert_eq!(files.contains(&locked_dir.join("hidden")), is_readable);
+        assert_eq!(DirWalk::new(ft.target_dir.join("missing")).count(), 0);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn dir_walk_symlinked_dirs() {
+        let ft = FileopsTest::start();
+        let real_dir = ft.target_dir.join("real");
+        let linked_dir = ft.target_dir.join("linked");
+        fs::create_dir_all(&real_dir).unwrap();
+        fs::write(real_dir.join("file"), "").unwrap();
+        std::os::unix::fs::symlink(&real_dir, &linked_dir).unwrap();
+
+        let files: Vec<_> = DirWalk::new(&ft.target_dir).collect();
+        assert!(files.contains(&linked_dir));
+        assert!(!files.contains(&linked_dir.join("file")));
+
+        let files: Vec<_> = DirWalk::new(&ft.target_dir).follow_symlinks(true).collect();
+        assert!(files.contains(&linked_dir.join("file")));
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn dir_walk_no_ignore() {
+        let ft = FileopsTest::start();
+        let junk_file = ft.target_dir.join(".directory");
+        fs::write(&junk_file, "").unwrap();
+
+        assert!(!DirWalk::new(&ft.target_dir).any(|f| f == junk_file));
+        assert!(DirWalk::new(&ft.target_dir)
+            .no_ignore(true)
+            .any(|f| f == junk_file));
+    }
+
+    #[test]
+    fn list_groups_status() {
+        let ft = FileopsTest::start();
+        let ctx = &ft.ctx;
+        let configs_dir = ft.dotfiles_dir.join("Configs");
+
+        // a conditional group for a platform that isn't the current one
+        let unsupported = if cfg!(target_family = "unix") {
+            "zsh_windows"
+        } else {
+            "zsh_unix"
+        };
+        for group in ["git", "zsh", unsupported] {
+            fs::create_dir_all(configs_dir.join(group)).unwrap();
+            fs::write(configs_dir.join(group).join(format!(".{group}rc")), "").unwrap();
+        }
+
+        symlinks::add_cmd(ctx, None, &["zsh".into()], &[], &Default::default()).unwrap();
+
+        assert_eq!(
+            list_groups(ctx, None).unwrap(),
+            [
+                ("git".to_string(), true, false),
+                ("zsh".to_string(), true, true),
+                (unsupported.to_string(), false, false),
+            ]
+        );
+    }
 }
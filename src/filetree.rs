@@ -1,4 +1,9 @@
-use std::collections::HashSet;
+//! A tree of the paths of dotfiles, used to work out which of them should be symlinked
+//!
+//! Paths are stored as nodes under the tree's root, directories that weren't inserted
+//! themselves are added as nodes without a group so that every node has a parent.
+
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone)]
@@ -11,10 +16,13 @@ struct FileNode<'a> {
 }
 
 #[derive(Default, Debug)]
-struct FileTree<'a> {
+pub struct FileTree<'a> {
     paths: Vec<Option<PathBuf>>,
     nodes: Vec<Option<FileNode<'a>>>,
-    groups: HashSet<String>,
+    // number of nodes that belong to each group
+    groups: HashMap<String, usize>,
+    // node index of each path, so that lookups don't have to walk the tree
+    index: HashMap<PathBuf, usize>,
 }
 
 struct FileTreeIterator<'a> {
@@ -57,7 +65,7 @@ impl<'a> FileTree<'a> {
         }
     }
 
-    pub fn path_is_in_root(&self, value: &Path) -> bool {
+    fn root_path(&self) -> &Path {
         let root_node = self
             .nodes
             .first()
@@ -65,14 +73,15 @@ impl<'a> FileTree<'a> {
             .as_ref()
             .expect("root node should never be marked as None");
 
-        let root_path = self
-            .paths
+        self.paths
             .get(root_node.path_idx)
             .unwrap()
             .as_ref()
-            .expect("root should never be None");
+            .expect("root should never be None")
+    }
 
-        value.starts_with(root_path)
+    pub fn path_is_in_root(&self, value: &Path) -> bool {
+        value.starts_with(self.root_path())
     }
 
     pub fn contains_path(&self, value: &Path) -> bool {
@@ -80,99 +89,96 @@ impl<'a> FileTree<'a> {
     }
 
     pub fn contains_group(&self, groupname: &str) -> bool {
-        self.groups.contains(groupname)
+        self.groups.contains_key(groupname)
     }
 
     fn find_node_idx(&self, value: &Path) -> Option<usize> {
-        for (idx, node) in self.iter() {
-            let node = match node {
-                Some(node) => node,
-                None => continue,
-            };
+        self.index.get(value).copied()
+    }
 
-            if let Some(ref path_node) = self.paths[node.path_idx] {
-                if path_node == value {
-                    return Some(idx);
-                }
+    /// Inserts a path into the tree, its missing parent directories are inserted without a group.
+    ///
+    /// Returns false if the path is outside of the tree's root or was already inserted
+    pub fn insert(&mut self, group: Option<&'a str>, value: &Path) -> bool {
+        if let Some(idx) = self.find_node_idx(value) {
+            // the path might have been added as the parent of another path
+            let node = self.nodes[idx].as_mut().unwrap();
+            if node.group.is_some() || group.is_none() {
+                return false;
             }
+
+            node.group = group;
+            *self.groups.entry(group.unwrap().into()).or_default() += 1;
+            return true;
         }
 
-        None
-    }
+        let parent = if self.nodes.is_empty() {
+            None
+        } else {
+            if !self.path_is_in_root(value) {
+                return false;
+            }
 
-    // todo: separate values into components and then insert each component's contructed path at a time
-    // todo: make all paths absolute before insertion
-    pub fn insert(&mut self, group: Option<&'a str>, value: &Path) -> bool {
-        if self.contains_path(value) {
-            return false;
-        }
+            let value_parent = value.parent().unwrap_or(value);
+            if !self.contains_path(value_parent) {
+                self.insert(None, value_parent);
+            }
+            self.find_node_idx(value_parent)
+        };
 
         if let Some(group) = group {
-            self.groups.insert(group.into());
+            *self.groups.entry(group.into()).or_default() += 1;
         }
 
         self.paths.push(Some(value.into()));
         let path_idx = self.paths.len() - 1;
-
-        let value_parent = {
-            let mut parent = value.to_path_buf();
-            parent.pop();
-            parent
-        };
-
-        if self.nodes.is_empty() {
-            self.nodes.push(Some(FileNode {
-                group,
-                parent_node_idx: 0,
-                path_idx,
-                children: None,
-            }));
-
-            return true;
+        let new_node_idx = self.nodes.len();
+        self.index.insert(value.into(), new_node_idx);
+
+        if let Some(parent) = parent {
+            if let Some(ref mut node) = self.nodes[parent] {
+                node.children
+                    .get_or_insert_with(Vec::new)
+                    .push(new_node_idx);
+            }
         }
 
-        match self.find_node_idx(&value_parent) {
-            Some(parent) => {
-                let new_node_idx = self.nodes.len();
-
-                if let Some(ref mut node) = self.nodes[parent] {
-                    match node.children {
-                        Some(ref mut children) => children.push(new_node_idx),
-                        None => node.children = Some(vec![new_node_idx]),
-                    }
-                }
-
-                self.nodes.push(Some(FileNode {
-                    group,
-                    parent_node_idx: parent,
-                    path_idx,
-                    children: None,
-                }));
+        self.nodes.push(Some(FileNode {
+            group,
+            parent_node_idx: parent.unwrap_or(0),
+            path_idx,
+            children: None,
+        }));
 
-                true
-            }
-
-            None => false,
-        }
+        true
     }
 
+    /// Removes a node and everything under it, returning the node's path
     pub fn remove(&mut self, idx: usize) -> Option<PathBuf> {
         let node = self.nodes.get(idx)?.clone()?;
 
         if let Some(ref mut parent_node) = self.nodes[node.parent_node_idx] {
-            if let Some(ref children) = parent_node.children {
-                parent_node.children =
-                    Some(children.iter().filter(|v| **v != idx).copied().collect());
+            if let Some(ref mut children) = parent_node.children {
+                children.retain(|v| *v != idx);
             }
         }
 
+        for child in node.children.iter().flatten() {
+            self.remove(*child);
+        }
+
         if let Some(group) = node.group {
-            self.groups.remove(group);
+            if let Some(count) = self.groups.get_mut(group) {
+                *count -= 1;
+                if *count == 0 {
+                    self.groups.remove(group);
+                }
+            }
         }
 
         self.nodes[idx] = None;
-        let discarded_path = self.paths[node.path_idx].clone()?;
-        self.paths[node.path_idx] = None;
+        let discarded_path = self.paths[node.path_idx].take()?;
+        self.index.remove(&discarded_path);
 
         Some(discarded_path)
     }
@@ -201,19 +207,42 @@ impl<'a> FileTree<'a> {
         Some(group_paths)
     }
 
-    pub fn canonicalize(&mut self) {
-        todo!()
+    /// Returns every path that was inserted with a group
+    pub fn group_paths(&self) -> impl Iterator<Item = &Path> {
+        self.nodes
+            .iter()
+            .flatten()
+            .filter(|node| node.group.is_some())
+            .filter_map(|node| self.paths[node.path_idx].as_deref())
     }
 
-    pub fn is_empty(&self) -> bool {
-        if self.paths.is_empty() && self.nodes.is_empty() {
-            return true;
+    /// Removes the paths that are deployed along with another path, so that only the
+    /// shallowest paths are left.
+    ///
+    /// covered_by: paths under any of its group paths are removed as well,
+    /// eg: files inside of directories that are already symlinked
+    pub fn canonicalize(&mut self, covered_by: Option<&FileTree>) {
+        if let Some(other) = covered_by {
+            for path in other.group_paths() {
+                self.remove_path(path);
+            }
         }
 
-        let has_items = self.paths.iter().any(|path| path.is_some())
-            || self.nodes.iter().any(|node| node.is_some());
+        let mut stack = vec![0];
+        while let Some(idx) = stack.pop() {
+            let Some(ref node) = self.nodes[idx] else {
+                continue;
+            };
 
-        !has_items
+            let children = node.children.clone().unwrap_or_default();
+            if node.group.is_some() {
+                for child in children {
+                    self.remove(child);
+                }
+            } else {
+                stack.extend(children);
+            }
+        }
     }
 }
 
@@ -273,9 +302,29 @@ mod tests {
     #[test]
     fn insert_rejects_paths_outside_of_root() {
         let mut ft = FileTree::new(Path::new("/home/tuckr"));
-        assert_eq!(ft.insert(Some("test"), Path::new("/home/tuckr/test")), true);
+        assert!(ft.insert(Some("test"), Path::new("/home/tuckr/test")));
 
-        assert_eq!(ft.insert(Some("test"), Path::new("/usr/bin")), false);
+        assert!(!ft.insert(Some("test"), Path::new("/usr/bin")));
         assert!(ft.remove_path(Path::new("/usr/bin")).is_none());
     }
+
+    #[test]
+    fn canonicalize_keeps_shallowest_paths() {
+        let mut linked = FileTree::new(Path::new("/dots"));
+        linked.insert(Some("nvim"), Path::new("/dots/nvim/.config/nvim"));
+
+        let mut ft = FileTree::new(Path::new("/dots"));
+        ft.insert(Some("zsh"), Path::new("/dots/zsh/.zsh/plugins/a.zsh"));
+        ft.insert(Some("zsh"), Path::new("/dots/zsh/.zsh"));
+        ft.insert(Some("zsh"), Path::new("/dots/zsh/.zshrc"));
+        ft.insert(Some("nvim"), Path::new("/dots/nvim/.config/nvim/init.lua"));
+
+        ft.canonicalize(Some(&linked));
+
+        let zsh = ft.get("zsh").unwrap();
+        assert_eq!(zsh.len(), 2);
+        assert!(zsh.contains(Path::new("/dots/zsh/.zsh")));
+        assert!(!ft.contains_group("nvim"));
+        assert!(ft.contains_path(Path::new("/dots/zsh")));
+    }
 }
@@ -4,20 +4,27 @@
 //! Hooking steps:
 //! 1. Setup scripts are run
 //! 2. Dotfiles are symlinked
-//! 3. Post setup scripts are run
+//! 3. Secrets are decrypted, only if requested
+//! 4. Post setup scripts are run
 
-use crate::dotfiles::{self, Dotfile, ReturnCode};
+use crate::dotfiles::{self, Context, Dotfile, ReturnCode};
+use crate::secrets::SecretsDeployer;
 use crate::symlinks;
 use owo_colors::OwoColorize;
 use rust_i18n::t;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Command, ExitCode};
+use std::time::{Duration, Instant};
 use tabled::{Table, Tabled};
 
 /// Prints a single row info box with title on the left
 /// and content on the right
-fn print_info_box(title: &str, content: &str) {
+fn print_info_box(ctx: &Context, title: &str, content: &str) {
+    if ctx.verbosity == dotfiles::Verbosity::Quiet {
+        return;
+    }
+
     let mut hook_box = tabled::builder::Builder::default()
         .set_columns([title])
         .add_record([content])
@@ -34,6 +41,7 @@ enum DeployStep {
     Initialize, // Default value before starting deployment
     PreHook,
     Symlink,
+    Secrets,
     PostHook,
 }
 
@@ -44,6 +52,11 @@ impl DeployStages {
     fn new() -> DeployStages {
         DeployStages(DeployStep::Initialize)
     }
+
+    /// Skips the remaining steps so that the next group can be deployed
+    fn finish(&mut self) {
+        self.0 = DeployStep::PostHook;
+    }
 }
 
 impl Iterator for DeployStages {
@@ -60,6 +73,10 @@ impl Iterator for DeployStages {
                 Some(DeployStep::Symlink)
             }
             DeployStep::Symlink => {
+                self.0 = DeployStep::Secrets;
+                Some(DeployStep::Secrets)
+            }
+            DeployStep::Secrets => {
                 self.0 = DeployStep::PostHook;
                 Some(DeployStep::PostHook)
             }
@@ -68,9 +85,136 @@ impl Iterator for DeployStages {
     }
 }
 
+/// Options that change how hooks are run by `set_cmd`
+#[derive(Default, Clone)]
+pub struct HookOptions {
+    /// Go on to the next groups after one of a group's steps fails and exit with an error
+    /// once all of them ran, the rest of the failed group is always skipped
+    pub keep_going: bool,
+    /// How long a hook can run for before being killed
+    pub timeout: Option<Duration>,
+    /// Make hooks that aren't executable executable instead of failing
+    pub fix_permissions: bool,
+    /// Decrypt the groups' secrets to their targets after symlinking them
+    pub with_secrets: bool,
+    /// Read the password for the secrets from this file instead of prompting for it
+    pub passphrase_file: Option<PathBuf>,
+}
+
+/// Returns the interpreter and its arguments from the script's shebang line
+///
+/// `env` is skipped so that the interpreter is looked up in PATH, eg: `#!/usr/bin/env python3` returns `python3`
+fn shebang_interpreter(script: &str) -> Option<Vec<String>> {
+    let shebang = script.lines().next()?.strip_prefix("#!")?;
+    let mut args = shebang.split_whitespace();
+
+    let interpreter = args.next()?;
+    // only the interpreter's name is kept so that windows can find it in PATH
+    let interpreter = interpreter
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or(interpreter);
+
+    let command: Vec<String> = if interpreter == "env" {
+        args.by_ref()
+            .skip_while(|arg| arg.starts_with('-'))
+            .map(String::from)
+            .collect()
+    } else {
+        std::iter::once(interpreter.to_string())
+            .chain(args.map(String::from))
+            .collect()
+    };
+
+    if command.is_empty() {
+        return None;
+    }
+
+    Some(command)
+}
+
+/// Creates the command that runs a hook
+///
+/// On windows, which can't run scripts directly, hooks are run through the shell their extension
+/// belongs to or the interpreter in their shebang line. Everywhere else they're run directly
+fn hook_command(hook: &Path) -> Command {
+    if !cfg!(target_family = "windows") {
+        return Command::new(hook);
+    }
+
+    let extension = hook
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+
+    match extension.as_deref() {
+        Some("ps1") => {
+            let mut command = Command::new("powershell");
+            command
+                .args(["-NoProfile", "-ExecutionPolicy", "Bypass", "-File"])
+                .arg(hook);
+            command
+        }
+        Some("cmd" | "bat") => {
+            let mut command = Command::new("cmd");
+            command.arg("/C").arg(hook);
+            command
+        }
+        Some("exe" | "com") => Command::new(hook),
+        _ => {
+            let interpreter = fs::read_to_string(hook)
+                .ok()
+                .and_then(|script| shebang_interpreter(&script));
+
+            match interpreter {
+                Some(interpreter) => {
+                    let mut command = Command::new(&interpreter[0]);
+                    command.args(&interpreter[1..]).arg(hook);
+                    command
+                }
+                None => Command::new(hook),
+            }
+        }
+    }
+}
+
+/// Returns true if the hook can be run on the current platform
+///
+/// Hooks use the same suffixes as conditional groups to only run on certain targets,
+/// eg: `post_linux.sh` only runs on linux and `pre_#work.sh` only when the `work` target is enabled
+fn hook_is_valid_target(ctx: &Context, hook: &Path) -> bool {
+    let Some(filename) = hook.file_name().and_then(|name| name.to_str()) else {
+        return true;
+    };
+
+    // the extension is ignored so that `post_linux.sh` is read as `post_linux`
+    let stem = hook
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(filename);
+    let name = if dotfiles::group_ends_with_target_name(stem) {
+        stem
+    } else {
+        filename
+    };
+
+    dotfiles::group_is_valid_target(ctx, name)
+}
+
 /// Runs hooks of type PreHook or PostHook
-fn run_hook(profile: Option<String>, group: &str, hook_type: DeployStep) -> Result<(), ExitCode> {
-    let dotfiles_dir = match dotfiles::get_dotfiles_path(profile) {
+///
+/// Hooks are run with the following environment variables set:
+/// - TUCKR_GROUP: the group the hook belongs to
+/// - TUCKR_PROFILE: the profile being used, empty if using the default one
+/// - TUCKR_DOTFILES_DIR: the path to the dotfiles directory
+/// - TUCKR_TARGET: the directory the dotfiles are deployed to
+fn run_hook(
+    ctx: &Context,
+    profile: Option<String>,
+    group: &str,
+    hook_type: DeployStep,
+    opts: &HookOptions,
+) -> Result<(), ExitCode> {
+    let dotfiles_dir = match dotfiles::get_dotfiles_path(ctx, profile.clone()) {
         Ok(dir) => dir,
         Err(e) => {
             eprintln!("{e}");
@@ -84,8 +228,16 @@ fn run_hook(profile: Option<String>, group: &str, hook_type: DeployStep) -> Resu
         return Err(ReturnCode::NoSetupFolder.into());
     };
 
-    for file in group_dir {
-        let file = file.unwrap().path();
+    // hooks are run in lexicographic order so that they can be ordered with prefixes
+    // eg: pre_10_foo.sh runs before pre_20_bar.sh
+    let mut hooks: Vec<_> = group_dir.map(|file| file.unwrap().path()).collect();
+    hooks.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+
+    for file in hooks {
+        if !hook_is_valid_target(ctx, &file) {
+            continue;
+        }
+
         let filename = file.file_name().unwrap().to_str().unwrap();
         let file = file.to_str().unwrap();
         // make sure it will only run for their specific hooks
@@ -95,6 +247,7 @@ fn run_hook(profile: Option<String>, group: &str, hook_type: DeployStep) -> Resu
                     continue;
                 }
                 print_info_box(
+                    ctx,
                     &t!("info.running_prehook"),
                     group.yellow().to_string().as_str(),
                 );
@@ -105,6 +258,7 @@ fn run_hook(profile: Option<String>, group: &str, hook_type: DeployStep) -> Resu
                     continue;
                 }
                 print_info_box(
+                    ctx,
                     &t!("info.running_posthook"),
                     group.yellow().to_string().as_str(),
                 );
@@ -112,7 +266,40 @@ fn run_hook(profile: Option<String>, group: &str, hook_type: DeployStep) -> Resu
             _ => (),
         }
 
-        let mut output = match Command::new(file).spawn() {
+        #[cfg(target_family = "unix")]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = fs::metadata(file)
+                .map(|m| m.permissions().mode())
+                .unwrap_or(0);
+            if mode & 0o111 == 0 {
+                let fixed = opts.fix_permissions
+                    && fs::set_permissions(file, fs::Permissions::from_mode(mode | 0o111)).is_ok();
+
+                if !fixed {
+                    eprintln!("{}", t!("errors.hook_not_executable", hook = file).red());
+                    return Err(ExitCode::FAILURE);
+                }
+            }
+        }
+
+        let mut command = hook_command(Path::new(file));
+        command
+            .env("TUCKR_GROUP", group)
+            .env("TUCKR_PROFILE", profile.as_deref().unwrap_or_default())
+            .env("TUCKR_DOTFILES_DIR", &dotfiles_dir)
+            .env("TUCKR_TARGET", ctx.target_dir());
+
+        // hooks that can time out get their own process group so that everything they started is killed with them.
+        // the others stay in tuckr's group so they can still read from the terminal
+        #[cfg(target_family = "unix")]
+        if opts.timeout.is_some() {
+            use std::os::unix::process::CommandExt;
+            command.process_group(0);
+        }
+
+        let mut output = match command.spawn() {
             Ok(output) => output,
             Err(e) => {
                 eprintln!("{e}");
@@ -120,8 +307,46 @@ fn run_hook(profile: Option<String>, group: &str, hook_type: DeployStep) -> Resu
             }
         };
 
-        if !output.wait().unwrap().success() {
+        let status = match opts.timeout {
+            Some(timeout) => {
+                let start = Instant::now();
+                loop {
+                    if let Some(status) = output.try_wait().unwrap() {
+                        break status;
+                    }
+
+                    if start.elapsed() >= timeout {
+                        // a negative pid stands for the hook's whole process group
+                        #[cfg(target_family = "unix")]
+                        {
+                            let group = format!("-{}", output.id());
+                            _ = Command::new("kill")
+                                .args(["-s", "KILL", "--", &group])
+                                .status();
+                        }
+                        _ = output.kill();
+                        _ = output.wait();
+                        eprintln!(
+                            "{}",
+                            t!(
+                                "errors.hook_timed_out",
+                                hook = filename,
+                                seconds = timeout.as_secs()
+                            )
+                            .red()
+                        );
+                        return Err(ExitCode::FAILURE);
+                    }
+
+                    std::thread::sleep(Duration::from_millis(50));
+                }
+            }
+            None => output.wait().unwrap(),
+        };
+
+        if !status.success() {
             print_info_box(
+                ctx,
                 t!("errors.failed_to_hook").red().to_string().as_str(),
                 format!("{group} {filename}").as_str(),
             );
@@ -133,16 +358,25 @@ fn run_hook(profile: Option<String>, group: &str, hook_type: DeployStep) -> Resu
 }
 
 /// Runs hooks for specified groups
-pub fn set_cmd(
+pub(crate) fn set_cmd(
+    ctx: &Context,
     profile: Option<String>,
     groups: &[String],
     exclude: &[String],
-    force: bool,
-    adopt: bool,
-    assume_yes: bool,
+    add_opts: &symlinks::AddOptions,
+    hook_opts: &HookOptions,
 ) -> Result<(), ExitCode> {
+    let keep_going = hook_opts.keep_going;
+
+    let groups = &dotfiles::expand_group_patterns(
+        ctx,
+        profile.clone(),
+        dotfiles::DotfileType::Hooks,
+        groups,
+    );
+
     if let Some(invalid_groups) =
-        dotfiles::check_invalid_groups(profile.clone(), dotfiles::DotfileType::Hooks, groups)
+        dotfiles::check_invalid_groups(ctx, profile.clone(), dotfiles::DotfileType::Hooks, groups)
     {
         for group in invalid_groups {
             eprintln!("{}", t!("errors.x_doesnt_exist", x = group).red());
@@ -151,7 +385,7 @@ pub fn set_cmd(
         return Err(ReturnCode::NoSuchFileOrDir.into());
     }
 
-    let hooks_dir = match dotfiles::get_dotfiles_path(profile.clone()) {
+    let hooks_dir = match dotfiles::get_dotfiles_path(ctx, profile.clone()) {
         Ok(dir) => dir.join("Hooks"),
         Err(e) => {
             eprintln!("{e}",);
@@ -159,21 +393,67 @@ pub fn set_cmd(
         }
     };
 
-    let run_deploy_steps = |step: DeployStages, group: &Dotfile| -> Result<(), ExitCode> {
+    // every group is added along with its dependencies
+    // but only the dependencies that have hooks get them run
+    let resolved_groups = match dotfiles::resolve_group_dependencies(ctx, profile.clone(), groups) {
+        Ok(resolved) => resolved,
+        Err(err) => {
+            eprintln!("{}", err.red());
+            return Err(ReturnCode::NoSuchFileOrDir.into());
+        }
+    };
+    if resolved_groups.len() != groups.len() {
+        symlinks::print_resolved_group_order(ctx, &resolved_groups);
+    }
+    let groups = &resolved_groups
+        .iter()
+        .filter(|group| groups.contains(group) || hooks_dir.join(group).is_dir())
+        .cloned()
+        .collect::<Vec<_>>();
+
+    // the changes are confirmed once for every group instead of before each of them is added
+    let add_opts = &if add_opts.interactive_apply {
+        if !symlinks::confirm_add_plan(ctx, profile.clone(), &resolved_groups, exclude, add_opts)? {
+            return Ok(());
+        }
+
+        symlinks::AddOptions {
+            interactive_apply: false,
+            assume_yes: true,
+            ..add_opts.clone()
+        }
+    } else {
+        add_opts.clone()
+    };
+
+    let secrets = SecretsDeployer::new(ctx, profile.clone(), hook_opts.passphrase_file.clone());
+
+    // groups with a failed step, their dotfiles are left out when the next groups are symlinked
+    let failed_groups = std::cell::RefCell::new(Vec::new());
+
+    // a failed step skips the group's remaining steps, e.g. nothing is symlinked after a failed pre-hook
+    let run_deploy_steps = |mut steps: DeployStages, group: &Dotfile| -> Result<(), ExitCode> {
         if !group.is_valid_target() {
             return Ok(());
         }
 
-        for i in step {
-            match i {
+        let mut result = Ok(());
+
+        while let Some(step) = steps.next() {
+            let step_result = match step {
                 DeployStep::Initialize => return Ok(()),
 
-                DeployStep::PreHook => {
-                    run_hook(profile.clone(), &group.group_name, DeployStep::PreHook)?;
-                }
+                DeployStep::PreHook => run_hook(
+                    ctx,
+                    profile.clone(),
+                    &group.group_name,
+                    DeployStep::PreHook,
+                    hook_opts,
+                ),
 
                 DeployStep::Symlink => {
                     if dotfiles::check_invalid_groups(
+                        ctx,
                         profile.clone(),
                         dotfiles::DotfileType::Configs,
                         &[&group.group_name],
@@ -184,19 +464,51 @@ pub fn set_cmd(
                     }
 
                     print_info_box(
+                        ctx,
                         &t!("info.symlinking_group"),
                         group.group_name.yellow().to_string().as_str(),
                     );
-                    symlinks::add_cmd(profile.clone(), groups, exclude, force, adopt, assume_yes)?;
+                    // only the group and its dependencies, the groups after it haven't had their pre hooks run yet
+                    let exclude = [exclude, &failed_groups.borrow()].concat();
+                    symlinks::add_cmd(
+                        ctx,
+                        profile.clone(),
+                        std::slice::from_ref(&group.group_name),
+                        &exclude,
+                        add_opts,
+                    )
                 }
 
-                DeployStep::PostHook => {
-                    run_hook(profile.clone(), &group.group_name, DeployStep::PostHook)?
+                DeployStep::Secrets => {
+                    if !hook_opts.with_secrets || !secrets.has_secrets(&group.group_name) {
+                        continue;
+                    }
+
+                    print_info_box(
+                        ctx,
+                        &t!("info.decrypting_group"),
+                        group.group_name.yellow().to_string().as_str(),
+                    );
+                    secrets.deploy_group(&group.group_name, add_opts.force)
                 }
+
+                DeployStep::PostHook => run_hook(
+                    ctx,
+                    profile.clone(),
+                    &group.group_name,
+                    DeployStep::PostHook,
+                    hook_opts,
+                ),
+            };
+
+            if let Err(err) = step_result {
+                result = Err(err);
+                failed_groups.borrow_mut().push(group.group_name.clone());
+                steps.finish();
             }
         }
 
-        Ok(())
+        result
     };
 
     #[derive(Tabled)]
@@ -219,11 +531,12 @@ pub fn set_cmd(
     };
 
     let mut hooks_summary: Vec<RunStatus> = Vec::new();
+    let mut any_failed = false;
 
     if groups.contains(&'*'.to_string()) {
         for folder in fs::read_dir(hooks_dir).unwrap() {
             let folder = folder.unwrap().path();
-            let Ok(group) = Dotfile::try_from(folder.clone()) else {
+            let Ok(group) = Dotfile::new(ctx, folder.clone()) else {
                 eprintln!(
                     "{}",
                     format!("Got an invalid group: {}", folder.display()).red()
@@ -231,8 +544,14 @@ pub fn set_cmd(
                 return Err(ExitCode::FAILURE);
             };
 
+            if dotfiles::group_is_excluded(&group.group_name, exclude) {
+                continue;
+            }
+
+            let succeeded = run_deploy_steps(DeployStages::new(), &group).is_ok();
+            any_failed |= !succeeded;
             hooks_summary.push(RunStatus {
-                succeeded: get_symbol(run_deploy_steps(DeployStages::new(), &group).is_ok()),
+                succeeded: get_symbol(succeeded),
                 group: group.group_name,
             })
         }
@@ -251,14 +570,21 @@ pub fn set_cmd(
                 }
             }
 
-            // sorting is necessary to ensure that the conditional groups are run right after their base group
-            groups.sort();
+            groups.retain(|group| !dotfiles::group_is_excluded(group, exclude));
+
+            // sorting is necessary to ensure that the conditional groups are run right after their base group,
+            // base groups are kept in the order their dependencies were resolved in
+            groups.sort_by_key(|group| {
+                let base_group = dotfiles::group_without_target(group);
+                let position = resolved_groups.iter().position(|g| g == base_group);
+                (position.unwrap_or(usize::MAX), group.clone())
+            });
             groups
         };
 
         for group in groups {
             let hook_path = hooks_dir.join(group);
-            let Ok(group) = Dotfile::try_from(hook_path.clone()) else {
+            let Ok(group) = Dotfile::new(ctx, hook_path.clone()) else {
                 eprintln!(
                     "{}",
                     t!("errors.got_invalid_group", group = hook_path.display()).red()
@@ -266,14 +592,16 @@ pub fn set_cmd(
                 return Err(ExitCode::FAILURE);
             };
 
+            let succeeded = run_deploy_steps(DeployStages::new(), &group).is_ok();
+            any_failed |= !succeeded;
             hooks_summary.push(RunStatus {
-                succeeded: get_symbol(run_deploy_steps(DeployStages::new(), &group).is_ok()),
+                succeeded: get_symbol(succeeded),
                 group: group.group_name,
             })
         }
     }
 
-    if groups.len() > 1 {
+    if groups.len() > 1 && ctx.verbosity != dotfiles::Verbosity::Quiet {
         use tabled::{object::Segment, Alignment, Margin, Modify, Style};
 
         let mut hooks_list = Table::new(hooks_summary);
@@ -286,7 +614,124 @@ pub fn set_cmd(
         println!("{hooks_list}");
     }
 
-    Ok(())
+    // failures are only reflected in the exit code with keep_going, they're shown in the summary either way
+    if any_failed && keep_going {
+        Err(ExitCode::FAILURE)
+    } else {
+        Ok(())
+    }
+}
+
+/// Removes the groups' dotfiles and sets them up again by running `set_cmd` on them,
+/// groups that don't have hooks are only added again
+///
+/// The groups are checked before anything is removed and nothing is set up if removing fails
+pub(crate) fn reload_cmd(
+    ctx: &Context,
+    profile: Option<String>,
+    groups: &[String],
+    exclude: &[String],
+    add_opts: &symlinks::AddOptions,
+    hook_opts: &HookOptions,
+) -> Result<(), ExitCode> {
+    use dotfiles::DotfileType;
+
+    // a group can have dotfiles, hooks or both so patterns are expanded in both of them
+    let mut expanded: Vec<String> = Vec::new();
+    for group in groups {
+        let mut matches: Vec<_> = [DotfileType::Configs, DotfileType::Hooks]
+            .into_iter()
+            .flat_map(|dtype| {
+                dotfiles::expand_group_patterns(
+                    ctx,
+                    profile.clone(),
+                    dtype,
+                    std::slice::from_ref(group),
+                )
+            })
+            .filter(|matched| matched != group)
+            .collect();
+        if matches.is_empty() {
+            matches.push(group.clone());
+        }
+
+        for group in matches {
+            if !expanded.contains(&group) {
+                expanded.push(group);
+            }
+        }
+    }
+
+    let contains = |dtype, group: &String| {
+        group == "*" || dotfiles::dotfile_contains(ctx, profile.clone(), dtype, group)
+    };
+    let invalid_groups: Vec<_> = expanded
+        .iter()
+        .filter(|group| {
+            !contains(DotfileType::Configs, group) && !contains(DotfileType::Hooks, group)
+        })
+        .collect();
+    if !invalid_groups.is_empty() {
+        for group in invalid_groups {
+            eprintln!("{}", t!("errors.x_doesnt_exist", x = group).red());
+        }
+
+        return Err(ReturnCode::NoSuchFileOrDir.into());
+    }
+
+    let (config_groups, hook_groups): (Vec<_>, Vec<_>) = (
+        expanded
+            .iter()
+            .filter(|group| contains(DotfileType::Configs, group))
+            .cloned()
+            .collect(),
+        expanded
+            .iter()
+            .filter(|group| contains(DotfileType::Hooks, group))
+            .cloned()
+            .collect(),
+    );
+
+    // groups that only have hooks have no dotfiles to remove
+    if !config_groups.is_empty() {
+        symlinks::remove_cmd(
+            ctx,
+            profile.clone(),
+            &config_groups,
+            exclude,
+            false,
+            false,
+            false,
+            false,
+        )?;
+    }
+
+    // groups without hooks have nothing for `set_cmd` to run so they're only added again
+    let hookless_groups: Vec<_> = config_groups
+        .iter()
+        .filter(|group| *group == "*" || !hook_groups.contains(group))
+        .cloned()
+        .collect();
+    if !hookless_groups.is_empty() {
+        symlinks::add_cmd(ctx, profile.clone(), &hookless_groups, exclude, add_opts)?;
+
+        if hook_opts.with_secrets {
+            let secrets =
+                SecretsDeployer::new(ctx, profile.clone(), hook_opts.passphrase_file.clone());
+            for group in hookless_groups
+                .iter()
+                .filter(|group| secrets.has_secrets(group))
+            {
+                secrets.deploy_group(group, add_opts.force)?;
+            }
+        }
+    }
+
+    if hook_groups.is_empty() {
+        return Ok(());
+    }
+
+    set_cmd(ctx, profile, &hook_groups, exclude, add_opts, hook_opts)
 }
 
 #[cfg(test)]
@@ -302,6 +747,255 @@ mod tests {
         steps.next();
         assert!(steps.0 == DeployStep::Symlink);
         steps.next();
+        assert!(steps.0 == DeployStep::Secrets);
+        steps.next();
         assert!(steps.0 == DeployStep::PostHook);
+        assert!(steps.next().is_none());
+
+        let mut steps = DeployStages::new();
+        steps.next();
+        steps.finish();
+        assert!(steps.next().is_none());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn run_hooks_in_order() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let ctx = &Context::default();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(ctx, None).unwrap();
+        let hooks_dir = dotfiles_dir.join("Hooks").join("ordered");
+        fs::create_dir_all(&hooks_dir).unwrap();
+
+        let output = dotfiles_dir.join("output");
+        for hook in ["pre_20_b", "pre_10_a", "post_05_d", "pre_30_c"] {
+            let hook_path = hooks_dir.join(hook);
+            fs::write(
+                &hook_path,
+                format!(
+                    "#!/bin/sh\necho {hook} $TUCKR_GROUP >> '{}'\n",
+                    output.display()
+                ),
+            )
+            .unwrap();
+            fs::set_permissions(&hook_path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        run_hook(
+            ctx,
+            None,
+            "ordered",
+            DeployStep::PreHook,
+            &Default::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(&output).unwrap(),
+            "pre_10_a ordered\npre_20_b ordered\npre_30_c ordered\n"
+        );
+
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn kill_hooks_that_time_out() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let ctx = &Context::default();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(ctx, None).unwrap();
+        let hooks_dir = dotfiles_dir.join("Hooks").join("slow");
+        fs::create_dir_all(&hooks_dir).unwrap();
+
+        // the hook starts a process of its own that would outlive it
+        let marker = dotfiles_dir.join("marker");
+        let hook_path = hooks_dir.join("pre.sh");
+        fs::write(
+            &hook_path,
+            format!(
+                "#!/bin/sh\n(sleep 0.5; touch '{}') &\nexec sleep 10\n",
+                marker.display()
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&hook_path, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let start = Instant::now();
+        let opts = HookOptions {
+            timeout: Some(Duration::from_millis(200)),
+            ..Default::default()
+        };
+        assert!(run_hook(ctx, None, "slow", DeployStep::PreHook, &opts).is_err());
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        // it was killed along with the hook
+        std::thread::sleep(Duration::from_secs(1).saturating_sub(start.elapsed()));
+        assert!(!marker.exists());
+
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn keep_going_after_failed_hooks() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&Context::default(), None).unwrap();
+        let target_dir = dotfiles_dir.with_file_name("target");
+        let ctx = &Context {
+            target_dir: Some(target_dir.clone()),
+            ..Default::default()
+        };
+        crate::fileops::init_cmd(ctx, None, false).unwrap();
+
+        for (group, hook) in [("broken", "exit 1"), ("working", "exit 0")] {
+            let hooks_dir = dotfiles_dir.join("Hooks").join(group);
+            fs::create_dir_all(&hooks_dir).unwrap();
+            fs::write(hooks_dir.join("pre.sh"), format!("#!/bin/sh\n{hook}\n")).unwrap();
+            fs::set_permissions(hooks_dir.join("pre.sh"), fs::Permissions::from_mode(0o755))
+                .unwrap();
+
+            let group_dir = dotfiles_dir.join("Configs").join(group);
+            fs::create_dir_all(&group_dir).unwrap();
+            fs::write(group_dir.join(group), "").unwrap();
+        }
+
+        let add_opts = symlinks::AddOptions {
+            assume_yes: true,
+            ..Default::default()
+        };
+        // the group that fails comes last so that symlinking the first one can't take it along
+        let groups = ["working".to_string(), "broken".to_string()];
+        let hook_opts = HookOptions {
+            keep_going: true,
+            ..Default::default()
+        };
+        assert!(set_cmd(ctx, None, &groups, &[], &add_opts, &hook_opts).is_err());
+        // the failed pre-hook skips the rest of its group but not the other group
+        assert!(!target_dir.join("broken").is_symlink());
+        assert!(target_dir.join("working").is_symlink());
+
+        // without keep_going the failure is only shown in the summary
+        assert!(set_cmd(ctx, None, &groups, &[], &add_opts, &Default::default()).is_ok());
+        assert!(!target_dir.join("broken").is_symlink());
+
+        fs::remove_dir_all(dotfiles_dir.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn reload_group_without_hooks() {
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&Context::default(), None).unwrap();
+        let target_dir = dotfiles_dir.with_file_name("target");
+        let ctx = &Context {
+            target_dir: Some(target_dir.clone()),
+            ..Default::default()
+        };
+        crate::fileops::init_cmd(ctx, None, false).unwrap();
+
+        let group_dir = dotfiles_dir.join("Configs").join("nohooks");
+        fs::create_dir_all(&group_dir).unwrap();
+        fs::write(group_dir.join("config"), "").unwrap();
+
+        let add_opts = symlinks::AddOptions {
+            assume_yes: true,
+            ..Default::default()
+        };
+        let groups = ["nohooks".to_string()];
+        symlinks::add_cmd(ctx, None, &groups, &[], &add_opts).unwrap();
+        reload_cmd(ctx, None, &groups, &[], &add_opts, &Default::default()).unwrap();
+        assert_eq!(
+            fs::read_link(target_dir.join("config")).unwrap(),
+            group_dir.join("config")
+        );
+
+        assert!(reload_cmd(
+            ctx,
+            None,
+            &["missing".into()],
+            &[],
+            &add_opts,
+            &Default::default()
+        )
+        .is_err());
+
+        fs::remove_dir_all(dotfiles_dir.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn skip_hooks_for_other_targets() {
+        let ctx = &Context::default();
+        let is_valid = |hook: &str| super::hook_is_valid_target(ctx, Path::new(hook));
+        let other_os = if std::env::consts::OS == "windows" {
+            "macos"
+        } else {
+            "windows"
+        };
+
+        assert!(is_valid("pre_10_setup.sh"));
+        assert!(is_valid(&format!("post_{}.sh", std::env::consts::OS)));
+        assert!(!is_valid(&format!("post_{other_os}.sh")));
+
+        assert!(!is_valid("pre_#work.sh"));
+        let ctx = &Context {
+            custom_targets: vec!["work".into()],
+            ..Default::default()
+        };
+        assert!(super::hook_is_valid_target(ctx, Path::new("pre_#work.sh")));
+    }
+
+    #[test]
+    fn parse_shebang_interpreters() {
+        let interpreter = |script: &str| super::shebang_interpreter(script);
+
+        assert_eq!(interpreter("#!/bin/sh\necho"), Some(vec!["sh".into()]));
+        assert_eq!(
+            interpreter("#!/usr/bin/env python3\n"),
+            Some(vec!["python3".into()])
+        );
+        assert_eq!(
+            interpreter("#!/usr/bin/env -S bash -e\n"),
+            Some(vec!["bash".into(), "-e".into()])
+        );
+        assert_eq!(
+            interpreter("#!/bin/bash -eu"),
+            Some(vec!["bash".into(), "-eu".into()])
+        );
+        assert_eq!(interpreter("echo no shebang"), None);
+        assert_eq!(interpreter("#!/usr/bin/env"), None);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn non_executable_hooks() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let ctx = &Context::default();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(ctx, None).unwrap();
+        let hooks_dir = dotfiles_dir.join("Hooks").join("noexec");
+        fs::create_dir_all(&hooks_dir).unwrap();
+
+        let hook_path = hooks_dir.join("pre.sh");
+        fs::write(&hook_path, "#!/bin/sh\ntrue\n").unwrap();
+        fs::set_permissions(&hook_path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        assert!(run_hook(
+            ctx,
+            None,
+            "noexec",
+            DeployStep::PreHook,
+            &Default::default()
+        )
+        .is_err());
+
+        let opts = HookOptions {
+            fix_permissions: true,
+            ..Default::default()
+        };
+        assert!(run_hook(ctx, None, "noexec", DeployStep::PreHook, &opts).is_ok());
+        let mode = fs::metadata(&hook_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+
+        fs::remove_dir_all(dotfiles_dir).unwrap();
     }
 }
@@ -0,0 +1,34 @@
+//! Tuckr is a set of tools to manage dotfiles
+//!
+//! Tuckr separates dotfiles into 3 different directories:
+//! - dotfiles/Configs - stores config files
+//! - dotfiles/Hooks - stores scripts that configure the environment
+//! - dotfiles/Secrets - stores encrypted/sensitive files
+//!
+//! Each of these of these directories contain directories with the name of the groups or logical
+//! groups which contains all user scripts, configs and scripts, these are used to label them on tuckr
+//! so you can add or remove them anytime
+//!
+//! [`status`], [`add`] and [`remove`] return reports of what was found or done so that tuckr
+//! can be used from other programs. [`SymlinkHandler`] can be used directly to scan the dotfiles
+//! once and work on them group by group.
+//!
+//! Options such as the target directory are passed explicitly through a [`Context`],
+//! [`Context::default`] deploys to the user's home directory.
+
+#[doc(hidden)]
+pub mod cli;
+mod dotfiles;
+mod fileops;
+mod filetree;
+mod hooks;
+mod secrets;
+mod symlinks;
+
+pub use dotfiles::{Context, Dotfile, DotfileError, Verbosity};
+pub use symlinks::{
+    add, remove, status, Action, ActionKind, AddOptions, ConflictReason, ConflictReport,
+    DeployMode, Report, StatusReport, SymlinkError, SymlinkHandler,
+};
+
+rust_i18n::i18n!("locales", minify_key = true, fallback = "en");
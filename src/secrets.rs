@@ -2,9 +2,10 @@
 //!
 //! Encrypts files into dotfiles/Secrets using the chacha20poly1305 algorithm
 
-use crate::dotfiles::{self, Dotfile, ReturnCode};
+use crate::dotfiles::{self, Context, Dotfile, ReturnCode};
 use crate::fileops::DirWalk;
-use chacha20poly1305::{aead::Aead, AeadCore, KeyInit, XChaCha20Poly1305};
+use chacha20poly1305::aead::{Aead, Payload};
+use chacha20poly1305::{AeadCore, KeyInit, XChaCha20Poly1305};
 use owo_colors::OwoColorize;
 use rand::rngs;
 use rust_i18n::t;
@@ -16,12 +17,18 @@ use std::process::ExitCode;
 struct SecretsHandler {
     dotfiles_dir: PathBuf,
     key: chacha20poly1305::Key,
-    nonce: chacha20poly1305::XNonce,
 }
 
 impl SecretsHandler {
-    fn try_new(profile: Option<String>) -> Result<Self, ExitCode> {
-        let dotfiles_dir = match dotfiles::get_dotfiles_path(profile) {
+    /// Creates a handler whose key is derived from `key_file`'s contents if supplied,
+    /// otherwise from the password, see `read_input_key`
+    fn try_new(
+        ctx: &Context,
+        profile: Option<String>,
+        key_file: Option<&Path>,
+        passphrase_file: Option<&Path>,
+    ) -> Result<Self, ExitCode> {
+        let dotfiles_dir = match dotfiles::get_dotfiles_path(ctx, profile) {
             Ok(path) => path,
             Err(e) => {
                 eprintln!("{e}");
@@ -29,21 +36,28 @@ impl SecretsHandler {
             }
         };
 
+        let input_key = read_input_key(
+            key_file,
+            passphrase_file,
+            "TUCKR_PASSPHRASE",
+            &t!("info.password"),
+        )?;
+
+        Ok(Self::with_key(dotfiles_dir, input_key.as_slice()))
+    }
+
+    fn with_key(dotfiles_dir: PathBuf, input_key: &[u8]) -> Self {
         // makes a hash of the password so that it can fit on the 256 bit buffer used by the
         // algorithm
-        let input_key = rpassword::prompt_password(format!("{}: ", t!("info.password"))).unwrap();
-        let input_hash = Sha256::digest(input_key);
-
-        Ok(SecretsHandler {
+        SecretsHandler {
             dotfiles_dir,
-            key: input_hash,
-            nonce: XChaCha20Poly1305::generate_nonce(&mut rngs::OsRng),
-        })
+            key: Sha256::digest(input_key),
+        }
     }
 
-    /// takes a path to a file and returns its encrypted content
+    /// takes a path to a file and returns the content of its secret file
     fn encrypt(&self, dotfile: &Path) -> Result<Vec<u8>, ExitCode> {
-        let cipher = XChaCha20Poly1305::new(&self.key);
+        let mode = file_mode(dotfile);
         let Ok(dotfile) = fs::read(dotfile) else {
             eprintln!(
                 "{}",
@@ -52,24 +66,62 @@ impl SecretsHandler {
             return Err(ReturnCode::NoSuchFileOrDir.into());
         };
 
-        match cipher.encrypt(&self.nonce, dotfile.as_slice()) {
-            Ok(f) => Ok(f),
+        self.encrypt_data(&dotfile, mode)
+    }
+
+    /// takes some data and returns the content of a secret file storing it
+    ///
+    /// A secret file is made of an optional mode header, a 24 byte nonce and the encrypted data.
+    /// Every file gets its own random nonce since reusing one with the same key breaks the encryption,
+    /// the mode header is authenticated along with the data so that it can't be tampered with
+    fn encrypt_data(&self, data: &[u8], mode: Option<u32>) -> Result<Vec<u8>, ExitCode> {
+        let mut secret_file = Vec::new();
+        if let Some(mode) = mode {
+            secret_file.extend_from_slice(MODE_HEADER);
+            secret_file.extend_from_slice(&(mode & 0o777).to_le_bytes());
+        }
+
+        let cipher = XChaCha20Poly1305::new(&self.key);
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut rngs::OsRng);
+        let payload = Payload {
+            msg: data,
+            aad: &secret_file,
+        };
+        let mut encrypted = match cipher.encrypt(&nonce, payload) {
+            Ok(f) => f,
             Err(e) => {
                 eprintln!("{}", e.red());
-                Err(ReturnCode::EncryptionFailed.into())
+                return Err(ReturnCode::EncryptionFailed.into());
             }
-        }
+        };
+
+        secret_file.extend_from_slice(&nonce);
+        secret_file.append(&mut encrypted);
+
+        Ok(secret_file)
     }
 
-    /// takes a path to a file and returns its decrypted content
-    fn decrypt(&self, dotfile: &str) -> Result<Vec<u8>, ExitCode> {
+    /// takes the content of a secret file and returns it decrypted
+    fn decrypt_data(&self, dotfile: &[u8]) -> Result<Vec<u8>, ExitCode> {
         let cipher = XChaCha20Poly1305::new(&self.key);
-        let dotfile = fs::read(dotfile).expect("Couldn't read dotfile");
+        let SecretHeader {
+            authenticated_header,
+            rest: dotfile,
+            ..
+        } = split_mode_header(dotfile);
+        if dotfile.len() < 24 {
+            eprintln!("{}", t!("errors.wrong_password").red());
+            return Err(ReturnCode::DecryptionFailed.into());
+        }
 
         // extracts the nonce from the first 24 bytes in the file
         let (nonce, contents) = dotfile.split_at(24);
 
-        match cipher.decrypt(nonce.into(), contents) {
+        let payload = Payload {
+            msg: contents,
+            aad: authenticated_header,
+        };
+        match cipher.decrypt(nonce.into(), payload) {
             Ok(f) => Ok(f),
             Err(_) => {
                 eprintln!("{}", t!("errors.wrong_password").red());
@@ -77,14 +129,373 @@ impl SecretsHandler {
             }
         }
     }
+
+    /// decrypts a secret file into dest, restoring the permissions of the original file
+    fn decrypt_to(&self, secret: &Path, dest: &Path) -> Result<(), ExitCode> {
+        let Ok(secret_file) = fs::read(secret) else {
+            eprintln!(
+                "{}",
+                t!("errors.x_doesnt_exist", x = secret.display()).red()
+            );
+            return Err(ReturnCode::NoSuchFileOrDir.into());
+        };
+
+        let decrypted = zeroize::Zeroizing::new(self.decrypt_data(&secret_file)?);
+        let mode = split_mode_header(&secret_file).mode;
+        // secrets encrypted before modes were stored get a safe default if they hold keys
+        let mode = mode.or_else(|| looks_like_private_key(secret, &decrypted).then_some(0o600));
+
+        if let Err(err) = write_decrypted(dest, &decrypted, mode) {
+            eprintln!("{}", format!("{}: {err}", dest.display()).red());
+            return Err(ReturnCode::DecryptionFailed.into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Decrypts the secrets of the groups deployed by `tuckr set --with-secrets`
+///
+/// Secrets are decrypted to the same targets as the group's dotfiles would be symlinked to.
+/// The password is only asked for once, when the first group that has secrets is deployed
+pub(crate) struct SecretsDeployer {
+    ctx: Context,
+    profile: Option<String>,
+    passphrase_file: Option<PathBuf>,
+    handler: std::cell::OnceCell<Result<SecretsHandler, ExitCode>>,
+}
+
+impl SecretsDeployer {
+    pub(crate) fn new(
+        ctx: &Context,
+        profile: Option<String>,
+        passphrase_file: Option<PathBuf>,
+    ) -> Self {
+        Self {
+            ctx: ctx.clone(),
+            profile,
+            passphrase_file,
+            handler: std::cell::OnceCell::new(),
+        }
+    }
+
+    /// Returns whether the group has secrets that can be deployed
+    pub(crate) fn has_secrets(&self, group: &str) -> bool {
+        let ctx = &self.ctx;
+        dotfiles::get_dotfiles_path(ctx, self.profile.clone())
+            .is_ok_and(|dir| dir.join("Secrets").join(group).is_dir())
+    }
+
+    /// Decrypts every secret in the group to its target
+    ///
+    /// force: overwrites targets that already exist with other contents instead of treating them as conflicts
+    pub(crate) fn deploy_group(&self, group: &str, force: bool) -> Result<(), ExitCode> {
+        let ctx = &self.ctx;
+        let handler = self
+            .handler
+            .get_or_init(|| {
+                SecretsHandler::try_new(
+                    ctx,
+                    self.profile.clone(),
+                    None,
+                    self.passphrase_file.as_deref(),
+                )
+            })
+            .as_ref()
+            .map_err(|err| *err)?;
+
+        let group_dir = handler.dotfiles_dir.join("Secrets").join(group);
+        let dotfiles_dir = fs::canonicalize(&handler.dotfiles_dir)
+            .unwrap_or_else(|_| handler.dotfiles_dir.clone());
+        let mut result = Ok(());
+
+        for secret in DirWalk::new(group_dir) {
+            if secret.is_dir() {
+                continue;
+            }
+
+            let Ok(dest) = Dotfile::new(ctx, secret.clone()).map(|f| f.to_target_path()) else {
+                continue;
+            };
+
+            // writing through a symlink would overwrite the file it points to,
+            // which could be one of the group's dotfiles
+            if dest.is_symlink() {
+                eprintln!(
+                    "{}",
+                    t!("errors.x_already_exists", x = dest.display()).red()
+                );
+                result = Err(ReturnCode::DecryptionFailed.into());
+                continue;
+            }
+
+            if let Some(parent) = dest.parent() {
+                // a symlinked directory would make the decrypted secret end up in the dotfiles,
+                // this is checked on the closest directory that exists so nothing is created in them
+                let existing_parent = parent.ancestors().find(|dir| dir.exists());
+                if existing_parent
+                    .and_then(|dir| fs::canonicalize(dir).ok())
+                    .is_some_and(|dir| dir.starts_with(&dotfiles_dir))
+                {
+                    eprintln!(
+                        "{}",
+                        t!(
+                            "errors.x_would_be_decrypted_into_dotfiles",
+                            x = dest.display()
+                        )
+                        .red()
+                    );
+                    result = Err(ReturnCode::DecryptionFailed.into());
+                    continue;
+                }
+
+                if let Err(err) = fs::create_dir_all(parent) {
+                    eprintln!("{}", format!("{}: {err}", parent.display()).red());
+                    result = Err(ReturnCode::DecryptionFailed.into());
+                    continue;
+                }
+            }
+
+            // a target that already holds the decrypted secret is ours, anything else is a conflict
+            if dest.exists() && !force {
+                let decrypted = fs::read(&secret)
+                    .map_err(|_| ExitCode::FAILURE)
+                    .and_then(|secret_file| handler.decrypt_data(&secret_file))
+                    .map(zeroize::Zeroizing::new);
+                match decrypted {
+                    Ok(decrypted) if fs::read(&dest).is_ok_and(|target| target == *decrypted) => (),
+                    Ok(_) => {
+                        eprintln!(
+                            "{}",
+                            t!("errors.x_already_exists", x = dest.display()).red()
+                        );
+                        result = Err(ReturnCode::DecryptionFailed.into());
+                    }
+                    Err(err) => result = Err(err),
+                }
+                continue;
+            }
+
+            if let Err(err) = handler.decrypt_to(&secret, &dest) {
+                result = Err(err);
+            }
+        }
+
+        result
+    }
+}
+
+/// Reads what the key is derived from, the contents of `key_file` if supplied,
+/// otherwise the password is read with `read_passphrase`
+fn read_input_key(
+    key_file: Option<&Path>,
+    passphrase_file: Option<&Path>,
+    env_var: &str,
+    prompt: &str,
+) -> Result<zeroize::Zeroizing<Vec<u8>>, ExitCode> {
+    let Some(key_file) = key_file else {
+        let passphrase = read_passphrase(passphrase_file, env_var, prompt)?;
+        return Ok(zeroize::Zeroizing::new(passphrase.as_bytes().to_vec()));
+    };
+
+    match fs::read(key_file) {
+        Ok(key) => Ok(zeroize::Zeroizing::new(key)),
+        Err(err) => {
+            eprintln!(
+                "{}",
+                t!(
+                    "errors.could_not_read_key_file",
+                    file = key_file.display(),
+                    err = err
+                )
+                .red()
+            );
+            Err(ExitCode::FAILURE)
+        }
+    }
+}
+
+/// Prompts for a password without echoing it
+fn prompt_password(prompt: &str) -> Result<zeroize::Zeroizing<String>, ExitCode> {
+    rpassword::prompt_password(format!("{prompt}: "))
+        .map(zeroize::Zeroizing::new)
+        .map_err(|err| {
+            eprintln!("{}", err.red());
+            ExitCode::FAILURE
+        })
+}
+
+/// Reads the password from `passphrase_file`, or the `env_var` environment variable,
+/// the user is only prompted for it if neither of them is set
+///
+/// A single trailing newline is removed since files and `echo` usually add one
+fn read_passphrase(
+    passphrase_file: Option<&Path>,
+    env_var: &str,
+    prompt: &str,
+) -> Result<zeroize::Zeroizing<String>, ExitCode> {
+    let passphrase = match passphrase_file {
+        Some(file) => match fs::read_to_string(file) {
+            Ok(passphrase) => passphrase,
+            Err(err) => {
+                eprintln!(
+                    "{}",
+                    t!(
+                        "errors.could_not_read_passphrase_file",
+                        file = file.display(),
+                        err = err
+                    )
+                    .red()
+                );
+                return Err(ExitCode::FAILURE);
+            }
+        },
+        None => match std::env::var(env_var) {
+            Ok(passphrase) => passphrase,
+            Err(_) => return prompt_password(prompt),
+        },
+    };
+
+    let mut passphrase = zeroize::Zeroizing::new(passphrase);
+    if passphrase.ends_with('\n') {
+        passphrase.pop();
+        if passphrase.ends_with('\r') {
+            passphrase.pop();
+        }
+    }
+
+    Ok(passphrase)
+}
+
+/// Marks secret files that store the permissions of their original file.
+/// The header is authenticated along with the encrypted data
+const MODE_HEADER: &[u8] = b"tuckr-mode2\0";
+
+/// The parts of a secret file
+struct SecretHeader<'a> {
+    /// Permissions of the original file
+    mode: Option<u32>,
+    /// The part of the header that's authenticated along with the encrypted data
+    authenticated_header: &'a [u8],
+    /// The nonce followed by the encrypted data
+    rest: &'a [u8],
+}
+
+/// Splits the content of a secret file into its stored mode and the encrypted data
+fn split_mode_header(secret_file: &[u8]) -> SecretHeader<'_> {
+    let no_header = SecretHeader {
+        mode: None,
+        authenticated_header: &[],
+        rest: secret_file,
+    };
+
+    let Some(rest) = secret_file.strip_prefix(MODE_HEADER) else {
+        return no_header;
+    };
+
+    let Some((mode, rest)) = rest.split_first_chunk::<4>() else {
+        return no_header;
+    };
+
+    let header_len = secret_file.len() - rest.len();
+    SecretHeader {
+        mode: Some(u32::from_le_bytes(*mode) & 0o777),
+        authenticated_header: &secret_file[..header_len],
+        rest,
+    }
+}
+
+/// Writes decrypted data to `dest` without it ever being readable by more users than `mode` allows
+///
+/// The data is written to a new file that is created with the mode and then renamed over `dest`
+fn write_decrypted(dest: &Path, data: &[u8], mode: Option<u32>) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut tmp_file = dest.as_os_str().to_owned();
+    tmp_file.push(".tuckr-tmp");
+    let tmp_file = PathBuf::from(tmp_file);
+    let _ = fs::remove_file(&tmp_file);
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(mode.unwrap_or(0o666) & 0o777);
+    }
+
+    let result = options.open(&tmp_file).and_then(|mut file| {
+        // the mode is set again since the umask might have taken permissions away
+        #[cfg(target_family = "unix")]
+        if let Some(mode) = mode {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(fs::Permissions::from_mode(mode & 0o777))?;
+        }
+
+        #[cfg(not(target_family = "unix"))]
+        let _ = mode;
+
+        file.write_all(data)?;
+        fs::rename(&tmp_file, dest)
+    });
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_file);
+    }
+    result
+}
+
+/// Returns the permission bits of a file on platforms that support them
+fn file_mode(file: &Path) -> Option<u32> {
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::metadata(file)
+            .ok()
+            .map(|metadata| metadata.permissions().mode() & 0o777)
+    }
+
+    #[cfg(not(target_family = "unix"))]
+    {
+        let _ = file;
+        None
+    }
+}
+
+fn looks_like_private_key(path: &Path, content: &[u8]) -> bool {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let is_key_name = (file_name.starts_with("id_") && !file_name.ends_with(".pub"))
+        || file_name.ends_with(".pem")
+        || file_name.ends_with(".key");
+
+    let content = String::from_utf8_lossy(content);
+    is_key_name || (content.contains("-----BEGIN") && content.contains("PRIVATE KEY-----"))
 }
 
 /// Encrypts secrets
-pub fn encrypt_cmd(
+pub(crate) fn encrypt_cmd(
+    ctx: &Context,
     profile: Option<String>,
     group: &str,
     dotfiles: &[String],
+    exclude: &[String],
+    key_file: Option<&Path>,
+    passphrase_file: Option<&Path>,
 ) -> Result<(), ExitCode> {
+    let mut exclude_patterns = Vec::new();
+    for pattern in exclude {
+        match glob::Pattern::new(pattern) {
+            Ok(pattern) => exclude_patterns.push(pattern),
+            Err(err) => {
+                eprintln!(
+                    "{}",
+                    t!("errors.invalid_glob_x", x = pattern, err = err).red()
+                );
+                return Err(ExitCode::FAILURE);
+            }
+        }
+    }
+
     {
         let mut invalid_dotfiles = false;
         for dotfile in dotfiles {
@@ -99,19 +510,31 @@ pub fn encrypt_cmd(
         }
     }
 
-    let handler = SecretsHandler::try_new(profile)?;
+    let handler = SecretsHandler::try_new(ctx, profile, key_file, passphrase_file)?;
 
     let dest_dir = handler.dotfiles_dir.join("Secrets").join(group);
     if !dest_dir.exists() {
         fs::create_dir_all(&dest_dir).unwrap();
     }
 
-    // canonicalizing the home_dir so that it can work with
+    // canonicalizing the target directory so that it can work with
     // windows' NT UNC paths (the paths used by fs::canonicalize on windows)
-    let home_dir = dirs::home_dir().unwrap().canonicalize().unwrap();
+    let target_dir = ctx.target_dir();
+    let target_dir = target_dir.canonicalize().unwrap_or(target_dir);
 
     let encrypt_file = |dotfile: &Path| -> Result<(), ExitCode> {
-        let target_file = dotfile.strip_prefix(&home_dir).unwrap();
+        let Ok(target_file) = dotfile.strip_prefix(&target_dir) else {
+            eprintln!(
+                "{}",
+                t!(
+                    "errors.x_not_in_home",
+                    x = dotfile.display(),
+                    home = target_dir.display()
+                )
+                .red()
+            );
+            return Err(ReturnCode::NoSuchFileOrDir.into());
+        };
 
         let dir_path = {
             let mut tf = target_file.to_path_buf();
@@ -119,10 +542,7 @@ pub fn encrypt_cmd(
             tf
         };
 
-        let mut encrypted = handler.encrypt(dotfile)?;
-        let mut encrypted_file = handler.nonce.to_vec();
-        // appends a 24 byte nonce to the beginning of the file
-        encrypted_file.append(&mut encrypted);
+        let encrypted_file = handler.encrypt(dotfile)?;
 
         // makes sure all parent directories of the dotfile are created
         fs::create_dir_all(dest_dir.join(dir_path)).unwrap();
@@ -135,14 +555,11 @@ pub fn encrypt_cmd(
         let dotfile = Path::new(dotfile).canonicalize().unwrap();
 
         if dotfile.is_dir() {
-            let Ok(dir) = dotfile.read_dir() else {
-                eprintln!("{}", t!("errors.x_doesnt_exist", x = dotfile.display()));
-                return Err(ExitCode::FAILURE);
-            };
-
-            for file in dir {
-                let file = file.unwrap().path();
-                encrypt_file(&file)?;
+            for file in DirWalk::new(&dotfile).follow_symlinks(true) {
+                let relative = file.strip_prefix(&dotfile).unwrap_or(&file);
+                if !file.is_dir() && !is_excluded(relative, &exclude_patterns) {
+                    encrypt_file(&file)?;
+                }
             }
         } else if dotfile.is_file() {
             encrypt_file(&dotfile)?;
@@ -152,16 +569,51 @@ pub fn encrypt_cmd(
     Ok(())
 }
 
+/// Returns true if the file or any of the directories it's in match one of the patterns
+///
+/// Patterns are matched against both the path relative to the directory being encrypted and the file's name,
+/// eg: `*.log` excludes `logs/today.log` and `cache` excludes everything inside of `cache/`
+fn is_excluded(relative: &Path, patterns: &[glob::Pattern]) -> bool {
+    relative
+        .ancestors()
+        .filter(|path| !path.as_os_str().is_empty())
+        .any(|path| {
+            patterns.iter().any(|pattern| {
+                pattern.matches_path(path)
+                    || path
+                        .file_name()
+                        .is_some_and(|name| pattern.matches(&name.to_string_lossy()))
+            })
+        })
+}
+
 /// Decrypts secrets
-pub fn decrypt_cmd(
+///
+/// out: directory the secrets are decrypted into keeping their path relative to their group,
+/// by default they're all put in the current directory
+///
+/// check: only checks that every secret can be decrypted, nothing is written
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn decrypt_cmd(
+    ctx: &Context,
     profile: Option<String>,
     groups: &[String],
     exclude: &[String],
+    key_file: Option<&Path>,
+    out: Option<&Path>,
+    check: bool,
+    passphrase_file: Option<&Path>,
 ) -> Result<(), ExitCode> {
-    let handler = SecretsHandler::try_new(profile.clone())?;
+    let handler = SecretsHandler::try_new(ctx, profile.clone(), key_file, passphrase_file)?;
 
+    let groups = &dotfiles::expand_group_patterns(
+        ctx,
+        profile.clone(),
+        dotfiles::DotfileType::Secrets,
+        groups,
+    );
     if let Some(invalid_groups) =
-        dotfiles::check_invalid_groups(profile, dotfiles::DotfileType::Secrets, groups)
+        dotfiles::check_invalid_groups(ctx, profile, dotfiles::DotfileType::Secrets, groups)
     {
         for group in invalid_groups {
             eprintln!("{}", t!("errors.no_group", group = group).red());
@@ -171,20 +623,48 @@ pub fn decrypt_cmd(
 
     let dest_dir = std::env::current_dir().unwrap();
 
+    let checked = std::cell::Cell::new(0);
+    let failed = std::cell::Cell::new(0);
+
     let decrypt_group = |group: Dotfile| -> Result<(), ExitCode> {
         if exclude.contains(&group.group_name) || !group.is_valid_target() {
             return Ok(());
         }
 
         let group_dir = handler.dotfiles_dir.join("Secrets").join(&group.group_path);
-        for secret in DirWalk::new(group_dir) {
+        for secret in DirWalk::new(group_dir.clone()) {
             if secret.is_dir() {
                 continue;
             }
 
-            let decrypted = handler.decrypt(secret.to_str().unwrap())?;
+            if check {
+                checked.set(checked.get() + 1);
+                let decrypted = fs::read(&secret)
+                    .map_err(|_| ExitCode::FAILURE)
+                    .and_then(|secret_file| handler.decrypt_data(&secret_file));
+                if decrypted.is_err() {
+                    eprintln!(
+                        "{}",
+                        t!("errors.failed_to_decrypt_x", x = secret.display()).red()
+                    );
+                    failed.set(failed.get() + 1);
+                }
+                continue;
+            }
+
+            let dest = match out {
+                Some(out) => out.join(secret.strip_prefix(&group_dir).unwrap_or(&secret)),
+                None => dest_dir.join(secret.file_name().unwrap()),
+            };
+
+            if let Some(parent) = dest.parent() {
+                if let Err(err) = fs::create_dir_all(parent) {
+                    eprintln!("{}", format!("{}: {err}", parent.display()).red());
+                    return Err(ReturnCode::DecryptionFailed.into());
+                }
+            }
 
-            fs::write(dest_dir.join(secret.file_name().unwrap()), decrypted).unwrap();
+            handler.decrypt_to(&secret, &dest)?;
         }
 
         Ok(())
@@ -193,24 +673,509 @@ pub fn decrypt_cmd(
     if groups.contains(&"*".to_string()) {
         let groups_dir = handler.dotfiles_dir.join("Secrets");
         for group in fs::read_dir(groups_dir).unwrap() {
-            let Ok(group) = Dotfile::try_from(group.unwrap().path()) else {
+            let Ok(group) = Dotfile::new(ctx, group.unwrap().path()) else {
                 eprintln!("{}", t!("errors.got_invalid_group").red());
                 return Err(ExitCode::FAILURE);
             };
             decrypt_group(group)?;
         }
+    } else {
+        for group in groups {
+            let group = handler.dotfiles_dir.join("Secrets").join(group);
+            let Ok(group) = Dotfile::new(ctx, group) else {
+                eprintln!("{}", t!("errors.got_invalid_group").red());
+                return Err(ExitCode::FAILURE);
+            };
+            decrypt_group(group)?;
+        }
+    }
+
+    if check {
+        let (total, failed) = (checked.get(), failed.get());
+        let summary = t!(
+            "info.checked_x_of_y_secrets",
+            ok = total - failed,
+            total = total
+        );
+        if failed > 0 {
+            eprintln!("{}", summary.red());
+            return Err(ReturnCode::DecryptionFailed.into());
+        }
+        println!("{}", summary.green());
+    }
+
+    Ok(())
+}
+
+/// Re-encrypts all secrets in the supplied groups with a new password or key file
+///
+/// The old key is read the same way as when decrypting, the new one from `new_key_file`,
+/// `new_passphrase_file` or the `TUCKR_NEW_PASSPHRASE` environment variable before prompting for it
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn reencrypt_cmd(
+    ctx: &Context,
+    profile: Option<String>,
+    groups: &[String],
+    exclude: &[String],
+    key_file: Option<&Path>,
+    passphrase_file: Option<&Path>,
+    new_key_file: Option<&Path>,
+    new_passphrase_file: Option<&Path>,
+) -> Result<(), ExitCode> {
+    let groups = &dotfiles::expand_group_patterns(
+        ctx,
+        profile.clone(),
+        dotfiles::DotfileType::Secrets,
+        groups,
+    );
+    if let Some(invalid_groups) =
+        dotfiles::check_invalid_groups(ctx, profile.clone(), dotfiles::DotfileType::Secrets, groups)
+    {
+        for group in invalid_groups {
+            eprintln!("{}", t!("errors.no_group", group = group).red());
+        }
+        return Err(ReturnCode::NoSuchFileOrDir.into());
+    }
+
+    let dotfiles_dir = match dotfiles::get_dotfiles_path(ctx, profile) {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("{e}");
+            return Err(ReturnCode::CouldntFindDotfiles.into());
+        }
+    };
+    let secrets_dir = dotfiles_dir.join("Secrets");
+
+    let old_key = read_input_key(
+        key_file,
+        passphrase_file,
+        "TUCKR_PASSPHRASE",
+        &t!("info.old_password"),
+    )?;
+    let old_handler = SecretsHandler::with_key(dotfiles_dir.clone(), &old_key);
 
-        return Ok(());
+    // a typo in a password that was typed in would make the secrets unrecoverable
+    let new_key_is_prompted = new_key_file.is_none()
+        && new_passphrase_file.is_none()
+        && std::env::var_os("TUCKR_NEW_PASSPHRASE").is_none();
+    let new_key = read_input_key(
+        new_key_file,
+        new_passphrase_file,
+        "TUCKR_NEW_PASSPHRASE",
+        &t!("info.new_password"),
+    )?;
+    if new_key_is_prompted
+        && new_key.as_slice() != prompt_password(&t!("info.confirm_password"))?.as_bytes()
+    {
+        eprintln!("{}", t!("errors.passwords_dont_match").red());
+        return Err(ReturnCode::EncryptionFailed.into());
     }
+    let new_handler = SecretsHandler::with_key(dotfiles_dir, &new_key);
 
+    let groups: Vec<String> = if groups.contains(&"*".to_string()) {
+        match fs::read_dir(&secrets_dir) {
+            Ok(entries) => entries
+                .filter_map(|group| group.ok())
+                .map(|group| group.file_name().to_string_lossy().into_owned())
+                .collect(),
+            Err(_) => {
+                eprintln!("{}", t!("errors.no_dir_setup_for_x", x = "Secrets").red());
+                return Err(ReturnCode::NoSetupFolder.into());
+            }
+        }
+    } else {
+        groups.to_vec()
+    };
+
+    // every secret is decrypted before anything is written so that a wrong
+    // password doesn't leave the secrets encrypted with different passwords
+    let mut secrets = Vec::new();
     for group in groups {
-        let group = handler.dotfiles_dir.join("Secrets").join(group);
-        let Ok(group) = Dotfile::try_from(group) else {
-            eprintln!("{}", t!("errors.got_invalid_group").red());
+        if dotfiles::group_is_excluded(&group, exclude) {
+            continue;
+        }
+
+        for secret in DirWalk::new(secrets_dir.join(&group)) {
+            if secret.is_dir() {
+                continue;
+            }
+
+            let Ok(encrypted) = fs::read(&secret) else {
+                eprintln!(
+                    "{}",
+                    t!("errors.x_doesnt_exist", x = secret.display()).red()
+                );
+                return Err(ReturnCode::NoSuchFileOrDir.into());
+            };
+            let decrypted = zeroize::Zeroizing::new(old_handler.decrypt_data(&encrypted)?);
+            let mode = split_mode_header(&encrypted).mode;
+            secrets.push((secret, decrypted, mode));
+        }
+    }
+
+    for (secret, decrypted, mode) in secrets {
+        let encrypted_file = new_handler.encrypt_data(&decrypted, mode)?;
+        if let Err(err) = write_secret(&secret, &encrypted_file) {
+            eprintln!("{}", format!("{}: {err}", secret.display()).red());
+            return Err(ReturnCode::EncryptionFailed.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Replaces the content of a secret file.
+///
+/// The secret is written to a temporary file first so that an interruption can't leave it half written
+fn write_secret(secret: &Path, encrypted_file: &[u8]) -> std::io::Result<()> {
+    let mut tmp_file = secret.as_os_str().to_owned();
+    tmp_file.push(".tuckr-tmp");
+
+    let result = fs::write(&tmp_file, encrypted_file).and_then(|_| fs::rename(&tmp_file, secret));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_file);
+    }
+    result
+}
+
+/// Temporary directory that only the current user can access.
+/// Its contents are overwritten and removed once it's dropped
+struct PrivateTempDir(PathBuf);
+
+impl PrivateTempDir {
+    fn new() -> std::io::Result<Self> {
+        let dir = std::env::temp_dir().join(format!("tuckr-edit-{}", std::process::id()));
+
+        #[cfg(target_family = "unix")]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            fs::DirBuilder::new().mode(0o700).create(&dir)?;
+        }
+
+        #[cfg(not(target_family = "unix"))]
+        fs::create_dir(&dir)?;
+
+        Ok(Self(dir))
+    }
+}
+
+impl Drop for PrivateTempDir {
+    fn drop(&mut self) {
+        for file in DirWalk::new(&self.0) {
+            if let Ok(metadata) = file.symlink_metadata() {
+                if metadata.is_file() {
+                    let _ = fs::write(&file, vec![0; metadata.len() as usize]);
+                }
+            }
+        }
+
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Decrypts a secret, opens it on $EDITOR and encrypts it back
+pub(crate) fn edit_cmd(
+    ctx: &Context,
+    profile: Option<String>,
+    group: &str,
+    file: &Path,
+    passphrase_file: Option<&Path>,
+) -> Result<(), ExitCode> {
+    let handler = SecretsHandler::try_new(ctx, profile, None, passphrase_file)?;
+    let secret = handler.dotfiles_dir.join("Secrets").join(group).join(file);
+
+    if !secret.is_file() {
+        eprintln!(
+            "{}",
+            t!("errors.x_doesnt_exist", x = secret.display()).red()
+        );
+        return Err(ReturnCode::NoSuchFileOrDir.into());
+    }
+
+    let Ok(secret_file) = fs::read(&secret) else {
+        eprintln!(
+            "{}",
+            t!("errors.x_doesnt_exist", x = secret.display()).red()
+        );
+        return Err(ReturnCode::NoSuchFileOrDir.into());
+    };
+    let decrypted = zeroize::Zeroizing::new(handler.decrypt_data(&secret_file)?);
+    let mode = split_mode_header(&secret_file).mode;
+
+    let tmp_dir = match PrivateTempDir::new() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("{}", err.red());
             return Err(ExitCode::FAILURE);
-        };
-        decrypt_group(group)?;
+        }
+    };
+    // keeping the file name allows editors to pick the right file type
+    let tmp_file = tmp_dir.0.join(secret.file_name().unwrap());
+    if let Err(err) = fs::write(&tmp_file, decrypted.as_slice()) {
+        eprintln!("{}", err.red());
+        return Err(ExitCode::FAILURE);
+    }
+
+    let editor = std::env::var("EDITOR").unwrap_or_else(|_| {
+        if cfg!(target_family = "windows") {
+            "notepad".into()
+        } else {
+            "vi".into()
+        }
+    });
+
+    match std::process::Command::new(&editor).arg(&tmp_file).status() {
+        Ok(status) if status.success() => (),
+        Ok(_) => {
+            eprintln!("{}", t!("errors.editor_failed", editor = editor).red());
+            return Err(ExitCode::FAILURE);
+        }
+        Err(err) => {
+            eprintln!("{}", format!("{editor}: {err}").red());
+            return Err(ExitCode::FAILURE);
+        }
+    }
+
+    let Ok(edited) = fs::read(&tmp_file).map(zeroize::Zeroizing::new) else {
+        eprintln!(
+            "{}",
+            t!("errors.x_doesnt_exist", x = tmp_file.display()).red()
+        );
+        return Err(ExitCode::FAILURE);
+    };
+
+    let encrypted_file = handler.encrypt_data(&edited, mode)?;
+    if let Err(err) = write_secret(&secret, &encrypted_file) {
+        eprintln!("{}", format!("{}: {err}", secret.display()).red());
+        return Err(ReturnCode::EncryptionFailed.into());
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn decrypted_secrets_keep_their_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let ctx = &Context::default();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(ctx, None).unwrap();
+        fs::create_dir_all(&dotfiles_dir).unwrap();
+        let handler = SecretsHandler::with_key(dotfiles_dir.clone(), b"password");
+
+        let dotfile = dotfiles_dir.join("config");
+        fs::write(&dotfile, "secret").unwrap();
+        fs::set_permissions(&dotfile, fs::Permissions::from_mode(0o600)).unwrap();
+
+        let secret = dotfiles_dir.join("secret");
+        fs::write(&secret, handler.encrypt(&dotfile).unwrap()).unwrap();
+
+        let decrypted = dotfiles_dir.join("decrypted");
+        handler.decrypt_to(&secret, &decrypted).unwrap();
+        assert_eq!(fs::read_to_string(&decrypted).unwrap(), "secret");
+        assert_eq!(
+            fs::metadata(&decrypted).unwrap().permissions().mode() & 0o7777,
+            0o600
+        );
+
+        // an existing file is replaced instead of keeping its permissions
+        fs::set_permissions(&decrypted, fs::Permissions::from_mode(0o644)).unwrap();
+        handler.decrypt_to(&secret, &decrypted).unwrap();
+        assert_eq!(
+            fs::metadata(&decrypted).unwrap().permissions().mode() & 0o7777,
+            0o600
+        );
+
+        // setuid and setgid bits are never stored
+        fs::set_permissions(&dotfile, fs::Permissions::from_mode(0o6755)).unwrap();
+        let secret_file = handler.encrypt(&dotfile).unwrap();
+        assert_eq!(split_mode_header(&secret_file).mode, Some(0o755));
+
+        // the stored mode can't be changed without the secret failing to decrypt
+        let mut tampered = secret_file.clone();
+        tampered[MODE_HEADER.len()..MODE_HEADER.len() + 4].copy_from_slice(&0o777u32.to_le_bytes());
+        assert!(handler.decrypt_data(&tampered).is_err());
+
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+    }
+
+    #[test]
+    fn encrypt_directories_recursively() {
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&Context::default(), None).unwrap();
+        fs::create_dir_all(&dotfiles_dir).unwrap();
+        let passphrase_file = dotfiles_dir.join("passphrase");
+        fs::write(&passphrase_file, "password").unwrap();
+
+        let target_dir = dotfiles_dir.with_file_name("target");
+        fs::create_dir_all(&target_dir).unwrap();
+        let ctx = &Context {
+            target_dir: Some(target_dir.canonicalize().unwrap()),
+            ..Default::default()
+        };
+        let app_dir = ctx.target_dir().join(".app");
+        let files = ["config", "nested/config", "nested/deeper/config"];
+        let excluded = ["cache/config", "nested/debug.log"];
+        for file in files.iter().chain(&excluded) {
+            let file = app_dir.join(file);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, "secret").unwrap();
+        }
+
+        encrypt_cmd(
+            ctx,
+            None,
+            "app",
+            &[app_dir.to_str().unwrap().into()],
+            &["cache".into(), "*.log".into()],
+            None,
+            Some(&passphrase_file),
+        )
+        .unwrap();
+
+        let secrets_dir = dotfiles_dir.join("Secrets").join("app").join(".app");
+        for file in files {
+            assert!(secrets_dir.join(file).is_file(), "{file} wasn't encrypted");
+        }
+        for file in excluded {
+            assert!(!secrets_dir.join(file).exists(), "{file} wasn't excluded");
+        }
+
+        fs::remove_dir_all(dotfiles_dir.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn passphrase_file_trailing_newline_is_trimmed() {
+        let ctx = &Context::default();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(ctx, None).unwrap();
+        fs::create_dir_all(&dotfiles_dir).unwrap();
+
+        let passphrase_file = dotfiles_dir.join("passphrase");
+        fs::write(&passphrase_file, "password\n\n").unwrap();
+        let passphrase = read_passphrase(Some(&passphrase_file), "TUCKR_PASSPHRASE", "").unwrap();
+        assert_eq!(passphrase.as_str(), "password\n");
+
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+    }
+
+    #[test]
+    fn every_secret_gets_its_own_nonce() {
+        let handler = SecretsHandler::with_key(PathBuf::new(), b"password");
+
+        let first = handler.encrypt_data(b"secret", None).unwrap();
+        let second = handler.encrypt_data(b"secret", None).unwrap();
+        assert_ne!(first[..24], second[..24]);
+        assert_ne!(first, second);
+
+        assert_eq!(handler.decrypt_data(&first).unwrap(), b"secret");
+        assert_eq!(handler.decrypt_data(&second).unwrap(), b"secret");
+    }
+
+    #[test]
+    fn secrets_are_replaced_atomically() {
+        let ctx = &Context::default();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(ctx, None).unwrap();
+        fs::create_dir_all(&dotfiles_dir).unwrap();
+
+        let secret = dotfiles_dir.join("secret");
+        fs::write(&secret, "old").unwrap();
+        write_secret(&secret, b"new").unwrap();
+        assert_eq!(fs::read(&secret).unwrap(), b"new");
+        assert!(!dotfiles_dir.join("secret.tuckr-tmp").exists());
+
+        // a secret that can't be replaced is left as it was
+        let dir_secret = dotfiles_dir.join("dir");
+        fs::create_dir_all(dir_secret.join("child")).unwrap();
+        assert!(write_secret(&dir_secret, b"new").is_err());
+        assert!(dir_secret.join("child").is_dir());
+        assert!(!dotfiles_dir.join("dir.tuckr-tmp").exists());
+
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+    }
+
+    #[test]
+    fn reencrypt_with_key_files() {
+        let ctx = &Context::default();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(ctx, None).unwrap();
+        let group_dir = dotfiles_dir.join("Secrets").join("ssh");
+        fs::create_dir_all(&group_dir).unwrap();
+
+        let old_key = dotfiles_dir.join("old.key");
+        let new_key = dotfiles_dir.join("new.key");
+        fs::write(&old_key, "old key").unwrap();
+        fs::write(&new_key, "new key").unwrap();
+
+        let secret = group_dir.join("id");
+        let old_handler = SecretsHandler::with_key(dotfiles_dir.clone(), b"old key");
+        fs::write(&secret, old_handler.encrypt_data(b"secret", None).unwrap()).unwrap();
+
+        // the group is selected through a pattern
+        reencrypt_cmd(
+            ctx,
+            None,
+            &["ss*".to_string()],
+            &[],
+            Some(&old_key),
+            None,
+            Some(&new_key),
+            None,
+        )
+        .unwrap();
+
+        let new_handler = SecretsHandler::with_key(dotfiles_dir.clone(), b"new key");
+        let encrypted = fs::read(&secret).unwrap();
+        assert_eq!(new_handler.decrypt_data(&encrypted).unwrap(), b"secret");
+        assert!(old_handler.decrypt_data(&encrypted).is_err());
+
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn deploy_secrets_without_overwriting() {
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&Context::default(), None).unwrap();
+        let target_dir = dotfiles_dir.with_file_name("target");
+        let ctx = &Context {
+            target_dir: Some(target_dir.clone()),
+            ..Default::default()
+        };
+        let group_dir = dotfiles_dir.join("Secrets").join("app");
+        fs::create_dir_all(group_dir.join("linked").join("nested")).unwrap();
+        fs::create_dir_all(dotfiles_dir.join("Configs")).unwrap();
+        fs::create_dir_all(&target_dir).unwrap();
+        let passphrase_file = dotfiles_dir.join("passphrase");
+        fs::write(&passphrase_file, "password").unwrap();
+
+        let handler = SecretsHandler::with_key(dotfiles_dir.clone(), b"password");
+        let secret = handler.encrypt_data(b"secret", None).unwrap();
+        fs::write(group_dir.join("config"), &secret).unwrap();
+        fs::write(
+            group_dir.join("linked").join("nested").join("config"),
+            &secret,
+        )
+        .unwrap();
+        // the secret's parent resolves to a directory inside of the dotfiles
+        std::os::unix::fs::symlink(dotfiles_dir.join("Configs"), target_dir.join("linked"))
+            .unwrap();
+
+        let target = target_dir.join("config");
+        fs::write(&target, "not ours").unwrap();
+
+        let deployer = SecretsDeployer::new(ctx, None, Some(passphrase_file));
+        assert!(deployer.deploy_group("app", false).is_err());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "not ours");
+        assert!(!dotfiles_dir.join("Configs").join("nested").exists());
+
+        assert!(deployer.deploy_group("app", true).is_err());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "secret");
+
+        // a target that already has the secret's contents is left alone
+        fs::remove_file(target_dir.join("linked")).unwrap();
+        assert!(deployer.deploy_group("app", false).is_ok());
+
+        fs::remove_dir_all(dotfiles_dir.parent().unwrap()).unwrap();
+    }
+}
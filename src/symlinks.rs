@@ -10,61 +10,552 @@
 //! This information is retrieved by walking through dotfiles/Configs and checking whether their
 //! $HOME equivalents are pointing to them and categorizing them accordingly.
 
-use crate::dotfiles::{self, Dotfile, DotfileType, ReturnCode};
+use crate::dotfiles::{
+    self, Context, Dotfile, DotfileError, DotfileType, ReturnCode, TargetKind, Verbosity,
+};
+use crate::fileops::DeployRecord;
+use crate::filetree::FileTree;
 use enumflags2::{make_bitflags, BitFlags};
 use owo_colors::OwoColorize;
 use rust_i18n::t;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 use std::process::ExitCode;
+use std::rc::Rc;
 use tabled::{Table, Tabled};
 
-fn symlink_file(f: PathBuf) {
-    match Dotfile::try_from(f.clone()) {
-        Ok(group) => {
-            let target_path = group.to_target_path();
-            if target_path.exists() {
-                return;
-            }
+/// How dotfiles are deployed to their targets
+#[derive(Clone, Copy, Default, Debug, PartialEq, clap::ValueEnum)]
+pub enum DeployMode {
+    /// Symlink dotfiles to their target
+    #[default]
+    Symlink,
+    /// Copy dotfiles to their target
+    Copy,
+    /// Hardlink dotfiles to their target
+    Hardlink,
+}
 
-            let result = {
-                #[cfg(target_family = "unix")]
-                {
-                    std::os::unix::fs::symlink(f, target_path)
-                }
+/// Kind of change made to a dotfile's target
+#[derive(serde::Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKind {
+    Symlink,
+    Copy,
+    Hardlink,
+    CreateDir,
+    /// The target was a broken symlink left behind by a dotfile that has since been moved,
+    /// it was replaced with a symlink to the dotfile
+    Relink,
+    /// The target is taken by a file that isn't managed by tuckr so it was left alone
+    Conflict,
+    Remove,
+    Restore,
+    /// The target was moved into the trash directory instead of being deleted
+    Trash,
+    /// The conflicting target was moved into the backup directory
+    Backup,
+    /// The symlink was removed because its source no longer exists
+    Prune,
+}
 
-                #[cfg(target_family = "windows")]
-                {
-                    if f.is_dir() {
-                        std::os::windows::fs::symlink_dir(f, target_path)
-                    } else {
-                        std::os::windows::fs::symlink_file(f, target_path)
-                    }
-                }
-            };
+/// A change made, or attempted, to a dotfile's target
+///
+/// Printed as JSON by `add --json` and `rm --json`
+#[derive(serde::Serialize, Debug)]
+pub struct Action {
+    pub action: ActionKind,
+    pub group: String,
+    pub source: PathBuf,
+    pub target: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The action failed because the target can only be written to with elevated privileges
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub needs_root: bool,
+    /// Where the target was moved to, only set for `Backup`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup: Option<PathBuf>,
+}
 
-            if let Err(err) = result {
-                eprintln!(
+impl Action {
+    fn new(action: ActionKind, dotfile: &Dotfile, target: &Path) -> Self {
+        Self {
+            action,
+            group: dotfile.group_name.clone(),
+            source: dotfile.path.clone(),
+            target: target.to_path_buf(),
+            error: None,
+            needs_root: false,
+            backup: None,
+        }
+    }
+
+    fn with_result(mut self, result: std::io::Result<()>) -> Self {
+        self.error = result.err().map(|err| err.to_string());
+        self
+    }
+
+    /// Prints the action the way it's shown to users when not using JSON
+    fn print(&self, verbosity: Verbosity) {
+        let is_deploy = !matches!(
+            self.action,
+            ActionKind::Remove
+                | ActionKind::Restore
+                | ActionKind::Trash
+                | ActionKind::Conflict
+                | ActionKind::Backup
+                | ActionKind::Prune
+        );
+
+        match &self.error {
+            Some(err) if is_deploy => eprintln!(
+                "{}",
+                t!(
+                    "errors.failed_to_symlink_x",
+                    groupname = self.group,
+                    err_msg = err.red()
+                )
+            ),
+            Some(err) => eprintln!("{}", format!("{}: {err}", self.target.display()).red()),
+            // backups and pruned symlinks are shown unless asked to be quiet
+            None if verbosity == Verbosity::Quiet => (),
+            None if self.action == ActionKind::Backup => println!(
+                "{}",
+                t!(
+                    "info.backed_up_x_to_y",
+                    x = self.target.display(),
+                    y = self.backup.as_deref().unwrap_or(Path::new("")).display()
+                )
+            ),
+            None if self.action == ActionKind::Prune => {
+                println!(
+                    "{}",
+                    t!("info.pruned_symlink", file = self.target.display())
+                )
+            }
+            None if verbosity != Verbosity::Verbose => (),
+            None => match self.action {
+                ActionKind::Conflict => (),
+                ActionKind::Remove => {
+                    println!("{}", t!("info.removed_x", file = self.target.display()))
+                }
+                ActionKind::Restore => {
+                    println!("{}", t!("info.restored_x", file = self.target.display()))
+                }
+                ActionKind::Trash => {
+                    println!("{}", t!("info.trashed_x", file = self.target.display()))
+                }
+                _ => println!(
                     "{}",
                     t!(
-                        "errors.failed_to_symlink_x",
-                        groupname = group.group_name,
-                        err_msg = err.red()
+                        "info.deployed_x_to_y",
+                        x = self.source.display(),
+                        y = self.target.display()
                     )
+                ),
+            },
+        }
+    }
+}
+
+/// What `add` and `remove` did, or attempted to do, along with the problems found on the way
+#[derive(Default, Debug)]
+pub struct Report {
+    pub actions: Vec<Action>,
+    /// Problems that didn't keep anything from being done, eg: two files of a group sharing a target
+    pub warnings: Vec<String>,
+    /// Files and groups that were skipped because something went wrong with them
+    pub errors: Vec<String>,
+    /// Targets that more than one of the groups deploys a file to, only one of them can end up deployed
+    pub shared_targets: BTreeMap<PathBuf, Vec<Dotfile>>,
+    /// Groups that were skipped because one of their targets couldn't be expanded
+    pub invalid_target_groups: Vec<String>,
+}
+
+impl Report {
+    fn extend(&mut self, other: Report) {
+        self.actions.extend(other.actions);
+        self.warnings.extend(other.warnings);
+        self.errors.extend(other.errors);
+        self.shared_targets.extend(other.shared_targets);
+        self.invalid_target_groups
+            .extend(other.invalid_target_groups);
+    }
+
+    /// Prints the warnings and errors, and the actions unless they're printed as JSON
+    fn print(&self, verbosity: Verbosity, json: bool) {
+        for warning in &self.warnings {
+            eprintln!("{}", warning.yellow());
+        }
+
+        for err in &self.errors {
+            eprintln!("{}", err.red());
+        }
+
+        if !json {
+            self.actions
+                .iter()
+                .for_each(|action| action.print(verbosity));
+        }
+    }
+}
+
+/// Errors returned by `status`, `add` and `remove`
+#[derive(Debug)]
+pub enum SymlinkError {
+    /// The dotfiles couldn't be scanned
+    Dotfiles(DotfileError),
+    /// The groups don't exist in dotfiles/Configs
+    NoSuchGroups(Vec<String>),
+    /// The groups' dependencies couldn't be resolved, holds the reason why
+    Dependencies(String),
+    /// None of the groups can be deployed on this platform
+    Unsupported(Vec<String>),
+}
+
+impl std::fmt::Display for SymlinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SymlinkError::Dotfiles(err) => write!(f, "{err}"),
+            SymlinkError::NoSuchGroups(groups) => {
+                write!(f, "{}", t!("errors.x_doesnt_exist", x = groups.join(", ")))
+            }
+            SymlinkError::Dependencies(reason) => write!(f, "{reason}"),
+            SymlinkError::Unsupported(groups) => write!(
+                f,
+                "{}",
+                t!(
+                    "errors.x_not_supported_on_this_platform",
+                    x = groups.join(", ")
+                )
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SymlinkError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SymlinkError::Dotfiles(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<DotfileError> for SymlinkError {
+    fn from(err: DotfileError) -> Self {
+        SymlinkError::Dotfiles(err)
+    }
+}
+
+/// Prints the error the way the commands report it and returns the exit code that goes with it
+fn report_symlink_error(err: SymlinkError) -> ExitCode {
+    match err {
+        SymlinkError::Dotfiles(err) => {
+            match err {
+                DotfileError::DotfilesDirNotFound(_) => eprintln!("{err}"),
+                _ => eprintln!("{}", err.to_string().red()),
+            }
+
+            match err {
+                DotfileError::NotADir(_) => ReturnCode::NoSetupFolder.into(),
+                _ => ReturnCode::CouldntFindDotfiles.into(),
+            }
+        }
+        SymlinkError::NoSuchGroups(groups) => {
+            for group in groups {
+                eprintln!("{}", t!("errors.x_doesnt_exist", x = group).red());
+            }
+            ReturnCode::NoSetupFolder.into()
+        }
+        SymlinkError::Dependencies(reason) => {
+            eprintln!("{}", reason.red());
+            ReturnCode::NoSuchFileOrDir.into()
+        }
+        SymlinkError::Unsupported(groups) => {
+            for group in groups {
+                eprintln!(
+                    "{}",
+                    t!("errors.x_not_supported_on_this_platform", x = group).red()
                 );
             }
+            ExitCode::FAILURE
         }
+    }
+}
 
-        Err(err) => {
-            eprintln!("{}", err);
-            eprintln!(
-                "{}",
-                t!("errors.failed_to_link_file", file = f.to_str().unwrap())
-            );
+/// Returns the command that redoes the action with elevated privileges,
+/// actions that aren't deployments don't have one
+#[cfg(target_family = "unix")]
+fn elevated_args(action: &Action) -> Option<Vec<&std::ffi::OsStr>> {
+    let source = action.source.as_os_str();
+    let target = action.target.as_os_str();
+
+    Some(match action.action {
+        ActionKind::Symlink => vec!["ln".as_ref(), "-s".as_ref(), source, target],
+        ActionKind::Relink => vec!["ln".as_ref(), "-sf".as_ref(), source, target],
+        ActionKind::Copy => vec!["cp".as_ref(), source, target],
+        ActionKind::Hardlink => vec!["ln".as_ref(), source, target],
+        ActionKind::CreateDir => vec!["mkdir".as_ref(), target],
+        _ => return None,
+    })
+}
+
+/// Redoes an action that failed due to missing permissions through `sudo`, or `doas` if
+/// sudo isn't available
+#[cfg(target_family = "unix")]
+fn run_elevated(action: &Action) -> std::io::Result<()> {
+    use std::process::Command;
+
+    let parent = action.target.parent().unwrap_or(Path::new("/")).as_os_str();
+    let Some(args) = elevated_args(action) else {
+        return Ok(());
+    };
+
+    let run = |args: &[&std::ffi::OsStr]| -> std::io::Result<()> {
+        let status = match Command::new("sudo").args(args).status() {
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                Command::new("doas").args(args).status()
+            }
+            status => status,
+        };
+
+        match status {
+            Ok(status) if status.success() => Ok(()),
+            Ok(status) => Err(std::io::Error::other(status.to_string())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                t!("errors.no_privilege_escalation").to_string(),
+            )),
+            Err(err) => Err(err),
+        }
+    };
+
+    run(&["mkdir".as_ref(), "-p".as_ref(), parent])?;
+    run(&args)
+}
+
+#[cfg(target_family = "windows")]
+fn run_elevated(_action: &Action) -> std::io::Result<()> {
+    Err(std::io::Error::other(
+        t!("errors.x_not_supported_on_this_platform", x = "--sudo").to_string(),
+    ))
+}
+
+/// Lists the files that couldn't be deployed without elevated privileges and how to deploy them
+fn print_needs_root(profile: Option<&str>, actions: &[Action]) {
+    if actions.is_empty() {
+        return;
+    }
+
+    eprintln!("{}:", t!("warn.files_need_root").yellow());
+    for action in actions {
+        eprintln!(
+            "\t{} -> {}",
+            action.source.display(),
+            action.target.display()
+        );
+    }
+
+    let mut groups: Vec<_> = actions.iter().map(|a| a.group.as_str()).collect();
+    groups.dedup();
+    let cmd = match profile {
+        Some(profile) => format!("tuckr -p {profile} add --sudo {}", groups.join(" ")),
+        None => format!("tuckr add --sudo {}", groups.join(" ")),
+    };
+    eprintln!("{}", t!("info.run_x_to_deploy", cmd = cmd));
+}
+
+/// Prints the actions as a JSON array
+fn print_actions_json(actions: &[Action]) {
+    println!("{}", serde_json::to_string(actions).unwrap());
+}
+
+/// Removes the dotfile's symlinks, or copies, from its targets
+///
+/// restore: replaces the symlinks with a copy of the dotfile, copies are left as they are
+/// trash: moves the targets into the trash directory instead of deleting them
+/// apply: if false nothing is removed, only the actions that would be taken are returned
+fn remove_symlink(dotfile: &Dotfile, restore: bool, trash: bool, apply: bool) -> Vec<Action> {
+    let mut actions = Vec::new();
+    let remove_kind = if trash {
+        ActionKind::Trash
+    } else {
+        ActionKind::Remove
+    };
+
+    for target_dotfile in dotfile.to_target_paths() {
+        match dotfile.target_exists_kind(&target_dotfile) {
+            TargetKind::SymlinkToUs => (),
+            TargetKind::CopyOfUs if restore => continue,
+            TargetKind::CopyOfUs if !apply => {
+                actions.push(Action::new(remove_kind, dotfile, &target_dotfile));
+                continue;
+            }
+            TargetKind::CopyOfUs => {
+                let result = if trash {
+                    crate::fileops::move_to_trash(&dotfile.ctx, &target_dotfile).map(|_| ())
+                } else {
+                    fs::remove_file(&target_dotfile)
+                }
+                .and_then(|_| DeployRecord::Copies.remove(&target_dotfile));
+                actions
+                    .push(Action::new(remove_kind, dotfile, &target_dotfile).with_result(result));
+                continue;
+            }
+            _ => continue,
+        }
+
+        if !apply {
+            let kind = if restore {
+                ActionKind::Restore
+            } else {
+                remove_kind
+            };
+            actions.push(Action::new(kind, dotfile, &target_dotfile));
+            continue;
+        }
+
+        let result = if trash {
+            crate::fileops::move_to_trash(&dotfile.ctx, &target_dotfile).map(|_| ())
+        } else if target_dotfile.is_dir() {
+            fs::remove_dir_all(&target_dotfile)
+        } else {
+            fs::remove_file(&target_dotfile)
+        }
+        .and_then(|_| DeployRecord::Symlinks.remove(&target_dotfile));
+
+        let action = match result {
+            Ok(()) if restore => {
+                let result = crate::fileops::copy_recursively(&dotfile.path, &target_dotfile);
+                Action::new(ActionKind::Restore, dotfile, &target_dotfile).with_result(result)
+            }
+            result => Action::new(remove_kind, dotfile, &target_dotfile).with_result(result),
+        };
+        actions.push(action);
+    }
+
+    actions
+}
+
+/// Creates a symlink at `link` pointing to `original`
+pub fn symlink(original: &Path, link: &Path) -> std::io::Result<()> {
+    #[cfg(target_family = "unix")]
+    {
+        std::os::unix::fs::symlink(original, link)
+    }
+
+    #[cfg(target_family = "windows")]
+    {
+        if original.is_dir() {
+            std::os::windows::fs::symlink_dir(original, link)
+        } else {
+            std::os::windows::fs::symlink_file(original, link)
+        }
+    }
+}
+
+/// Deploys a dotfile to its targets.
+/// If create_dirs is set, directories are created instead of symlinked
+/// If apply is not set nothing is deployed, only the actions that would be taken are returned
+///
+/// replaced: targets that link to a lower priority conditional group, they're relinked instead of being conflicts
+fn symlink_file(
+    group: &Dotfile,
+    mode: DeployMode,
+    create_dirs: bool,
+    apply: bool,
+    replaced: &[PathBuf],
+) -> Vec<Action> {
+    let f = &group.path;
+    let mut actions = Vec::new();
+
+    for target_path in group.to_target_paths() {
+        // already reported when the dotfiles' status is loaded
+        if group.source_loops_to(&target_path) {
+            continue;
+        }
+
+        let target_kind = group.target_exists_kind(&target_path);
+        let replace = replaced.contains(&target_path);
+        let stale = replace || is_stale_link(group, &target_path, &target_kind);
+        match target_kind {
+            TargetKind::Absent => (),
+            TargetKind::SymlinkToUs | TargetKind::CopyOfUs => continue,
+            // the files inside of it are deployed individually
+            TargetKind::PlainDir if group.is_walked_dir() => continue,
+            TargetKind::BrokenSymlink if stale => (),
+            TargetKind::SymlinkElsewhere(_) if replace => (),
+            _ => {
+                actions.push(Action::new(ActionKind::Conflict, group, &target_path));
+                continue;
+            }
+        }
+
+        let kind = match mode {
+            // directories are created instead of copied so that
+            // the files inside them get deployed individually
+            DeployMode::Copy | DeployMode::Hardlink if group.is_walked_dir() => {
+                ActionKind::CreateDir
+            }
+            DeployMode::Symlink if create_dirs && group.is_walked_dir() => ActionKind::CreateDir,
+            // symlinked directories can't be copied as a single file so they're linked as-is
+            DeployMode::Copy | DeployMode::Hardlink if f.is_dir() => ActionKind::Symlink,
+            DeployMode::Copy => ActionKind::Copy,
+            DeployMode::Hardlink => ActionKind::Hardlink,
+            DeployMode::Symlink if stale => ActionKind::Relink,
+            DeployMode::Symlink => ActionKind::Symlink,
+        };
+
+        let mut action = Action::new(kind, group, &target_path);
+        if !apply {
+            actions.push(action);
+            continue;
+        }
+
+        // custom target roots might not have been created yet
+        if let Some(target_parent) = target_path.parent() {
+            _ = fs::create_dir_all(target_parent);
+        }
+
+        if stale {
+            if let Err(err) = fs::remove_file(&target_path) {
+                actions.push(action.with_result(Err(err)));
+                continue;
+            }
         }
+
+        let result = match kind {
+            ActionKind::CreateDir => fs::create_dir(&target_path),
+            ActionKind::Copy => {
+                fs::copy(f, &target_path).and_then(|_| DeployRecord::Copies.insert(&target_path, f))
+            }
+            ActionKind::Hardlink => fs::hard_link(f, &target_path)
+                .and_then(|_| DeployRecord::Copies.insert(&target_path, f)),
+            _ => symlink(f, &target_path)
+                .and_then(|_| DeployRecord::Symlinks.insert(&target_path, f)),
+        };
+
+        action.needs_root = matches!(&result, Err(err) if err.kind() == std::io::ErrorKind::PermissionDenied)
+            && group.needs_root_privilege();
+        actions.push(action.with_result(result));
     }
+
+    actions
+}
+
+/// Checks whether the target is a broken symlink into the dotfile's Configs directory,
+/// which is what's left behind when a deployed dotfile is moved or renamed
+fn is_stale_link(dotfile: &Dotfile, target: &Path, kind: &TargetKind) -> bool {
+    let Some(configs_dir) = dotfile.group_path.parent() else {
+        return false;
+    };
+
+    *kind == TargetKind::BrokenSymlink
+        && fs::read_link(target).is_ok_and(|link| link.starts_with(configs_dir))
 }
 
 #[enumflags2::bitflags]
@@ -78,30 +569,108 @@ enum SymlinkType {
 
 type HashCache = HashMap<String, HashSet<Dotfile>>;
 
+/// Removes entries for paths that are subpaths of another entry (canonicalization).
+/// this procedure makes so that symlinks are shallow.
+///
+/// shallow symlinking: only symlinking files/directories that don't exist already
+///
+/// not_symlinked is also canonicalized based on symlinked. This is necessary because if a directory
+/// is canonicalized and symlinked, files inside it won't be symlinked and thus marked as `not_symlinked` wrongly.
+fn canonicalize_caches(
+    configs_dir: &Path,
+    symlinked: &mut HashCache,
+    not_symlinked: &mut HashCache,
+    not_owned: &mut HashCache,
+) {
+    fn build_tree<'a>(configs_dir: &Path, cache: &'a HashCache) -> FileTree<'a> {
+        let mut tree = FileTree::new(configs_dir);
+        for (group, files) in cache {
+            for file in files {
+                tree.insert(Some(group), &file.path);
+            }
+        }
+        tree
+    }
+
+    let mut symlinked_tree = build_tree(configs_dir, symlinked);
+    let mut not_symlinked_tree = build_tree(configs_dir, not_symlinked);
+    let mut not_owned_tree = build_tree(configs_dir, not_owned);
+
+    not_symlinked_tree.canonicalize(Some(&symlinked_tree));
+    symlinked_tree.canonicalize(None);
+    not_owned_tree.canonicalize(None);
+
+    // the trees borrow the group names from the caches so what's left in them is collected first
+    fn remaining_paths(tree: &FileTree, cache: &HashCache) -> HashMap<String, HashSet<PathBuf>> {
+        cache
+            .keys()
+            .filter(|group| tree.contains_group(group))
+            .filter_map(|group| Some((group.clone(), tree.get(group)?)))
+            .collect()
+    }
+
+    let remaining = [
+        remaining_paths(&symlinked_tree, symlinked),
+        remaining_paths(&not_symlinked_tree, not_symlinked),
+        remaining_paths(&not_owned_tree, not_owned),
+    ];
+
+    for (cache, mut remaining) in [symlinked, not_symlinked, not_owned]
+        .into_iter()
+        .zip(remaining)
+    {
+        for (group, files) in cache.iter_mut() {
+            let paths = remaining.remove(group).unwrap_or_default();
+            files.retain(|file| paths.contains(&file.path));
+        }
+    }
+}
+
 /// Handles dotfile symlinking and their current status
-struct SymlinkHandler {
+pub struct SymlinkHandler {
+    ctx: Rc<Context>,
     dotfiles_dir: PathBuf,    // path to the dotfiles directory
     symlinked: HashCache,     // dotfiles that have been symlinked from Dotfiles/Configs
     not_symlinked: HashCache, // dotfiles that haven't been symlinked to $HOME yet
     not_owned: HashCache, // dotfiles that are symlinks but points somewhere outside of their respective Dotfiles/Configs's group dir
+    targets: HashMap<PathBuf, Vec<(PathBuf, TargetKind)>>, // what was found at each dotfile's targets during the scan
+    file_counts: HashMap<String, usize>, // how many files each group has, directories aren't counted
+    shadowed: HashSet<PathBuf>, // dotfiles not deployed since a higher priority conditional group deploys to the same target
+    warnings: Vec<String>, // problems found during the scan, eg: dotfiles that loop back to their targets
 }
 
 impl SymlinkHandler {
+    /// Same as `new` but the warnings and errors are printed and errors are turned into the command's exit code
+    fn try_new(ctx: &Context, profile: Option<String>) -> Result<Self, ExitCode> {
+        let sym = Self::new(ctx, profile).map_err(|err| report_symlink_error(err.into()))?;
+
+        for warning in &sym.warnings {
+            eprintln!("{}", warning.yellow());
+        }
+
+        Ok(sym)
+    }
+
     /// Initializes SymlinkHandler and fills it dotfiles' status information
-    fn try_new(profile: Option<String>) -> Result<Self, ExitCode> {
-        let dotfiles_dir = match dotfiles::get_dotfiles_path(profile) {
-            Ok(dir) => dir,
-            Err(e) => {
-                eprintln!("{e}");
-                return Err(ReturnCode::CouldntFindDotfiles.into());
-            }
-        };
+    pub fn new(ctx: &Context, profile: Option<String>) -> Result<Self, DotfileError> {
+        // targets are resolved relative to it
+        if ctx.target_dir.is_none() && dirs::home_dir().is_none() {
+            return Err(DotfileError::NoHomeDir);
+        }
+
+        let dotfiles_dir =
+            dotfiles::get_dotfiles_path(ctx, profile).map_err(DotfileError::DotfilesDirNotFound)?;
 
         let symlinker = SymlinkHandler {
+            ctx: Rc::new(ctx.clone()),
             dotfiles_dir,
             symlinked: HashCache::new(),
             not_symlinked: HashCache::new(),
             not_owned: HashCache::new(),
+            targets: HashMap::new(),
+            file_counts: HashMap::new(),
+            shadowed: HashSet::new(),
+            warnings: Vec::new(),
         };
 
         // this fills the symlinker with dotfile status information
@@ -114,52 +683,117 @@ impl SymlinkHandler {
     /// into the struct
     ///
     /// Returns a copy of self with all the fields set accordingly
-    fn validate(mut self) -> Result<Self, ExitCode> {
-        let configs_dir = Dotfile::try_from(self.dotfiles_dir.join("Configs")).unwrap();
+    fn validate(mut self) -> Result<Self, DotfileError> {
+        let configs_dir = Dotfile::new(&self.ctx, self.dotfiles_dir.join("Configs"))?;
+        let configs: Vec<_> = configs_dir.try_iter()?.collect();
+        // what's at each target is only looked up once, telling which files are shadowed needs it too
+        let mut scanned: HashMap<PathBuf, Vec<(PathBuf, TargetKind)>> = configs
+            .iter()
+            .filter(|f| f.path != f.group_path)
+            .map(|f| {
+                let target_kinds = f
+                    .to_target_paths()
+                    .into_iter()
+                    .map(|target| {
+                        let kind = f.target_exists_kind(&target);
+                        (target, kind)
+                    })
+                    .collect();
+                (f.path.clone(), target_kinds)
+            })
+            .collect();
+        let shadowed = get_shadowed_dotfiles(&configs, &scanned);
 
         let mut symlinked = HashCache::new();
         let mut not_symlinked = HashCache::new();
         let mut not_owned = HashCache::new();
+        let mut targets = HashMap::new();
+        let mut file_counts: HashMap<String, usize> = HashMap::new();
 
         // iterates over every file inside dotfiles/Config and determines their symlink status
-        for f in configs_dir.try_iter().unwrap() {
+        'files: for f in configs {
             // skip group directories otherwise it would try to link dotfiles/Configs/Groups to the users home
             if f.path == f.group_path {
                 continue;
             }
 
-            let target = f.to_target_path();
+            if !f.is_walked_dir() {
+                *file_counts.entry(f.group_name.clone()).or_default() += 1;
+            }
 
-            if target.is_symlink() {
-                let link = match fs::read_link(target) {
-                    Ok(link) => link,
-                    Err(err) => {
-                        eprintln!("{err}");
-                        continue;
-                    }
-                };
+            // the conditional group that takes precedence is the one that gets reported,
+            // inactive groups are only kept track of while still deployed so that they can be removed
+            let file_targets = scanned.remove(&f.path).unwrap_or_default();
+            let deployed = || {
+                file_targets
+                    .iter()
+                    .any(|(_, kind)| *kind == TargetKind::SymlinkToUs)
+            };
+            if shadowed.contains(&f.path) && (f.is_valid_target() || !deployed()) {
+                continue;
+            }
 
-                if link == f.path {
-                    symlinked.entry(f.group_name.clone()).or_default();
+            // a file deployed to multiple targets is only symlinked if every target points to it
+            let mut status: Option<SymlinkType> = None;
+            let mut target_kinds = Vec::new();
+            for (target, kind) in file_targets {
+                if f.source_loops_to(&target) {
+                    self.warnings.push(
+                        t!(
+                            "warn.x_loops_to_its_target",
+                            file = f.path.display(),
+                            target = target.display()
+                        )
+                        .to_string(),
+                    );
+                    continue 'files;
+                }
 
-                    let group = symlinked.get_mut(&f.group_name).unwrap();
-                    group.insert(f);
-                } else {
-                    not_owned.entry(f.group_name.clone()).or_default();
+                let target_status = match kind {
+                    TargetKind::SymlinkToUs | TargetKind::CopyOfUs => Some(SymlinkType::Symlinked),
+                    // left behind by a lower priority conditional group, it's replaced when adding
+                    TargetKind::SymlinkElsewhere(ref link) if shadowed.contains(link) => {
+                        Some(SymlinkType::NotSymlinked)
+                    }
+                    // left behind by a dotfile that was moved, it's also replaced when adding
+                    TargetKind::BrokenSymlink if is_stale_link(&f, &target, &kind) => {
+                        Some(SymlinkType::NotSymlinked)
+                    }
+                    TargetKind::SymlinkElsewhere(_) | TargetKind::BrokenSymlink => {
+                        Some(SymlinkType::NotOwned)
+                    }
+                    // the files inside of it are checked on their own
+                    TargetKind::PlainDir if f.is_walked_dir() => None,
+                    TargetKind::PlainDir | TargetKind::PlainFile | TargetKind::Absent => {
+                        Some(SymlinkType::NotSymlinked)
+                    }
+                };
+                target_kinds.push((target, kind));
 
-                    let group = not_owned.get_mut(&f.group_name).unwrap();
-                    group.insert(f);
-                }
-            } else {
-                if target.is_dir() {
+                let Some(target_status) = target_status else {
                     continue;
-                }
-
-                not_symlinked.entry(f.group_name.clone()).or_default();
+                };
 
-                let group = not_symlinked.get_mut(&f.group_name).unwrap();
-                group.insert(f);
+                status = match (status, target_status) {
+                    (Some(SymlinkType::NotOwned), _) | (_, SymlinkType::NotOwned) => {
+                        Some(SymlinkType::NotOwned)
+                    }
+                    (Some(SymlinkType::NotSymlinked), _) | (_, SymlinkType::NotSymlinked) => {
+                        Some(SymlinkType::NotSymlinked)
+                    }
+                    _ => Some(SymlinkType::Symlinked),
+                };
             }
+
+            let cache = match status {
+                Some(SymlinkType::Symlinked) => &mut symlinked,
+                Some(SymlinkType::NotSymlinked) => &mut not_symlinked,
+                Some(SymlinkType::NotOwned) => &mut not_owned,
+                None => continue,
+            };
+
+            targets.insert(f.path.clone(), target_kinds);
+            cache.entry(f.group_name.clone()).or_default().insert(f);
         }
 
         fn remove_empty_groups(group_type: HashCache) -> HashCache {
@@ -170,53 +804,81 @@ impl SymlinkHandler {
                 .collect()
         }
 
-        // removes entries for paths that are subpaths of another entry (canonicalization).
-        // this procedure makes so that symlinks are shallow.
-        //
-        // shallow symlinking: only symlinking files/directories that don't exist already
-        fn canonicalize_groups(groups: &mut HashCache) {
-            for files in groups.values_mut() {
-                let files_copy = files.clone();
+        canonicalize_caches(
+            &configs_dir.path,
+            &mut symlinked,
+            &mut not_symlinked,
+            &mut not_owned,
+        );
 
-                for file in &files_copy {
-                    for file2 in &files_copy {
-                        if file2.path != file.path && file2.path.starts_with(&file.path) {
-                            files.remove(file2);
-                        }
-                    }
-                }
-            }
-        }
+        self.symlinked = remove_empty_groups(symlinked);
+        self.not_symlinked = remove_empty_groups(not_symlinked);
+        self.not_owned = remove_empty_groups(not_owned);
+        self.targets = targets;
+        self.file_counts = file_counts;
+        self.shadowed = shadowed;
 
-        // canonicalizes not_symlinked based on symlinked cache
-        //
-        // this is necessary because if a directory is canonicalized and symlinked,
-        // files inside it won't be symlinked and thus marked as `not_symlinked` wrongly.
-        for (group, files) in &symlinked {
-            let Some(unsymlinked_group) = not_symlinked.get_mut(group) else {
-                continue;
-            };
+        Ok(self)
+    }
 
-            let unsymlinked_group_copy = unsymlinked_group.clone();
+    /// Returns the problems found while scanning the dotfiles that didn't keep them from being scanned
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
 
-            for file1 in files {
-                for file2 in unsymlinked_group_copy.iter() {
-                    if file2.path.starts_with(&file1.path) {
-                        unsymlinked_group.remove(file2);
-                    }
-                }
-            }
+    /// Returns how many files the group and its conditional groups have
+    fn file_count(&self, base_group: &str) -> usize {
+        self.file_counts
+            .iter()
+            .filter(|(group, _)| dotfiles::group_without_target(group) == base_group)
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    /// Returns what is found at each of the file's targets
+    ///
+    /// The result from the scan done by `validate` is reused so that links aren't read again,
+    /// files that weren't scanned are checked on the spot
+    fn target_kinds(&self, file: &Dotfile) -> Vec<(PathBuf, TargetKind)> {
+        match self.targets.get(&file.path) {
+            Some(target_kinds) => target_kinds.clone(),
+            None => file
+                .to_target_paths()
+                .into_iter()
+                .map(|target| {
+                    let kind = file.target_exists_kind(&target);
+                    (target, kind)
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the name of the profile `link` points into if it's not the one being used
+    fn link_profile(&self, link: &Path) -> Option<String> {
+        if link.starts_with(&self.dotfiles_dir) {
+            return None;
         }
 
-        canonicalize_groups(&mut symlinked);
-        canonicalize_groups(&mut not_symlinked);
-        canonicalize_groups(&mut not_owned);
+        let profile = dotfiles::get_dotfile_profile_from_path(&self.ctx, link);
+        let profile_dir = dotfiles::get_dotfiles_path(&self.ctx, profile.clone()).ok()?;
+        if profile_dir == self.dotfiles_dir || !link.starts_with(profile_dir.join("Configs")) {
+            return None;
+        }
 
-        self.symlinked = remove_empty_groups(symlinked);
-        self.not_symlinked = remove_empty_groups(not_symlinked);
-        self.not_owned = remove_empty_groups(not_owned);
+        Some(profile.unwrap_or_else(|| "default".into()))
+    }
 
-        Ok(self)
+    /// Returns the conditional group a target is deployed from when a group with a higher
+    /// priority should be deployed there instead
+    fn lower_priority_group(&self, kind: &TargetKind) -> Option<String> {
+        match kind {
+            TargetKind::SymlinkElsewhere(link) if self.shadowed.contains(link) => {
+                Dotfile::new(&self.ctx, link.clone())
+                    .ok()
+                    .map(|f| f.group_name)
+            }
+            _ => None,
+        }
     }
 
     fn is_empty(&self) -> bool {
@@ -293,68 +955,255 @@ impl SymlinkHandler {
     }
 
     /// Symlinks all the files of a group to the user's $HOME
-    fn add(&self, group: &str) {
+    ///
+    /// depth: directories up to this depth inside of the group are created instead of symlinked
+    /// apply: if false nothing is deployed, only the actions that would be taken are returned
+    pub fn add(
+        &self,
+        group: &str,
+        mode: DeployMode,
+        create_dirs: bool,
+        depth: Option<usize>,
+        apply: bool,
+    ) -> Report {
         let Some(groups) =
             self.get_related_conditional_groups(group, SymlinkType::NotSymlinked.into())
         else {
-            return;
+            return Report::default();
         };
 
+        let mut report = Report::default();
         for group in groups {
-            let group = Dotfile::try_from(self.dotfiles_dir.join("Configs").join(&group)).unwrap();
-            if group.path.exists() {
+            let group =
+                match Dotfile::new(&self.ctx, self.dotfiles_dir.join("Configs").join(&group)) {
+                    Ok(group) => group,
+                    Err(err) => {
+                        report.errors.push(err.to_string());
+                        continue;
+                    }
+                };
+            if group.path.exists() {
+                let files = match group.try_iter() {
+                    Ok(files) => files,
+                    Err(err) => {
+                        report.errors.push(err.to_string());
+                        continue;
+                    }
+                };
+
+                // files that resolve to the same target overwrite each other,
+                // which is almost always a mistake in the group's layout or its targets
+                let mut group_targets: HashMap<PathBuf, PathBuf> = HashMap::new();
+
                 // iterate through all the files in group_dir
-                group.try_iter().unwrap().for_each(|f| symlink_file(f.path));
-            } else {
-                eprintln!(
-                    "{}",
-                    t!("errors.no_dotfiles_for_group", group = group.group_name).red()
-                );
-            }
-        }
-    }
+                for f in files {
+                    if !f.is_walked_dir() {
+                        for target in f.to_target_paths() {
+                            if let Some(other) = group_targets.get(&target) {
+                                report.warnings.push(
+                                    t!(
+                                        "warn.duplicate_target",
+                                        file = f.path.display(),
+                                        other = other.display(),
+                                        group = f.group_name,
+                                        target = target.display()
+                                    )
+                                    .to_string(),
+                                );
+                            } else {
+                                group_targets.insert(target, f.path.clone());
+                            }
+                        }
+                    }
 
-    /// Deletes symlinks from $HOME if they're owned by dotfiles dir
-    fn remove(&self, group: &str) {
-        fn remove_symlink(file: PathBuf) {
-            let dotfile = Dotfile::try_from(file).unwrap();
-            let target_dotfile = dotfile.to_target_path();
-            let Ok(linked) = fs::read_link(&target_dotfile) else {
-                return;
-            };
+                    if self.shadowed.contains(&f.path) {
+                        continue;
+                    }
 
-            if dotfile.path != linked {
-                return;
-            }
+                    // links to a lower priority conditional group are replaced
+                    let replaced: Vec<_> = self
+                        .target_kinds(&f)
+                        .into_iter()
+                        .filter(|(_, kind)| self.lower_priority_group(kind).is_some())
+                        .map(|(target, _)| target)
+                        .collect();
+
+                    // files inside of a symlinked directory come along with it
+                    if report.actions.iter().any(|action| {
+                        action.action == ActionKind::Symlink
+                            && action.error.is_none()
+                            && f.path != action.source
+                            && f.path.starts_with(&action.source)
+                    }) {
+                        continue;
+                    }
 
-            if target_dotfile.is_dir() {
-                fs::remove_dir_all(&target_dotfile).unwrap();
+                    let create_dirs = create_dirs
+                        || depth.is_some_and(|depth| {
+                            f.group_relative_path().components().count() <= depth
+                        });
+                    report
+                        .actions
+                        .extend(symlink_file(&f, mode, create_dirs, apply, &replaced));
+                }
             } else {
-                fs::remove_file(&target_dotfile)
-                    .map_err(|err| format!("error with path `{}`: {err}", target_dotfile.display()))
-                    .unwrap();
+                report
+                    .errors
+                    .push(t!("errors.no_dotfiles_for_group", group = group.group_name).to_string());
             }
         }
 
+        report
+    }
+
+    /// Deletes symlinks from $HOME if they're owned by dotfiles dir
+    ///
+    /// restore: replaces the symlinks with a copy of the dotfiles they pointed to
+    /// trash: moves the targets into the trash directory instead of deleting them
+    /// apply: if false nothing is removed, only the actions that would be taken are returned
+    pub fn remove(&self, group: &str, restore: bool, trash: bool, apply: bool) -> Report {
         let Some(groups) =
             self.get_related_conditional_groups(group, SymlinkType::Symlinked.into())
         else {
-            return;
+            return Report::default();
         };
 
+        let mut report = Report::default();
         for group in groups {
-            let group = Dotfile::try_from(self.dotfiles_dir.join("Configs").join(&group)).unwrap();
+            let group =
+                match Dotfile::new(&self.ctx, self.dotfiles_dir.join("Configs").join(&group)) {
+                    Ok(group) => group,
+                    Err(err) => {
+                        report.errors.push(err.to_string());
+                        continue;
+                    }
+                };
 
             if !group.path.exists() {
-                eprintln!("{}", t!("errors.no_group", group = group.group_name).red());
+                report
+                    .errors
+                    .push(t!("errors.no_group", group = group.group_name).to_string());
                 continue;
             }
 
-            group
-                .try_iter()
-                .unwrap()
-                .for_each(|f| remove_symlink(f.path));
+            let files = match group.try_iter() {
+                Ok(files) => files,
+                Err(err) => {
+                    report.errors.push(err.to_string());
+                    continue;
+                }
+            };
+
+            for f in files {
+                // files inside of a symlinked directory go away along with it
+                if report.actions.iter().any(|action| {
+                    action.error.is_none()
+                        && f.path != action.source
+                        && f.path.starts_with(&action.source)
+                }) {
+                    continue;
+                }
+
+                report
+                    .actions
+                    .extend(remove_symlink(&f, restore, trash, apply));
+            }
+        }
+
+        report
+    }
+
+    /// Returns every symlink that points into dotfiles/Configs along with the path it points to
+    ///
+    /// Every symlink tuckr creates is recorded, including the ones of groups that have since
+    /// been deleted and the ones outside of the target directory.
+    /// Symlinks made before they were recorded are found by scanning $HOME and the directories
+    /// mirroring a group's file tree since those are the only places where tuckr could have created them
+    fn get_owned_symlinks(&self) -> Vec<(PathBuf, PathBuf)> {
+        let configs_dir = self.dotfiles_dir.join("Configs");
+        let owns = |link: &Path| -> Option<PathBuf> {
+            if !link.is_symlink() {
+                return None;
+            }
+
+            fs::read_link(link)
+                .ok()
+                .filter(|source| source.starts_with(&configs_dir))
+        };
+
+        let mut scanned_dirs = HashSet::new();
+        scanned_dirs.insert(self.ctx.target_dir());
+
+        if let Ok(files) = Dotfile::new(&self.ctx, configs_dir.clone()).and_then(|c| c.try_iter()) {
+            for f in files {
+                if f.path == f.group_path || !f.is_walked_dir() {
+                    continue;
+                }
+
+                for target in f.to_target_paths() {
+                    if target.is_dir() && !target.is_symlink() {
+                        scanned_dirs.insert(target);
+                    }
+                }
+            }
+        }
+
+        let mut owned_symlinks: BTreeMap<PathBuf, PathBuf> = DeployRecord::Symlinks
+            .targets()
+            .keys()
+            .filter_map(|link| Some((link.clone(), owns(link)?)))
+            .collect();
+
+        for dir in scanned_dirs {
+            let Ok(entries) = fs::read_dir(dir) else {
+                continue;
+            };
+
+            for entry in entries.flatten() {
+                let link = entry.path();
+                if let Some(source) = owns(&link) {
+                    owned_symlinks.insert(link, source);
+                }
+            }
+        }
+
+        owned_symlinks.into_iter().collect()
+    }
+
+    /// Deletes symlinks from $HOME that point to files that no longer exist in the groups
+    ///
+    /// The groups' conditional groups are pruned as well.
+    /// Returns how many symlinks were removed
+    fn prune(&self, groups: &[String], exclude: &[String]) -> Vec<Action> {
+        let wildcard = groups.contains(&"*".to_string());
+        let mut pruned = Vec::new();
+
+        for (link, source) in self.get_owned_symlinks() {
+            if source.exists() {
+                continue;
+            }
+
+            let Ok(source) = Dotfile::new(&self.ctx, source) else {
+                continue;
+            };
+
+            let base_group = dotfiles::group_without_target(&source.group_name);
+            let is_selected = wildcard
+                || groups
+                    .iter()
+                    .any(|group| dotfiles::group_without_target(group) == base_group);
+
+            if !is_selected || dotfiles::group_is_excluded(&source.group_name, exclude) {
+                continue;
+            }
+
+            let result = fs::remove_file(&link)
+                .or_else(|_| fs::remove_dir(&link))
+                .and_then(|_| DeployRecord::Symlinks.remove(&link));
+            pruned.push(Action::new(ActionKind::Prune, &source, &link).with_result(result));
         }
+
+        pruned
     }
 }
 
@@ -366,6 +1215,7 @@ impl SymlinkHandler {
 /// iterates over each group in the dotfiles and calls a function F giving it the SymlinkHandler
 /// instance and the name of the group that's being handled
 fn foreach_group<F: Fn(&SymlinkHandler, &String)>(
+    ctx: &Context,
     profile: Option<String>,
     groups: &[String],
     exclude: &[String],
@@ -373,52 +1223,87 @@ fn foreach_group<F: Fn(&SymlinkHandler, &String)>(
     func: F,
 ) -> Result<(), ExitCode> {
     // loads the runtime information needed to carry out actions
-    let sym = SymlinkHandler::try_new(profile.clone())?;
-
-    let groups = {
-        // detect if user provided an invalid group
-        // note: a group only is invalid only if the group itself or one of its related conditional groups don't exist
-        let valid_groups =
-            match dotfiles::check_invalid_groups(profile.clone(), DotfileType::Configs, groups) {
-                Some(invalid_groups) => {
-                    let mut valid_groups = Vec::new();
-                    let mut groups_checked_as_invalid = Vec::new();
-
-                    for group in invalid_groups {
-                        let valid_related_groups = sym.get_related_conditional_groups(
-                            &group,
-                            enumflags2::make_bitflags!(SymlinkType::{
-                                Symlinked|
-                                NotSymlinked|
-                                NotOwned
-                            }),
-                        );
-
-                        match valid_related_groups {
-                            Some(mut valid_related_groups) => {
-                                valid_groups.append(&mut valid_related_groups)
-                            }
-                            None => groups_checked_as_invalid.push(group.clone()),
-                        }
-                    }
+    let sym = SymlinkHandler::try_new(ctx, profile.clone())?;
+    foreach_group_in(&sym, profile, groups, exclude, symlinked, func)
+}
 
-                    for group in groups_checked_as_invalid {
-                        eprintln!("{}", t!("errors.x_doesnt_exist", x = group).red());
-                    }
+/// Same as `foreach_group` but reuses an already loaded SymlinkHandler,
+/// commands that go over the groups more than once use it to only scan the dotfiles once
+fn foreach_group_in<F: Fn(&SymlinkHandler, &String)>(
+    sym: &SymlinkHandler,
+    profile: Option<String>,
+    groups: &[String],
+    exclude: &[String],
+    symlinked: bool,
+    func: F,
+) -> Result<(), ExitCode> {
+    let (groups, invalid_groups) = select_groups(sym, profile, groups);
+    for group in invalid_groups {
+        eprintln!("{}", t!("errors.x_doesnt_exist", x = group).red());
+    }
 
-                    valid_groups
-                }
+    if groups.is_empty() {
+        return Err(ReturnCode::NoSetupFolder.into());
+    }
 
-                None => groups.to_vec(),
-            };
+    foreach_selected_group(sym, &groups, exclude, symlinked, func);
+    Ok(())
+}
+
+/// Expands the group patterns and splits them into the groups that exist and the ones that don't
+///
+/// note: a group only is invalid only if the group itself or one of its related conditional groups don't exist
+fn select_groups(
+    sym: &SymlinkHandler,
+    profile: Option<String>,
+    groups: &[String],
+) -> (Vec<String>, Vec<String>) {
+    let ctx = &sym.ctx;
+    let groups =
+        dotfiles::expand_group_patterns(ctx, profile.clone(), DotfileType::Configs, groups);
+    let Some(invalid_groups) =
+        dotfiles::check_invalid_groups(ctx, profile, DotfileType::Configs, &groups)
+    else {
+        return (groups, Vec::new());
+    };
+
+    let mut valid_groups: Vec<_> = groups
+        .iter()
+        .filter(|group| !invalid_groups.contains(group))
+        .cloned()
+        .collect();
+    let mut groups_checked_as_invalid = Vec::new();
+
+    for group in invalid_groups {
+        let valid_related_groups = sym.get_related_conditional_groups(
+            &group,
+            enumflags2::make_bitflags!(SymlinkType::{
+                Symlinked|
+                NotSymlinked|
+                NotOwned
+            }),
+        );
 
-        if valid_groups.is_empty() {
-            return Err(ReturnCode::NoSetupFolder.into());
+        match valid_related_groups {
+            Some(mut valid_related_groups) => valid_groups.append(&mut valid_related_groups),
+            None => groups_checked_as_invalid.push(group.clone()),
         }
+    }
 
-        valid_groups
-    };
+    (valid_groups, groups_checked_as_invalid)
+}
 
+/// Calls `func` on every group that was returned by `select_groups` that isn't excluded
+///
+/// symlinked: whether the wildcard stands for the groups that are yet to be symlinked or the symlinked ones
+fn foreach_selected_group<F: Fn(&SymlinkHandler, &String)>(
+    sym: &SymlinkHandler,
+    groups: &[String],
+    exclude: &[String],
+    symlinked: bool,
+    func: F,
+) {
+    let ctx = &sym.ctx;
     // handles wildcard
     if groups.contains(&"*".to_string()) {
         let symgroups = if symlinked {
@@ -430,411 +1315,2214 @@ fn foreach_group<F: Fn(&SymlinkHandler, &String)>(
         for group in symgroups.keys() {
             // Takes the name of the group to be passed the function
             // Ignore groups in the excludes array
-            if exclude.contains(group) {
+            if dotfiles::group_is_excluded(group, exclude) {
                 continue;
             }
 
             // Ignore conditional groups for other platforms.
             // To force linking a group of other target_os/target_family, use
             // explict argument passing instead of wildcard.
-            if !dotfiles::group_is_valid_target(group) {
+            if !dotfiles::group_is_valid_target(ctx, group) {
                 continue;
             }
 
             // do something with the group name
             // passing the sym context
-            func(&sym, group);
+            func(sym, group);
         }
 
-        return Ok(());
+        return;
     }
 
     for group in groups {
-        if exclude.contains(&group) {
+        if dotfiles::group_is_excluded(group, exclude) {
             continue;
         }
-        func(&sym, &group);
+        func(sym, group);
     }
+}
 
-    Ok(())
+/// Options that change how groups are deployed by `add_cmd`
+#[derive(Default, Clone)]
+pub struct AddOptions {
+    /// Override conflicting dotfiles
+    pub force: bool,
+    /// Don't override conflicting dotfiles even if a group's group.toml says to
+    pub no_force: bool,
+    /// Adopt conflicting dotfiles
+    pub adopt: bool,
+    /// Automatically answer yes to stdin prompts
+    pub assume_yes: bool,
+    /// Remove stale symlinks for files that were deleted from the groups
+    pub prune: bool,
+    /// Where overridden dotfiles are moved to instead of being deleted
+    pub backup_dir: Option<PathBuf>,
+    /// How the dotfiles are deployed
+    pub mode: DeployMode,
+    /// Create the group's directories instead of symlinking them, only files are symlinked
+    pub create_dirs: bool,
+    /// Create the group's directories up to this depth and symlink everything below it
+    pub depth: Option<usize>,
+    /// Ask what to do with each conflicting file
+    pub interactive: bool,
+    /// Print what was done as JSON instead of human readable text
+    pub json: bool,
+    /// Deploy files that need elevated privileges through sudo or doas
+    pub sudo: bool,
+    /// Show every change that will be made and ask once before making them
+    pub interactive_apply: bool,
 }
 
-/// Adds symlinks
-pub fn add_cmd(
-    profile: Option<String>,
-    groups: &[String],
-    exclude: &[String],
-    force: bool,
-    adopt: bool,
-    assume_yes: bool,
-) -> Result<(), ExitCode> {
-    if !assume_yes {
-        if force {
-            print!("Are you sure you want to override conflicts? (N/y) ");
-        } else if adopt {
-            print!("Are you sure you want to adopt conflicts? (N/y) ");
-        }
+/// What to do with a conflicting target when resolving conflicts interactively
+enum ConflictChoice {
+    Overwrite,
+    Adopt,
+    Skip,
+    Diff,
+}
 
-        if force || adopt {
+impl ConflictChoice {
+    /// Asks the user what to do with the conflicting target until a valid choice is given
+    fn prompt(target: &Path) -> Self {
+        loop {
+            print!(
+                "{} ",
+                t!("info.resolve_conflict", file = target.display()).yellow()
+            );
             std::io::stdout()
                 .flush()
                 .expect("Could not print to stdout");
 
             let mut answer = String::new();
-            std::io::stdin()
+            let read = std::io::stdin()
                 .read_line(&mut answer)
                 .expect("Could not read from stdin");
 
+            // there's nobody left to answer once stdin is closed
+            if read == 0 {
+                println!();
+                return Self::Skip;
+            }
+
             match answer.trim().to_lowercase().as_str() {
-                "y" | "yes" => (),
-                _ => return Ok(()),
+                "o" | "overwrite" => return Self::Overwrite,
+                "a" | "adopt" => return Self::Adopt,
+                "s" | "skip" => return Self::Skip,
+                "d" | "diff" => return Self::Diff,
+                choice => eprintln!("{}", t!("errors.invalid_choice", choice = choice).red()),
             }
         }
     }
+}
 
-    foreach_group(profile, groups, exclude, true, |sym, group| {
-        let remove_files_and_decide_if_adopt = |status_group: &HashCache, adopt: bool| {
-            let group = status_group.get(group);
-            if let Some(group_files) = group {
-                for file in group_files {
-                    let target_file = file.to_target_path();
+/// Asks what to do with each of the group's conflicting targets and applies the choices
+///
+/// Skipped targets are left as is, which means they won't be symlinked
+fn resolve_conflicts_interactively(
+    sym: &SymlinkHandler,
+    group: &str,
+    assume_yes: bool,
+    backup_dir: Option<&Path>,
+) {
+    let ctx = &sym.ctx;
+    let mut files: Vec<_> = [&sym.not_symlinked, &sym.not_owned]
+        .into_iter()
+        .filter_map(|cache| cache.get(group))
+        .flatten()
+        .collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    files.dedup();
+
+    for file in files {
+        for target in file.to_target_paths() {
+            match file.target_exists_kind(&target) {
+                TargetKind::Absent
+                | TargetKind::BrokenSymlink
+                | TargetKind::SymlinkToUs
+                | TargetKind::CopyOfUs => continue,
+                // the files inside of the directory are handled on their own
+                TargetKind::PlainDir if file.is_walked_dir() => continue,
+                _ => (),
+            }
 
-                    let deleted_file = if adopt { &file.path } else { &target_file };
+            let choice = loop {
+                if assume_yes {
+                    break ConflictChoice::Overwrite;
+                }
 
-                    if target_file.is_dir() {
-                        fs::remove_dir_all(deleted_file).unwrap();
-                    } else if target_file.is_file() {
-                        fs::remove_file(deleted_file).unwrap();
-                    }
+                match ConflictChoice::prompt(&target) {
+                    ConflictChoice::Diff => crate::fileops::print_file_diff(&target, &file.path),
+                    choice => break choice,
+                }
+            };
 
-                    if adopt {
-                        fs::rename(target_file, &file.path).unwrap();
-                    }
+            let result = match choice {
+                ConflictChoice::Skip | ConflictChoice::Diff => Ok(()),
+                ConflictChoice::Overwrite => match backup_dir {
+                    Some(backup_dir) => backup_target(ctx, &target, backup_dir).map(|backup| {
+                        if ctx.verbosity != Verbosity::Quiet {
+                            println!(
+                                "{}",
+                                t!(
+                                    "info.backed_up_x_to_y",
+                                    x = target.display(),
+                                    y = backup.display()
+                                )
+                            )
+                        }
+                    }),
+                    None if target.is_dir() && !target.is_symlink() => fs::remove_dir_all(&target),
+                    None => fs::remove_file(&target),
+                },
+                ConflictChoice::Adopt => if file.path.is_dir() {
+                    fs::remove_dir_all(&file.path)
+                } else {
+                    fs::remove_file(&file.path)
                 }
+                .and_then(|_| fs::rename(&target, &file.path)),
+            };
+
+            if let Err(err) = result {
+                eprintln!("{}", format!("{}: {err}", target.display()).red());
             }
-        };
-        // Symlink dotfile by force
-        if force {
-            remove_files_and_decide_if_adopt(&sym.not_owned, false);
-            remove_files_and_decide_if_adopt(&sym.not_symlinked, false);
         }
+    }
+}
 
-        // Discard dotfile and adopt the conflicting dotfile
-        if adopt {
-            remove_files_and_decide_if_adopt(&sym.not_owned, true);
-            remove_files_and_decide_if_adopt(&sym.not_symlinked, true);
+/// Moves target into the backup directory while keeping its path relative to $HOME
+///
+/// A numeric suffix is appended to the backup if a file with the same name was already backed up
+pub(crate) fn backup_target(
+    ctx: &Context,
+    target: &Path,
+    backup_dir: &Path,
+) -> std::io::Result<PathBuf> {
+    let relative_target: PathBuf = match dotfiles::get_target_basepath(ctx, target) {
+        Some(path) => path,
+        None => target
+            .components()
+            .filter(|c| matches!(c, Component::Normal(_)))
+            .collect(),
+    };
+
+    let mut backup = backup_dir.join(&relative_target);
+    let mut suffix = 1;
+    while backup.exists() || backup.is_symlink() {
+        let mut filename = relative_target.file_name().unwrap().to_os_string();
+        filename.push(format!(".{suffix}"));
+        backup.set_file_name(filename);
+        suffix += 1;
+    }
+
+    // the backup directory is only created once there's something to back up
+    fs::create_dir_all(backup.parent().unwrap())?;
+
+    // renaming fails if the backup directory is on another filesystem
+    if fs::rename(target, &backup).is_err() {
+        crate::fileops::copy_recursively(target, &backup)?;
+
+        if target.is_dir() && !target.is_symlink() {
+            fs::remove_dir_all(target)?;
+        } else {
+            fs::remove_file(target)?;
         }
+    }
 
-        sym.add(group)
-    })?;
+    Ok(backup)
+}
 
-    Ok(())
+/// Whether the group's conflicts are overridden, with `--force` or with `force = true` in its group.toml.
+///
+/// Conflicts are never overridden when they're resolved interactively
+fn group_is_forced(sym: &SymlinkHandler, group: &str, opts: &AddOptions) -> bool {
+    if opts.interactive {
+        return false;
+    }
+
+    opts.force
+        || (!opts.no_force
+            && dotfiles::GroupConfig::load(&sym.dotfiles_dir, group)
+                .defaults
+                .force)
 }
 
-/// Removes symlinks
-pub fn remove_cmd(
+/// Returns the groups being added whose conflicts are overridden, see `group_is_forced`
+fn forced_groups(
+    sym: &SymlinkHandler,
     profile: Option<String>,
     groups: &[String],
     exclude: &[String],
-) -> Result<(), ExitCode> {
-    foreach_group(profile, groups, exclude, false, |sym, p| sym.remove(p))?;
-    Ok(())
+    opts: &AddOptions,
+) -> Vec<String> {
+    let (groups, _) = select_groups(sym, profile, groups);
+    let forced = std::cell::RefCell::new(Vec::new());
+    foreach_selected_group(sym, &groups, exclude, true, |sym, group| {
+        if group_is_forced(sym, group, opts) {
+            forced.borrow_mut().push(group.clone());
+        }
+    });
+    forced.into_inner()
 }
 
-/// returns a cache with files in dotfiles that already exist in $HOME
-fn get_conflicts_in_cache(cache: &HashCache) -> HashCache {
-    let mut conflicts = HashCache::new();
+/// Deploys a single group the way `opts` says to,
+/// conflicting targets are overridden, backed up or adopted first if asked to
+///
+/// Nothing is prompted for unless `opts.interactive` is set
+fn deploy_group(sym: &SymlinkHandler, group: &str, opts: &AddOptions) -> Report {
+    let ctx = &sym.ctx;
+    let AddOptions {
+        adopt,
+        assume_yes,
+        ref backup_dir,
+        mode,
+        create_dirs,
+        depth,
+        interactive,
+        ..
+    } = *opts;
+
+    // options set in the group's group.toml
+    let defaults = dotfiles::GroupConfig::load(&sym.dotfiles_dir, group).defaults;
+    let force = group_is_forced(sym, group, opts);
+    let create_dirs = create_dirs || (depth.is_none() && defaults.only_files);
+
+    let mut report = Report::default();
+    let mut remove_files_and_decide_if_adopt = |status_group: &HashCache, adopt: bool| {
+        let group = status_group.get(group);
+        if let Some(group_files) = group {
+            for file in group_files {
+                // only one of the targets can be adopted, the others are discarded
+                let mut adopted = false;
+
+                for target_file in file.to_target_paths() {
+                    let adopt = adopt && !adopted && target_file.exists();
+
+                    if let (Some(backup_dir), false) = (backup_dir, adopt) {
+                        if !target_file.exists() && !target_file.is_symlink() {
+                            continue;
+                        }
 
-    // mark group as conflicting if at least one value already exists in $HOME
-    for files in cache.values() {
-        for file in files {
-            if !file.to_target_path().exists() || !file.is_valid_target() {
-                continue;
-            }
+                        let result = backup_target(ctx, &target_file, backup_dir);
+                        let mut action = Action::new(ActionKind::Backup, file, &target_file);
+                        action.backup = result.as_ref().ok().cloned();
+                        report.actions.push(action.with_result(result.map(|_| ())));
+                        continue;
+                    }
 
-            conflicts.entry(file.group_name.clone()).or_default();
-            let entry = conflicts.get_mut(&file.group_name).unwrap();
-            entry.insert(file.clone());
+                    let deleted_file = if adopt { &file.path } else { &target_file };
+
+                    let removed = if target_file.is_dir() {
+                        fs::remove_dir_all(deleted_file)
+                    } else if target_file.is_file() {
+                        fs::remove_file(deleted_file)
+                    } else {
+                        Ok(())
+                    };
+
+                    let result = removed.and_then(|_| {
+                        if adopt {
+                            fs::rename(&target_file, &file.path)?;
+                            adopted = true;
+                        }
+                        Ok(())
+                    });
+
+                    if let Err(err) = result {
+                        report
+                            .errors
+                            .push(format!("{}: {err}", deleted_file.display()));
+                    }
+                }
+            }
         }
+    };
+    if interactive {
+        resolve_conflicts_interactively(sym, group, assume_yes, backup_dir.as_deref());
     }
 
-    conflicts
+    // Symlink dotfile by force
+    if force {
+        remove_files_and_decide_if_adopt(&sym.not_owned, false);
+        remove_files_and_decide_if_adopt(&sym.not_symlinked, false);
+    }
+
+    // Discard dotfile and adopt the conflicting dotfile
+    if adopt {
+        remove_files_and_decide_if_adopt(&sym.not_owned, true);
+        remove_files_and_decide_if_adopt(&sym.not_symlinked, true);
+    }
+
+    report.extend(sym.add(group, mode, create_dirs, depth, true));
+    report
 }
 
-fn print_global_status(sym: &SymlinkHandler) -> Result<(), ExitCode> {
-    #[derive(Tabled, Debug)]
-    struct SymlinkRow<'a> {
-        #[tabled(rename = "Symlinked")]
-        symlinked: &'a str,
+/// Returns the groups that are conditional groups for other platforms,
+/// they're skipped which would go unnoticed when they were asked for by name.
+///
+/// It's an error if none of the groups can be deployed on this platform
+fn unsupported_groups(
+    ctx: &Context,
+    profile: Option<String>,
+    groups: &[String],
+) -> Result<Vec<String>, SymlinkError> {
+    let unsupported: Vec<_> = groups
+        .iter()
+        .filter(|group| {
+            !dotfiles::group_is_valid_target(ctx, group)
+                && dotfiles::dotfile_contains(ctx, profile.clone(), DotfileType::Configs, group)
+        })
+        .cloned()
+        .collect();
 
-        #[tabled(rename = "Not Symlinked")]
-        not_symlinked: &'a str,
+    if !unsupported.is_empty() && unsupported.len() == groups.len() {
+        return Err(SymlinkError::Unsupported(unsupported));
     }
 
-    // --- process status from symlink ---
-    // groups that are both in symlinked and not_symlinked
-    // will be marked as not_symlinked only
+    Ok(unsupported)
+}
 
-    let (symlinked, not_symlinked) = {
-        let mut not_symlinked: Vec<_> = sym
-            .not_symlinked
-            .keys()
-            .map(|group| group.as_str())
-            .collect();
+/// Deploys the groups along with their dependencies, nothing is prompted for unless `opts.interactive` is set
+///
+/// Returns everything that was done, or attempted, to the groups' targets.
+/// Targets that need elevated privileges are returned with `needs_root` set instead of being retried.
+/// Groups that don't exist are reported in the errors, it's only an error if none of them exist
+pub fn add(
+    ctx: &Context,
+    profile: Option<String>,
+    groups: &[String],
+    exclude: &[String],
+    opts: &AddOptions,
+) -> Result<Report, SymlinkError> {
+    let groups = dotfiles::resolve_group_dependencies(ctx, profile.clone(), groups)
+        .map_err(SymlinkError::Dependencies)?;
+    let unsupported = unsupported_groups(ctx, profile.clone(), &groups)?;
+    let sym = SymlinkHandler::new(ctx, profile.clone())?;
+
+    let mut report = Report {
+        warnings: sym.warnings.clone(),
+        ..Default::default()
+    };
+    report.warnings.extend(
+        unsupported
+            .iter()
+            .map(|group| t!("errors.x_not_supported_on_this_platform", x = group).into_owned()),
+    );
+    report.extend(add_in(&sym, profile, &groups, exclude, opts)?);
 
-        let mut symlinked: Vec<_> = sym
-            .symlinked
-            .keys()
-            .filter_map(|group| {
+    Ok(report)
+}
+
+/// Same as `add` but reuses an already loaded SymlinkHandler,
+/// the groups' dependencies have to be resolved beforehand
+fn add_in(
+    sym: &SymlinkHandler,
+    profile: Option<String>,
+    groups: &[String],
+    exclude: &[String],
+    opts: &AddOptions,
+) -> Result<Report, SymlinkError> {
+    let ctx = &sym.ctx;
+    let (groups, invalid_groups) = select_groups(sym, profile, groups);
+    if groups.is_empty() {
+        return Err(SymlinkError::NoSuchGroups(invalid_groups));
+    }
+
+    // files that more than one of the groups being added want to own,
+    // only one of them can end up deployed
+    let all_groups = groups.iter().any(|group| group == "*");
+    let shared_targets = get_shared_targets(sym, |group| {
+        let base_group = dotfiles::group_without_target(group);
+        !dotfiles::group_is_excluded(group, exclude)
+            && (all_groups
+                || groups
+                    .iter()
+                    .any(|g| dotfiles::group_without_target(g) == base_group))
+    });
+
+    let report = std::cell::RefCell::new(Report {
+        errors: invalid_groups
+            .iter()
+            .map(|group| t!("errors.x_doesnt_exist", x = group).into_owned())
+            .collect(),
+        shared_targets,
+        ..Default::default()
+    });
+    foreach_selected_group(sym, &groups, exclude, true, |sym, group| {
+        // deploying it to the rest of its targets would leave it half deployed
+        if dotfiles::group_has_invalid_target(ctx, &sym.dotfiles_dir, group) {
+            report
+                .borrow_mut()
+                .invalid_target_groups
+                .push(group.clone());
+            return;
+        }
+
+        report.borrow_mut().extend(deploy_group(sym, group, opts));
+    });
+
+    let mut report = report.into_inner();
+    if opts.prune {
+        // pruning looks for stale symlinks on the filesystem so the scan doesn't need refreshing
+        report.actions.extend(sym.prune(&groups, exclude));
+    }
+
+    Ok(report)
+}
+
+/// Adds symlinks
+pub(crate) fn add_cmd(
+    ctx: &Context,
+    profile: Option<String>,
+    groups: &[String],
+    exclude: &[String],
+    opts: &AddOptions,
+) -> Result<(), ExitCode> {
+    let AddOptions {
+        adopt,
+        assume_yes,
+        interactive,
+        json,
+        sudo,
+        interactive_apply,
+        ..
+    } = *opts;
+
+    let groups = &match dotfiles::resolve_group_dependencies(ctx, profile.clone(), groups) {
+        Ok(resolved) => {
+            if resolved.len() != groups.len() && !json {
+                print_resolved_group_order(ctx, &resolved);
+            }
+            resolved
+        }
+        Err(err) => return Err(report_symlink_error(SymlinkError::Dependencies(err))),
+    };
+
+    let unsupported =
+        unsupported_groups(ctx, profile.clone(), groups).map_err(report_symlink_error)?;
+    for group in unsupported {
+        eprintln!(
+            "{}",
+            t!("errors.x_not_supported_on_this_platform", x = group).yellow()
+        );
+    }
+
+    // the scan is shared by the plan, the deployment and pruning
+    let sym = SymlinkHandler::try_new(ctx, profile.clone())?;
+
+    if interactive_apply && !confirm_add_plan_in(&sym, profile.clone(), groups, exclude, opts)? {
+        return Ok(());
+    }
+
+    // group.toml can force a group, which is confirmed the same way as --force
+    let force = !forced_groups(&sym, profile.clone(), groups, exclude, opts).is_empty();
+    if !assume_yes && !interactive && !interactive_apply {
+        if force {
+            print!("Are you sure you want to override conflicts? (N/y) ");
+        } else if adopt {
+            print!("Are you sure you want to adopt conflicts? (N/y) ");
+        }
+
+        if force || adopt {
+            std::io::stdout()
+                .flush()
+                .expect("Could not print to stdout");
+
+            let mut answer = String::new();
+            std::io::stdin()
+                .read_line(&mut answer)
+                .expect("Could not read from stdin");
+
+            match answer.trim().to_lowercase().as_str() {
+                "y" | "yes" => (),
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    let mut report =
+        add_in(&sym, profile.clone(), groups, exclude, opts).map_err(report_symlink_error)?;
+    if !json {
+        print_shared_targets(&report.shared_targets);
+    }
+
+    let (mut needs_root, actions): (Vec<_>, Vec<_>) = std::mem::take(&mut report.actions)
+        .into_iter()
+        .partition(|action| action.needs_root);
+    report.actions = actions;
+    report.print(ctx.verbosity, json);
+
+    if sudo {
+        for action in &mut needs_root {
+            let result = run_elevated(action).and_then(|_| match action.action {
+                ActionKind::Copy | ActionKind::Hardlink => {
+                    DeployRecord::Copies.insert(&action.target, &action.source)
+                }
+                ActionKind::Symlink | ActionKind::Relink => {
+                    DeployRecord::Symlinks.insert(&action.target, &action.source)
+                }
+                _ => Ok(()),
+            });
+            action.needs_root = result.is_err();
+            action.error = result.err().map(|err| err.to_string());
+            if !json {
+                action.print(ctx.verbosity);
+            }
+        }
+    } else if !json {
+        print_needs_root(profile.as_deref(), &needs_root);
+    }
+
+    if json {
+        report.actions.append(&mut needs_root);
+        print_actions_json(&report.actions);
+    }
+
+    if !report.invalid_target_groups.is_empty() {
+        return Err(ReturnCode::InvalidTarget.into());
+    }
+
+    Ok(())
+}
+
+/// Shows the order groups are added in once their dependencies have been pulled in
+pub(crate) fn print_resolved_group_order(ctx: &Context, groups: &[String]) {
+    if ctx.verbosity == Verbosity::Quiet {
+        return;
+    }
+
+    println!(
+        "{}",
+        t!("info.resolved_group_order", groups = groups.join(", "))
+    );
+}
+
+/// Prints the changes that are about to be made and asks whether to make them
+///
+/// conflicts are shown as being overwritten for the forced groups or adopted when adopt is set
+fn confirm_plan(plan: &[Action], forced_groups: &[String], adopt: bool) -> bool {
+    if plan.is_empty() {
+        println!("{}", t!("info.nothing_to_do"));
+        return false;
+    }
+
+    println!("{}:", t!("info.planned_changes"));
+    for action in plan {
+        let target = action.target.display();
+        let change = match action.action {
+            ActionKind::Conflict if forced_groups.contains(&action.group) => {
+                t!("info.plan_overwrite_x", x = target).red().to_string()
+            }
+            ActionKind::Conflict if adopt => {
+                t!("info.plan_adopt_x", x = target).yellow().to_string()
+            }
+            ActionKind::Conflict => t!("info.plan_skip_x", x = target).dimmed().to_string(),
+            ActionKind::Remove => t!("info.plan_remove_x", x = target).to_string(),
+            ActionKind::Restore => t!("info.plan_restore_x", x = target).to_string(),
+            ActionKind::Trash => t!("info.plan_trash_x", x = target).to_string(),
+            ActionKind::Relink => t!(
+                "info.plan_relink_x_to_y",
+                x = target,
+                y = action.source.display()
+            )
+            .green()
+            .to_string(),
+            _ => t!(
+                "info.plan_deploy_x_to_y",
+                x = action.source.display(),
+                y = target
+            )
+            .green()
+            .to_string(),
+        };
+        println!("\t{change}");
+    }
+
+    print!("\n{} ", t!("warn.want_to_proceed"));
+    std::io::stdout().flush().unwrap();
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).unwrap();
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Works out what adding the groups would change and asks the user whether to go ahead
+pub(crate) fn confirm_add_plan(
+    ctx: &Context,
+    profile: Option<String>,
+    groups: &[String],
+    exclude: &[String],
+    opts: &AddOptions,
+) -> Result<bool, ExitCode> {
+    let sym = SymlinkHandler::try_new(ctx, profile.clone())?;
+    let groups = dotfiles::resolve_group_dependencies(ctx, profile.clone(), groups)
+        .map_err(|err| report_symlink_error(SymlinkError::Dependencies(err)))?;
+    confirm_add_plan_in(&sym, profile, &groups, exclude, opts)
+}
+
+fn confirm_add_plan_in(
+    sym: &SymlinkHandler,
+    profile: Option<String>,
+    groups: &[String],
+    exclude: &[String],
+    opts: &AddOptions,
+) -> Result<bool, ExitCode> {
+    // groups that don't exist are reported once they're deployed
+    let (selected, invalid_groups) = select_groups(sym, profile.clone(), groups);
+    if selected.is_empty() {
+        return Err(report_symlink_error(SymlinkError::NoSuchGroups(
+            invalid_groups,
+        )));
+    }
+
+    let plan = std::cell::RefCell::new(Vec::new());
+    foreach_selected_group(sym, &selected, exclude, true, |sym, group| {
+        let defaults = dotfiles::GroupConfig::load(&sym.dotfiles_dir, group).defaults;
+        let create_dirs = opts.create_dirs || (opts.depth.is_none() && defaults.only_files);
+        plan.borrow_mut().extend(
+            sym.add(group, opts.mode, create_dirs, opts.depth, false)
+                .actions,
+        );
+    });
+
+    let forced = forced_groups(sym, profile, groups, exclude, opts);
+    Ok(confirm_plan(&plan.into_inner(), &forced, opts.adopt))
+}
+
+/// Removes the groups' deployed dotfiles
+///
+/// restore: replaces the removed symlinks with a copy of the dotfiles they pointed to
+/// trash: moves the removed targets into the trash directory instead of deleting them
+///
+/// Groups that don't exist are reported in the errors, it's only an error if none of them exist
+pub fn remove(
+    ctx: &Context,
+    profile: Option<String>,
+    groups: &[String],
+    exclude: &[String],
+    restore: bool,
+    trash: bool,
+) -> Result<Report, SymlinkError> {
+    let sym = SymlinkHandler::new(ctx, profile.clone())?;
+
+    let mut report = Report {
+        warnings: sym.warnings.clone(),
+        ..Default::default()
+    };
+    report.extend(remove_in(
+        &sym, profile, groups, exclude, restore, trash, true,
+    )?);
+
+    Ok(report)
+}
+
+/// Same as `remove` but reuses an already loaded SymlinkHandler,
+/// nothing is removed unless `apply` is set
+fn remove_in(
+    sym: &SymlinkHandler,
+    profile: Option<String>,
+    groups: &[String],
+    exclude: &[String],
+    restore: bool,
+    trash: bool,
+    apply: bool,
+) -> Result<Report, SymlinkError> {
+    let (groups, invalid_groups) = select_groups(sym, profile, groups);
+    if groups.is_empty() {
+        return Err(SymlinkError::NoSuchGroups(invalid_groups));
+    }
+
+    let report = std::cell::RefCell::new(Report {
+        errors: invalid_groups
+            .iter()
+            .map(|group| t!("errors.x_doesnt_exist", x = group).into_owned())
+            .collect(),
+        ..Default::default()
+    });
+    foreach_selected_group(sym, &groups, exclude, false, |sym, group| {
+        report
+            .borrow_mut()
+            .extend(sym.remove(group, restore, trash, apply));
+    });
+
+    Ok(report.into_inner())
+}
+
+/// Removes symlinks
+///
+/// restore: replaces the removed symlinks with a copy of the dotfiles they pointed to
+/// trash: moves the removed targets into the trash directory instead of deleting them
+/// json: prints what was removed as JSON instead of human readable text
+/// interactive_apply: shows what will be removed and asks before removing anything
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn remove_cmd(
+    ctx: &Context,
+    profile: Option<String>,
+    groups: &[String],
+    exclude: &[String],
+    restore: bool,
+    trash: bool,
+    json: bool,
+    interactive_apply: bool,
+) -> Result<(), ExitCode> {
+    let sym = SymlinkHandler::try_new(ctx, profile.clone())?;
+
+    if interactive_apply {
+        // groups that don't exist are reported once they're removed
+        let plan = remove_in(
+            &sym,
+            profile.clone(),
+            groups,
+            exclude,
+            restore,
+            trash,
+            false,
+        )
+        .map_err(report_symlink_error)?;
+        if !confirm_plan(&plan.actions, &[], false) {
+            return Ok(());
+        }
+    }
+
+    let report = remove_in(&sym, profile, groups, exclude, restore, trash, true)
+        .map_err(report_symlink_error)?;
+    report.print(ctx.verbosity, json);
+
+    if json {
+        print_actions_json(&report.actions);
+    }
+
+    Ok(())
+}
+
+/// Returns every profile that has a dotfiles directory, the default profile is `None` and comes first
+fn list_profiles(ctx: &Context) -> Result<Vec<Option<String>>, ExitCode> {
+    let mut profiles = Vec::new();
+    if dotfiles::get_dotfiles_path(ctx, None).is_ok() {
+        profiles.push(None);
+    }
+    profiles.extend(crate::fileops::get_profiles(ctx).into_iter().map(Some));
+
+    if profiles.is_empty() {
+        eprintln!("{}", t!("errors.no_x_setup_yet", x = "profiles").red());
+        return Err(ReturnCode::CouldntFindDotfiles.into());
+    }
+
+    Ok(profiles)
+}
+
+/// Removes symlinks from every profile that has a dotfiles directory
+///
+/// Each target is only reported once, even if more than one profile deploys to it.
+/// Targets are checked again right before being removed so a target that was already
+/// removed by a previous profile is left alone.
+/// The include list is resolved against each profile's groups
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn remove_all_profiles_cmd(
+    ctx: &Context,
+    groups: &[String],
+    include: &[String],
+    exclude: &[String],
+    restore: bool,
+    trash: bool,
+    json: bool,
+    interactive_apply: bool,
+) -> Result<(), ExitCode> {
+    let profiles = list_profiles(ctx)?;
+
+    // runs remove for every profile, profiles that don't have the groups are skipped.
+    // the result is only an error if none of the profiles had them
+    let remove_from_profiles = |apply: bool| {
+        let mut handled_targets = HashSet::new();
+        let mut actions = Vec::new();
+        let mut result = Ok(());
+        let mut any_succeeded = false;
+
+        for profile in &profiles {
+            let groups = &dotfiles::include_groups(
+                ctx,
+                profile.clone(),
+                DotfileType::Configs,
+                groups,
+                include,
+            );
+            let profile_actions = std::cell::RefCell::new(Vec::new());
+            match foreach_group(ctx, profile.clone(), groups, exclude, false, |sym, p| {
+                let report = sym.remove(p, restore, trash, apply);
+                if apply {
+                    report.print(ctx.verbosity, true);
+                }
+                profile_actions.borrow_mut().extend(report.actions);
+            }) {
+                Ok(()) => any_succeeded = true,
+                Err(err) => result = Err(err),
+            }
+
+            actions.extend(
+                profile_actions
+                    .into_inner()
+                    .into_iter()
+                    .filter(|action| handled_targets.insert(action.target.clone())),
+            );
+        }
+
+        if any_succeeded {
+            result = Ok(());
+        }
+        result.map(|_| actions)
+    };
+
+    if interactive_apply && !confirm_plan(&remove_from_profiles(false)?, &[], false) {
+        return Ok(());
+    }
+
+    let actions = remove_from_profiles(true)?;
+    if json {
+        print_actions_json(&actions);
+    } else {
+        actions
+            .iter()
+            .for_each(|action| action.print(ctx.verbosity));
+    }
+
+    Ok(())
+}
+
+/// Removes every symlink owned by the profile's dotfiles directory, including stale ones
+///
+/// restore: replaces the removed symlinks with a copy of the dotfiles they pointed to
+/// assume_yes: removes the symlinks without showing them and asking first
+pub(crate) fn uninstall_cmd(
+    ctx: &Context,
+    profile: Option<String>,
+    restore: bool,
+    assume_yes: bool,
+) -> Result<(), ExitCode> {
+    let sym = SymlinkHandler::try_new(ctx, profile)?;
+    let owned_symlinks = sym.get_owned_symlinks();
+
+    if !assume_yes {
+        let plan: Vec<_> = owned_symlinks
+            .iter()
+            .map(|(link, source)| Action {
+                action: if restore && source.exists() {
+                    ActionKind::Restore
+                } else {
+                    ActionKind::Remove
+                },
+                group: Dotfile::new(ctx, source.clone())
+                    .map(|dotfile| dotfile.group_name)
+                    .unwrap_or_default(),
+                source: source.clone(),
+                target: link.clone(),
+                error: None,
+                needs_root: false,
+                backup: None,
+            })
+            .collect();
+
+        if !confirm_plan(&plan, &[], false) {
+            return Ok(());
+        }
+    }
+
+    let mut removed = 0;
+    let mut restored = 0;
+    let mut failed = false;
+
+    let mut report_err = |path: &std::path::Path, err: std::io::Error| {
+        eprintln!("{}", format!("{}: {err}", path.display()).red());
+        failed = true;
+    };
+
+    for (link, source) in owned_symlinks {
+        if let Err(err) = fs::remove_file(&link)
+            .or_else(|_| fs::remove_dir(&link))
+            .and_then(|_| DeployRecord::Symlinks.remove(&link))
+        {
+            report_err(&link, err);
+            continue;
+        }
+        removed += 1;
+
+        if !restore || !source.exists() {
+            continue;
+        }
+
+        match crate::fileops::copy_recursively(&source, &link) {
+            Ok(()) => restored += 1,
+            Err(err) => report_err(&link, err),
+        }
+    }
+
+    if ctx.verbosity != Verbosity::Quiet {
+        println!("{}", t!("info.removed_x_symlinks", count = removed).green());
+        if restore {
+            println!("{}", t!("info.restored_x_files", count = restored).green());
+        }
+    }
+
+    if failed {
+        Err(ExitCode::FAILURE)
+    } else {
+        Ok(())
+    }
+}
+
+/// returns a cache with files in dotfiles that already exist in $HOME
+fn get_conflicts_in_cache(sym: &SymlinkHandler, cache: &HashCache) -> HashCache {
+    let mut conflicts = HashCache::new();
+
+    // mark group as conflicting if at least one value already exists in $HOME
+    for files in cache.values() {
+        for file in files {
+            // links to lower priority conditional groups are replaced instead of conflicting
+            let target_exists = sym.target_kinds(file).iter().any(|(_, kind)| match kind {
+                TargetKind::SymlinkElsewhere(link) => !sym.shadowed.contains(link),
+                kind => kind.exists(),
+            });
+
+            if !target_exists || !file.is_valid_target() {
+                continue;
+            }
+
+            conflicts.entry(file.group_name.clone()).or_default();
+            let entry = conflicts.get_mut(&file.group_name).unwrap();
+            entry.insert(file.clone());
+        }
+    }
+
+    conflicts
+}
+
+/// Returns the files that aren't deployed because another conditional group of the same base group
+/// with a higher priority deploys to the same target
+///
+/// When the priorities are the same the file that is already deployed wins,
+/// otherwise the group that comes first by name does.
+/// Files of groups that aren't used on this platform are always shadowed by the ones that are.
+/// `scanned` holds what was found at each file's targets
+fn get_shadowed_dotfiles(
+    files: &[Dotfile],
+    scanned: &HashMap<PathBuf, Vec<(PathBuf, TargetKind)>>,
+) -> HashSet<PathBuf> {
+    let mut targets: HashMap<(&Path, &str), Vec<(&Dotfile, &TargetKind)>> = HashMap::new();
+    for f in files {
+        if f.path == f.group_path || f.is_walked_dir() {
+            continue;
+        }
+
+        let base_group = dotfiles::group_without_target(&f.group_name);
+        for (target, kind) in scanned.get(&f.path).into_iter().flatten() {
+            targets
+                .entry((target.as_path(), base_group))
+                .or_default()
+                .push((f, kind));
+        }
+    }
+
+    let mut shadowed = HashSet::new();
+    for candidates in targets.into_values() {
+        let Some((first, _)) = candidates.first() else {
+            continue;
+        };
+        // files of the same group that share a target are a mistake reported when adding
+        if candidates
+            .iter()
+            .all(|(f, _)| f.group_name == first.group_name)
+        {
+            continue;
+        }
+
+        let Some((winner, _)) = candidates
+            .iter()
+            .filter(|(f, _)| f.is_valid_target())
+            .max_by_key(|(f, kind)| {
+                let deployed = matches!(kind, TargetKind::SymlinkToUs | TargetKind::CopyOfUs);
+                (
+                    dotfiles::group_priority(&f.group_name),
+                    deployed,
+                    std::cmp::Reverse(&f.group_name),
+                )
+            })
+        else {
+            continue;
+        };
+
+        for (f, _) in &candidates {
+            if f.group_name != winner.group_name {
+                shadowed.insert(f.path.clone());
+            }
+        }
+    }
+
+    shadowed
+}
+
+/// Returns the target paths that more than one group deploys a file to
+///
+/// Only groups for which `is_selected` returns true are taken into account.
+/// Conditional groups don't conflict with their base group since they're meant to be deployed together.
+/// The targets found by the scan are reused so the dotfiles aren't walked again
+fn get_shared_targets(
+    sym: &SymlinkHandler,
+    is_selected: impl Fn(&str) -> bool,
+) -> BTreeMap<PathBuf, Vec<Dotfile>> {
+    let mut targets: BTreeMap<PathBuf, Vec<Dotfile>> = BTreeMap::new();
+    for (path, target_kinds) in &sym.targets {
+        let Ok(f) = Dotfile::with_shared_context(sym.ctx.clone(), path.clone()) else {
+            continue;
+        };
+
+        // directories are expected to be shared, only the files inside them can collide
+        if f.is_walked_dir() || !is_selected(&f.group_name) || !f.is_valid_target() {
+            continue;
+        }
+
+        for (target, _) in target_kinds {
+            targets.entry(target.clone()).or_default().push(f.clone());
+        }
+    }
+
+    targets.retain(|_, files| {
+        files.sort_by(|a, b| a.group_name.cmp(&b.group_name));
+        let base_group = dotfiles::group_without_target(&files[0].group_name);
+        files
+            .iter()
+            .any(|f| dotfiles::group_without_target(&f.group_name) != base_group)
+    });
+
+    targets
+}
+
+/// Formats how many files a group has, eg: `(3 files)`
+fn format_file_count(count: usize) -> String {
+    if count == 1 {
+        t!("info.single_file_count").into_owned()
+    } else {
+        t!("info.file_count", count = count).into_owned()
+    }
+}
+
+fn print_shared_targets(shared_targets: &BTreeMap<PathBuf, Vec<Dotfile>>) {
+    if shared_targets.is_empty() {
+        return;
+    }
+
+    println!("{}:", t!("warn.shared_targets").yellow());
+    for (target, files) in shared_targets {
+        let groups: Vec<_> = files.iter().map(|f| f.group_name.as_str()).collect();
+        println!("	{} ({})", target.display(), groups.join(", "));
+    }
+    println!();
+}
+
+fn print_global_status(sym: &SymlinkHandler, count: bool) -> Result<(), ExitCode> {
+    let ctx = &sym.ctx;
+    #[derive(Tabled, Debug)]
+    struct SymlinkRow<'a> {
+        #[tabled(rename = "Symlinked")]
+        symlinked: &'a str,
+
+        #[tabled(rename = "Not Symlinked")]
+        not_symlinked: &'a str,
+    }
+
+    // --- process status from symlink ---
+    // groups that are both in symlinked and not_symlinked
+    // will be marked as not_symlinked only
+
+    let (symlinked, not_symlinked) = {
+        let mut not_symlinked: Vec<_> = sym
+            .not_symlinked
+            .keys()
+            .map(|group| group.as_str())
+            .collect();
+
+        let mut symlinked: Vec<_> = sym
+            .symlinked
+            .keys()
+            .filter_map(|group| {
                 if sym
                     .get_related_conditional_groups(group, SymlinkType::NotSymlinked.into())
                     .is_none()
                 {
                     Some(dotfiles::group_without_target(group))
                 } else {
-                    not_symlinked.push(group);
-                    None
+                    not_symlinked.push(group);
+                    None
+                }
+            })
+            .collect();
+
+        let mut not_symlinked: Vec<_> = not_symlinked
+            .iter()
+            .map(|group| dotfiles::group_without_target(group))
+            .collect();
+
+        symlinked.sort();
+        symlinked.dedup();
+
+        not_symlinked.sort();
+        not_symlinked.dedup();
+
+        (symlinked, not_symlinked)
+    };
+
+    // groups are shown along with how many files they have when counting
+    let label = |group: &&str| -> String {
+        if count {
+            format!("{group} {}", format_file_count(sym.file_count(group)))
+        } else {
+            group.to_string()
+        }
+    };
+    let symlinked_labels: Vec<_> = symlinked.iter().map(label).collect();
+    let not_symlinked_labels: Vec<_> = not_symlinked.iter().map(label).collect();
+    let empty = String::new();
+
+    let status_rows: Vec<SymlinkRow> = {
+        let (longest, shortest, symlinked_is_longest) =
+            if symlinked_labels.len() >= not_symlinked_labels.len() {
+                (&symlinked_labels, &not_symlinked_labels, true)
+            } else {
+                (&not_symlinked_labels, &symlinked_labels, false)
+            };
+
+        longest
+            .iter()
+            .zip(shortest.iter().chain(std::iter::repeat(&empty)))
+            .map(|(longest, shortest)| SymlinkRow {
+                symlinked: if symlinked_is_longest {
+                    longest
+                } else {
+                    shortest
+                },
+
+                not_symlinked: if symlinked_is_longest {
+                    shortest
+                } else {
+                    longest
+                },
+            })
+            .collect()
+    };
+
+    // --- detect conflicts ---
+    let conflicts = get_conflicts_in_cache(sym, &sym.not_symlinked);
+    let shared_targets = get_shared_targets(sym, |_| true);
+    // whether a conflict is a symlink or a pre-existing file does not matter for global status
+    // so we just add them together
+    let conflicts: HashSet<_> = conflicts
+        .keys()
+        .chain(sym.not_owned.keys())
+        .map(|group| group.as_str())
+        .chain(
+            shared_targets
+                .values()
+                .flatten()
+                .map(|f| dotfiles::group_without_target(&f.group_name)),
+        )
+        .collect();
+
+    // --- Creates all the tables and prints them ---
+    use tabled::{
+        col, format::Format, object::Columns, object::Rows, Alignment, Margin, Modify, Style,
+    };
+
+    let mut sym_table = Table::new(status_rows);
+    sym_table
+        .with(Style::rounded())
+        .with(Margin::new(4, 4, 1, 1))
+        .with(Modify::new(Rows::first()).with(Format::new(|s| s.default_color().to_string())))
+        .with(Modify::new(Columns::single(0)).with(Format::new(|s| s.green().to_string())))
+        .with(Modify::new(Columns::single(1)).with(Format::new(|s| s.red().to_string())));
+
+    let mut conflict_table = Table::builder(&conflicts)
+        .set_columns(["Conflicting Dotfiles".yellow().to_string()])
+        .clone()
+        .build();
+    conflict_table
+        .with(Style::empty())
+        .with(Alignment::center());
+
+    // Creates a table with sym_table and conflict_table
+    let mut final_table = if conflicts.is_empty() {
+        col![sym_table]
+    } else {
+        col![sym_table, conflict_table]
+    };
+
+    final_table.with(Style::empty()).with(Alignment::center());
+    println!("{final_table}");
+
+    print_shared_targets(&shared_targets);
+
+    // groups that look not symlinked because they're symlinked by another profile
+    let mut other_profiles: Vec<_> = sym
+        .not_owned
+        .iter()
+        .flat_map(|(group, files)| files.iter().map(move |f| (group, f)))
+        .flat_map(|(group, f)| {
+            sym.target_kinds(f)
+                .into_iter()
+                .filter_map(|(_, kind)| match kind {
+                    TargetKind::SymlinkElsewhere(link) => sym.link_profile(&link),
+                    _ => None,
+                })
+                .map(move |profile| (dotfiles::group_without_target(group), profile))
+        })
+        .collect();
+    other_profiles.sort();
+    other_profiles.dedup();
+
+    for (group, profile) in other_profiles {
+        println!(
+            "{}",
+            t!(
+                "warn.x_symlinked_by_profile_y",
+                group = group,
+                profile = profile
+            )
+            .yellow()
+        );
+    }
+
+    if !conflicts.is_empty() && ctx.verbosity != Verbosity::Quiet {
+        println!(
+            "\n{}",
+            t!(
+                "info.learn_more_about_conflicts",
+                cmd = "tuckr status <group...>"
+            )
+        );
+    }
+
+    // Determines exit code for the command based on the dotfiles' status
+    if !symlinked.is_empty() && not_symlinked.is_empty() && conflicts.is_empty() {
+        Ok(())
+    } else {
+        Err(ExitCode::FAILURE)
+    }
+}
+
+fn print_groups_status(
+    profile: Option<String>,
+    sym: &SymlinkHandler,
+    groups: Vec<String>,
+    count: bool,
+) -> Result<(), ExitCode> {
+    let ctx = &sym.ctx;
+    let get_related_groups =
+        |sym: &SymlinkHandler, not_symlinked_groups: Option<&Vec<String>>| -> Vec<String> {
+            let mut related_groups = Vec::new();
+
+            let symlinked = not_symlinked_groups.is_some();
+
+            // merges conditional groups into their base group
+            // eg: `dotfile_unix` gets merged into the `dotfile` group
+            for base_group in &groups {
+                let Some(related_cond_groups) = sym.get_related_conditional_groups(
+                    base_group,
+                    if symlinked {
+                        SymlinkType::Symlinked.into()
+                    } else {
+                        make_bitflags!(SymlinkType::{NotSymlinked | NotOwned})
+                    },
+                ) else {
+                    continue;
+                };
+
+                for group in related_cond_groups {
+                    match not_symlinked_groups {
+                        Some(not_symlinked) => {
+                            if not_symlinked.contains(&group) {
+                                continue;
+                            }
+                        }
+
+                        None => {
+                            if !sym.not_symlinked.contains_key(&group)
+                                && !sym.not_owned.contains_key(&group)
+                            {
+                                continue;
+                            }
+                        }
+                    }
+
+                    related_groups.push(group);
                 }
+            }
+
+            related_groups.sort();
+            related_groups.dedup();
+            related_groups
+        };
+
+    let not_symlinked = get_related_groups(sym, None);
+    let symlinked = get_related_groups(sym, Some(&not_symlinked));
+
+    let not_owned: HashCache = sym
+        .not_owned
+        .clone()
+        .into_iter()
+        .filter(|(group, _)| groups.contains(group))
+        .collect();
+
+    let unsupported = {
+        let mut unsupported = groups
+            .iter()
+            .filter_map(|group| {
+                Dotfile::new(ctx, sym.dotfiles_dir.join("Configs").join(group)).ok()
             })
-            .collect();
+            .filter(|group| !group.is_valid_target())
+            .map(|group| group.group_name)
+            .collect::<Vec<_>>();
 
-        let mut not_symlinked: Vec<_> = not_symlinked
+        unsupported.sort();
+        unsupported.dedup();
+        unsupported
+    };
+
+    // the description from the group's group.toml
+    let description = |group: &str| {
+        dotfiles::GroupConfig::load(&sym.dotfiles_dir, group)
+            .description
+            .map(|description| format!(" - {}", description.dimmed()))
+            .unwrap_or_default()
+    };
+
+    // how many files are in the group, only shown when counting
+    let file_count = |group: &str| {
+        if count {
+            let count = sym.file_counts.get(group).copied().unwrap_or_default();
+            format!(" {}", format_file_count(count).dimmed())
+        } else {
+            String::new()
+        }
+    };
+
+    if !not_symlinked.is_empty() || !not_owned.is_empty() {
+        let print_conflicts =
+            |conflicts_cache: &HashCache, group: &str, msg: &dyn Fn(&TargetKind) -> String| {
+                let Some(conflicts) = conflicts_cache.get(group) else {
+                    return;
+                };
+
+                for file in conflicts {
+                    if file.group_name != group {
+                        continue;
+                    }
+
+                    for (conflict, kind) in sym.target_kinds(file) {
+                        println!("\t\t-> {} ({})", conflict.display(), msg(&kind));
+                    }
+                }
+            };
+
+        let file_conflicts = get_conflicts_in_cache(sym, &sym.not_symlinked);
+        let already_exists = |_: &TargetKind| t!("errors.already_exists").into_owned();
+        // symlinks into another profile's dotfiles are called out so that
+        // it's clear why the group isn't symlinked for the current profile
+        let symlinks_elsewhere = |kind: &TargetKind| {
+            match kind {
+                TargetKind::SymlinkElsewhere(link) => sym.link_profile(link),
+                _ => None,
+            }
+            .map(|profile| t!("errors.owned_by_profile", profile = profile).into_owned())
+            .unwrap_or_else(|| t!("errors.symlinks_elsewhere").into_owned())
+        };
+
+        println!("{}:", t!("table-column.not_symlinked"));
+        for group in &not_symlinked {
+            println!(
+                "\t{}{}{}",
+                group.red(),
+                file_count(group),
+                description(group)
+            );
+            print_conflicts(&file_conflicts, group, &already_exists);
+            print_conflicts(&sym.not_owned, group, &symlinks_elsewhere);
+
+            // targets still deployed from a lower priority conditional group or from a dotfile
+            // that has been moved, adding the group replaces them
+            for file in sym.not_symlinked.get(group).into_iter().flatten() {
+                for (target, kind) in sym.target_kinds(file) {
+                    let reason = match sym.lower_priority_group(&kind) {
+                        Some(source) => t!("errors.deployed_from_lower_priority_x", group = source),
+                        None if is_stale_link(file, &target, &kind) => t!("errors.stale_symlink"),
+                        None => continue,
+                    };
+                    println!("\t\t-> {} ({reason})", target.display());
+                }
+            }
+        }
+
+        println!();
+    }
+
+    if !symlinked.is_empty() {
+        println!("{}:", t!("table-column.symlinked"));
+        for group in symlinked {
+            println!(
+                "\t{}{}{}",
+                group.green(),
+                file_count(&group),
+                description(&group)
+            );
+        }
+        println!();
+    }
+
+    print_group_variants(sym, &groups);
+
+    let shared_targets = get_shared_targets(sym, |_| true);
+    let shared_targets: BTreeMap<_, _> = shared_targets
+        .into_iter()
+        .filter(|(_, files)| {
+            files.iter().any(|f| {
+                groups.iter().any(|g| {
+                    dotfiles::group_without_target(g)
+                        == dotfiles::group_without_target(&f.group_name)
+                })
+            })
+        })
+        .collect();
+    print_shared_targets(&shared_targets);
+
+    if !unsupported.is_empty() {
+        println!("{}:", t!("errors.not_supported_on_this_platform"));
+        for group in unsupported {
+            println!("\t{}", group.yellow());
+        }
+        println!();
+    }
+
+    let invalid_groups =
+        dotfiles::check_invalid_groups(ctx, profile, DotfileType::Configs, &groups);
+    if let Some(invalid_groups) = &invalid_groups {
+        eprintln!("{}:", t!("errors.following_groups_dont_exist"));
+        for group in invalid_groups {
+            eprintln!("\t{}", group.red());
+        }
+        println!();
+    }
+
+    if !not_symlinked.is_empty() && ctx.verbosity != Verbosity::Quiet {
+        println!(
+            "{}",
+            t!("info.learn_how_to_fix_symlinks", cmd = "tuckr help add")
+        );
+    }
+
+    if invalid_groups.is_none() {
+        return Err(ReturnCode::NoSetupFolder.into());
+    }
+
+    Ok(())
+}
+
+/// Prints the conditional groups of each of the base groups and whether they're used on this platform
+fn print_group_variants(sym: &SymlinkHandler, groups: &[String]) {
+    let ctx = &sym.ctx;
+    let Ok(entries) = fs::read_dir(sym.dotfiles_dir.join("Configs")) else {
+        return;
+    };
+
+    let all_groups: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+
+    let mut base_groups: Vec<_> = groups
+        .iter()
+        .filter(|group| !dotfiles::group_ends_with_target_name(group))
+        .collect();
+    base_groups.sort();
+    base_groups.dedup();
+
+    for base_group in base_groups {
+        let mut variants: Vec<_> = all_groups
             .iter()
-            .map(|group| dotfiles::group_without_target(group))
+            .filter(|group| {
+                *group != base_group
+                    && dotfiles::group_ends_with_target_name(group)
+                    && dotfiles::group_without_target(group) == base_group
+            })
             .collect();
 
-        symlinked.sort();
-        symlinked.dedup();
+        if variants.is_empty() {
+            continue;
+        }
+        variants.sort();
+
+        println!("{}:", t!("info.variants_of_x", group = base_group));
+        for variant in variants {
+            if dotfiles::group_is_valid_target(ctx, variant) {
+                println!("\t{} ({})", variant.green(), t!("info.active"));
+            } else {
+                println!("\t{} ({})", variant.dimmed(), t!("info.inactive"));
+            }
+        }
+        println!();
+    }
+}
+
+/// Prints symlinks that are owned by the dotfiles dir but whose group no longer exists
+fn print_orphans_status(sym: &SymlinkHandler) -> Result<(), ExitCode> {
+    let ctx = &sym.ctx;
+    let mut orphans: HashMap<String, Vec<PathBuf>> = HashMap::new();
+
+    for (link, source) in sym.get_owned_symlinks() {
+        let Ok(source) = Dotfile::new(ctx, source) else {
+            continue;
+        };
 
-        not_symlinked.sort();
-        not_symlinked.dedup();
+        if source.group_path.exists() {
+            continue;
+        }
 
-        (symlinked, not_symlinked)
-    };
+        orphans.entry(source.group_name).or_default().push(link);
+    }
 
-    let status_rows: Vec<SymlinkRow> = {
-        let (longest, shortest, symlinked_is_longest) = if symlinked.len() >= not_symlinked.len() {
-            (&symlinked, &not_symlinked, true)
-        } else {
-            (&not_symlinked, &symlinked, false)
-        };
+    if orphans.is_empty() {
+        println!("{}", t!("info.no_orphaned_symlinks").green());
+        return Ok(());
+    }
 
-        longest
-            .iter()
-            .zip(shortest.iter().chain(std::iter::repeat(&"")))
-            .map(|(longest, shortest)| SymlinkRow {
-                symlinked: if symlinked_is_longest {
-                    longest
-                } else {
-                    shortest
-                },
+    let mut orphans: Vec<_> = orphans.into_iter().collect();
+    orphans.sort();
 
-                not_symlinked: if symlinked_is_longest {
-                    shortest
-                } else {
-                    longest
-                },
-            })
-            .collect()
-    };
+    println!("{}:", t!("errors.orphaned_symlinks"));
+    for (group, mut links) in orphans {
+        links.sort();
 
-    // --- detect conflicts ---
-    let conflicts = get_conflicts_in_cache(&sym.not_symlinked);
-    // whether a conflict is a symlink or a pre-existing file does not matter for global status
-    // so we just add them together
-    let conflicts: HashSet<_> = conflicts.keys().chain(sym.not_owned.keys()).collect();
+        println!("\t{}", group.red());
+        for link in links {
+            println!("\t\t-> {}", link.display());
+        }
+    }
 
-    // --- Creates all the tables and prints them ---
-    use tabled::{
-        col, format::Format, object::Columns, object::Rows, Alignment, Margin, Modify, Style,
-    };
+    Err(ExitCode::FAILURE)
+}
 
-    let mut sym_table = Table::new(status_rows);
-    sym_table
-        .with(Style::rounded())
-        .with(Margin::new(4, 4, 1, 1))
-        .with(Modify::new(Rows::first()).with(Format::new(|s| s.default_color().to_string())))
-        .with(Modify::new(Columns::single(0)).with(Format::new(|s| s.green().to_string())))
-        .with(Modify::new(Columns::single(1)).with(Format::new(|s| s.red().to_string())));
+/// Prints the differences between the conflicting files found on the targets and the dotfiles
+/// that would replace them
+///
+/// All groups are diffed if none are supplied
+pub(crate) fn diff_cmd(
+    ctx: &Context,
+    profile: Option<String>,
+    groups: &[String],
+    exclude: &[String],
+) -> Result<(), ExitCode> {
+    let sym = SymlinkHandler::try_new(ctx, profile.clone())?;
 
-    let mut conflict_table = Table::builder(&conflicts)
-        .set_columns(["Conflicting Dotfiles".yellow().to_string()])
-        .clone()
-        .build();
-    conflict_table
-        .with(Style::empty())
-        .with(Alignment::center());
+    if let Some(invalid_groups) =
+        dotfiles::check_invalid_groups(ctx, profile, DotfileType::Configs, groups)
+    {
+        for group in invalid_groups {
+            eprintln!("{}", t!("errors.x_doesnt_exist", x = group).red());
+        }
+        return Err(ReturnCode::NoSuchFileOrDir.into());
+    }
 
-    // Creates a table with sym_table and conflict_table
-    let mut final_table = if conflicts.is_empty() {
-        col![sym_table]
-    } else {
-        col![sym_table, conflict_table]
+    let is_selected = |group: &str| {
+        let base_group = dotfiles::group_without_target(group);
+        let selected = groups.is_empty()
+            || groups.contains(&"*".to_string())
+            || groups
+                .iter()
+                .any(|g| dotfiles::group_without_target(g) == base_group);
+
+        selected && !dotfiles::group_is_excluded(group, exclude)
     };
 
-    final_table.with(Style::empty()).with(Alignment::center());
-    println!("{final_table}");
+    let mut conflicts: Vec<_> = get_conflicts_in_cache(&sym, &sym.not_symlinked)
+        .into_values()
+        .chain(sym.not_owned.clone().into_values())
+        .flatten()
+        .filter(|file| is_selected(&file.group_name) && file.is_valid_target())
+        .collect();
+    conflicts.sort_by(|a, b| a.path.cmp(&b.path));
+    conflicts.dedup();
+
+    let mut found_differences = false;
+    for file in conflicts {
+        for (target, kind) in sym.target_kinds(&file) {
+            match kind {
+                TargetKind::PlainFile | TargetKind::SymlinkElsewhere(_) => (),
+                TargetKind::PlainDir if !file.is_walked_dir() => (),
+                _ => continue,
+            }
 
-    if !conflicts.is_empty() {
-        println!(
-            "\n{}",
-            t!(
-                "info.learn_more_about_conflicts",
-                cmd = "tuckr status <group...>"
-            )
-        );
+            found_differences = true;
+            crate::fileops::print_file_diff(&target, &file.path);
+        }
     }
 
-    // Determines exit code for the command based on the dotfiles' status
-    if !symlinked.is_empty() && not_symlinked.is_empty() && conflicts.is_empty() {
-        Ok(())
-    } else {
+    if found_differences {
         Err(ExitCode::FAILURE)
+    } else {
+        Ok(())
     }
 }
 
-fn print_groups_status(
+/// Returns whether `a` and `b` are hardlinks to the same file
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (fs::metadata(a), fs::metadata(b)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        }
+    }
+
+    #[cfg(target_family = "windows")]
+    {
+        _ = (a, b);
+        false
+    }
+}
+
+/// Renames a group and its conditional groups in Configs, Hooks and Secrets,
+/// eg: renaming `zsh` to `shell` also renames `zsh_linux` to `shell_linux`
+///
+/// References to the groups in tuckr.toml and in the groups' dependencies are renamed as well.
+/// Deployed dotfiles are removed and then deployed again the same way under the new name.
+/// If anything fails, everything is put back the way it was
+pub(crate) fn rename_cmd(
+    ctx: &Context,
     profile: Option<String>,
-    sym: &SymlinkHandler,
-    groups: Vec<String>,
+    from: &str,
+    to: &str,
 ) -> Result<(), ExitCode> {
-    let get_related_groups =
-        |sym: &SymlinkHandler, not_symlinked_groups: Option<&Vec<String>>| -> Vec<String> {
-            let mut related_groups = Vec::new();
+    if let Err(err) = dotfiles::is_valid_groupname(to) {
+        eprintln!("{}", err.red());
+        return Err(ExitCode::FAILURE);
+    }
 
-            let symlinked = not_symlinked_groups.is_some();
+    let sym = SymlinkHandler::try_new(ctx, profile)?;
+    let group_dirs = [
+        DotfileType::Configs,
+        DotfileType::Hooks,
+        DotfileType::Secrets,
+    ]
+    .map(|dtype| sym.dotfiles_dir.join(dtype.dir_name()));
+
+    let renames: Vec<(String, String)> = group_dirs
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|group| dotfiles::group_without_target(group) == from || group == from)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|group| {
+            let renamed = format!("{to}{}", &group[from.len()..]);
+            (group, renamed)
+        })
+        .collect();
 
-            // merges conditional groups into their base group
-            // eg: `dotfile_unix` gets merged into the `dotfile` group
-            for base_group in &groups {
-                let Some(related_cond_groups) = sym.get_related_conditional_groups(
-                    base_group,
-                    if symlinked {
-                        SymlinkType::Symlinked.into()
-                    } else {
-                        make_bitflags!(SymlinkType::{NotSymlinked | NotOwned})
-                    },
-                ) else {
-                    continue;
-                };
+    if renames.is_empty() {
+        eprintln!("{}", t!("errors.x_doesnt_exist", x = from).red());
+        return Err(ReturnCode::NoSuchFileOrDir.into());
+    }
 
-                for group in related_cond_groups {
-                    match not_symlinked_groups {
-                        Some(not_symlinked) => {
-                            if not_symlinked.contains(&group) {
-                                continue;
-                            }
-                        }
+    if let Some(existing) = renames
+        .iter()
+        .flat_map(|(_, renamed)| group_dirs.iter().map(move |dir| dir.join(renamed)))
+        .find(|group| group.exists())
+    {
+        eprintln!(
+            "{}",
+            format!("{} {}", existing.display(), t!("errors.already_exists")).red()
+        );
+        return Err(ExitCode::FAILURE);
+    }
 
-                        None => {
-                            if !sym.not_symlinked.contains_key(&group) {
-                                continue;
-                            }
-                        }
+    // every deployed dotfile along with how it was deployed
+    let configs_dir = sym.dotfiles_dir.join("Configs");
+    let mut deployed = Vec::new();
+    for (group, _) in &renames {
+        let Ok(files) = Dotfile::new(ctx, configs_dir.join(group)).and_then(|g| g.try_iter())
+        else {
+            continue;
+        };
+
+        for file in files {
+            let mode = sym
+                .target_kinds(&file)
+                .into_iter()
+                .find_map(|(target, kind)| match kind {
+                    TargetKind::SymlinkToUs => Some(DeployMode::Symlink),
+                    TargetKind::CopyOfUs if is_same_file(&target, &file.path) => {
+                        Some(DeployMode::Hardlink)
                     }
+                    TargetKind::CopyOfUs => Some(DeployMode::Copy),
+                    _ => None,
+                });
 
-                    related_groups.push(group);
+            if let Some(mode) = mode {
+                deployed.push((file.path, mode));
+            }
+        }
+    }
+
+    let renamed_deployed: Vec<_> = deployed
+        .iter()
+        .map(|(file, mode)| {
+            let (group, renamed) = renames
+                .iter()
+                .find(|(group, _)| file.starts_with(configs_dir.join(group)))
+                .unwrap();
+            let file = configs_dir
+                .join(renamed)
+                .join(file.strip_prefix(configs_dir.join(group)).unwrap());
+            (file, *mode)
+        })
+        .collect();
+
+    let undeploy = |files: &[(PathBuf, DeployMode)]| {
+        let actions: Vec<_> = files
+            .iter()
+            .filter_map(|(file, _)| Dotfile::new(ctx, file.clone()).ok())
+            .flat_map(|file| remove_symlink(&file, false, false, true))
+            .collect();
+        actions
+            .iter()
+            .for_each(|action| action.print(ctx.verbosity));
+        actions.iter().all(|action| action.error.is_none())
+    };
+    let deploy = |files: &[(PathBuf, DeployMode)]| {
+        let actions: Vec<_> = files
+            .iter()
+            .filter_map(|(file, mode)| Some((Dotfile::new(ctx, file.clone()).ok()?, *mode)))
+            .flat_map(|(file, mode)| symlink_file(&file, mode, false, true, &[]))
+            .collect();
+        actions
+            .iter()
+            .for_each(|action| action.print(ctx.verbosity));
+        actions.iter().all(|action| action.error.is_none())
+    };
+
+    if !undeploy(&deployed) {
+        deploy(&deployed);
+        return Err(ExitCode::FAILURE);
+    }
+
+    let mut renamed_dirs = Vec::new();
+    let mut originals = Vec::new();
+    let mut redeployed = false;
+    let renamed = (|| {
+        for dir in &group_dirs {
+            for (group, renamed) in &renames {
+                let (group, renamed) = (dir.join(group), dir.join(renamed));
+                if !group.exists() {
+                    continue;
                 }
+
+                if let Err(err) = fs::rename(&group, &renamed) {
+                    eprintln!("{}", format!("{}: {err}", group.display()).red());
+                    return false;
+                }
+                renamed_dirs.push((group, renamed));
             }
+        }
 
-            related_groups.sort();
-            related_groups.dedup();
-            related_groups
+        if let Err(err) =
+            dotfiles::rename_group_references(&sym.dotfiles_dir, &renames, &mut originals)
+        {
+            eprintln!("{}", err.red());
+            return false;
+        }
+
+        redeployed = true;
+        deploy(&renamed_deployed)
+    })();
+
+    if !renamed {
+        if redeployed {
+            undeploy(&renamed_deployed);
+        }
+        for (path, content) in originals.iter().rev() {
+            _ = fs::write(path, content);
+        }
+        for (group, renamed) in renamed_dirs.iter().rev() {
+            _ = fs::rename(renamed, group);
+        }
+        deploy(&deployed);
+
+        eprintln!("{}", t!("errors.rename_undone", from = from, to = to).red());
+        return Err(ExitCode::FAILURE);
+    }
+
+    Ok(())
+}
+
+/// Removes symlinks owned by tuckr whose dotfile no longer exists
+///
+/// Symlinks pointing outside of the dotfiles directory are never touched
+pub(crate) fn prune_cmd(
+    ctx: &Context,
+    profile: Option<String>,
+    groups: &[String],
+    exclude: &[String],
+) -> Result<(), ExitCode> {
+    let sym = SymlinkHandler::try_new(ctx, profile)?;
+
+    let groups = if groups.is_empty() {
+        &["*".to_string()]
+    } else {
+        groups
+    };
+
+    let pruned = sym.prune(groups, exclude);
+    if pruned.is_empty() {
+        println!("{}", t!("info.no_problems_found").green());
+    }
+    pruned.iter().for_each(|action| action.print(ctx.verbosity));
+
+    Ok(())
+}
+
+/// Reports symlinks owned by tuckr whose source no longer exists
+pub(crate) fn doctor_cmd(ctx: &Context, profile: Option<String>) -> Result<(), ExitCode> {
+    let sym = SymlinkHandler::try_new(ctx, profile)?;
+
+    // symlinks whose source file was deleted from a group that still exists
+    let mut dangling: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    // symlinks whose whole group was deleted
+    let mut orphaned: HashMap<String, Vec<PathBuf>> = HashMap::new();
+
+    for (link, source) in sym.get_owned_symlinks() {
+        if source.exists() {
+            continue;
+        }
+
+        let Ok(source) = Dotfile::new(ctx, source) else {
+            continue;
         };
 
-    let not_symlinked = get_related_groups(sym, None);
-    let symlinked = get_related_groups(sym, Some(&not_symlinked));
+        if source.group_path.exists() {
+            dangling.entry(source.group_name).or_default().push(link);
+        } else {
+            orphaned.entry(source.group_name).or_default().push(link);
+        }
+    }
 
-    let not_owned: HashCache = sym
-        .not_owned
-        .clone()
-        .into_iter()
-        .filter(|(group, _)| groups.contains(group))
-        .collect();
+    if dangling.is_empty() && orphaned.is_empty() {
+        println!("{}", t!("info.no_problems_found").green());
+        return Ok(());
+    }
 
-    let unsupported = {
-        let mut unsupported = groups
-            .iter()
-            .map(|group| Dotfile::try_from(sym.dotfiles_dir.join("Configs").join(group)).unwrap())
-            .filter(|group| !group.is_valid_target())
-            .map(|group| group.group_name)
-            .collect::<Vec<_>>();
+    let print_problems = |title: &str, problems: HashMap<String, Vec<PathBuf>>| {
+        if problems.is_empty() {
+            return;
+        }
 
-        unsupported.sort();
-        unsupported.dedup();
-        unsupported
+        let mut problems: Vec<_> = problems.into_iter().collect();
+        problems.sort();
+
+        println!("{title}:");
+        for (group, mut links) in problems {
+            links.sort();
+
+            println!("\t{}", group.red());
+            for link in &links {
+                println!("\t\t-> {}", link.display());
+            }
+
+            let fix = format!("tuckr prune {group}");
+            println!("\t{}", t!("info.suggested_fix", cmd = fix.yellow()));
+        }
+        println!();
     };
 
-    if !not_symlinked.is_empty() || !not_owned.is_empty() {
-        let print_conflicts = |conflicts_cache: &HashCache, group: &str, msg: &str| {
-            let Some(conflicts) = conflicts_cache.get(group) else {
-                return;
-            };
+    print_problems(&t!("errors.dangling_symlinks"), dangling);
+    print_problems(&t!("errors.orphaned_symlinks"), orphaned);
+
+    Err(ExitCode::FAILURE)
+}
+
+/// Machine readable version of the status command's output
+#[derive(serde::Serialize, Default, Debug)]
+pub struct StatusReport {
+    pub symlinked: Vec<String>,
+    pub not_symlinked: Vec<String>,
+    pub not_owned: Vec<String>,
+    pub conflicts: Vec<ConflictReport>,
+    /// groups that are meant for other platforms
+    pub unsupported: Vec<String>,
+    /// targets that are symlinks whose destination doesn't exist
+    #[serde(skip)]
+    pub broken: Vec<PathBuf>,
+    /// problems found while scanning the dotfiles, eg: dotfiles that link to their own target
+    #[serde(skip)]
+    pub warnings: Vec<String>,
+}
+
+/// A single category of the status command's output
+#[derive(Clone, Copy, Debug)]
+pub enum StatusFilter {
+    Symlinked,
+    NotSymlinked,
+    Conflicts,
+    Broken,
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct ConflictReport {
+    pub group: String,
+    pub dotfile: PathBuf,
+    pub target: PathBuf,
+    pub reason: ConflictReason,
+}
+
+#[derive(serde::Serialize, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictReason {
+    /// A file that is not a symlink already exists on the target path
+    AlreadyExists,
+    /// The target path is a symlink pointing somewhere else
+    SymlinksElsewhere,
+    /// Another group deploys a file to the same target path
+    SharedTarget,
+    /// The target path is deployed from a conditional group with a lower priority
+    LowerPriorityGroup,
+}
+
+impl StatusReport {
+    /// Creates a report for the given groups and their conditional groups, or for all groups
+    pub fn new(sym: &SymlinkHandler, groups: Option<&[String]>) -> Self {
+        let ctx = &sym.ctx;
+        let is_selected = |group: &str| match groups {
+            Some(groups) => {
+                let base_group = dotfiles::group_without_target(group);
+                groups
+                    .iter()
+                    .any(|g| dotfiles::group_without_target(g) == base_group)
+            }
+            None => true,
+        };
+
+        let groups_in = |cache: &HashCache, valid_target: bool| -> Vec<String> {
+            let mut groups: Vec<_> = cache
+                .keys()
+                .filter(|group| is_selected(group))
+                .filter(|group| dotfiles::group_is_valid_target(ctx, group) == valid_target)
+                .cloned()
+                .collect();
+            groups.sort();
+            groups
+        };
+
+        let not_symlinked = groups_in(&sym.not_symlinked, true);
+        let not_owned = groups_in(&sym.not_owned, true);
+        let symlinked = groups_in(&sym.symlinked, true)
+            .into_iter()
+            .filter(|group| !not_symlinked.contains(group) && !not_owned.contains(group))
+            .collect();
+
+        let mut unsupported: Vec<_> = [&sym.symlinked, &sym.not_symlinked, &sym.not_owned]
+            .into_iter()
+            .flat_map(|cache| groups_in(cache, false))
+            .collect();
+        unsupported.sort();
+        unsupported.dedup();
 
-            for file in conflicts {
-                if file.group_name != group {
+        let mut broken: Vec<_> = sym
+            .not_owned
+            .values()
+            .chain(sym.not_symlinked.values())
+            .flatten()
+            .filter(|file| is_selected(&file.group_name) && file.is_valid_target())
+            .flat_map(|file| sym.target_kinds(file))
+            .filter(|(_, kind)| *kind == TargetKind::BrokenSymlink)
+            .map(|(target, _)| target)
+            .collect();
+        broken.sort();
+
+        let mut conflicts = Vec::new();
+        let caches = [
+            (get_conflicts_in_cache(sym, &sym.not_symlinked), false),
+            (sym.not_owned.clone(), true),
+        ];
+        for (cache, symlinks_elsewhere) in caches {
+            for file in cache.values().flatten() {
+                if !is_selected(&file.group_name) || !file.is_valid_target() {
                     continue;
                 }
-
-                let conflict = file.to_target_path();
-                println!("\t\t-> {} ({})", conflict.display(), msg,);
+
+                for (target, kind) in sym.target_kinds(file) {
+                    let reason = match kind {
+                        TargetKind::SymlinkElsewhere(_) if symlinks_elsewhere => {
+                            ConflictReason::SymlinksElsewhere
+                        }
+                        TargetKind::PlainFile if !symlinks_elsewhere => {
+                            ConflictReason::AlreadyExists
+                        }
+                        _ => continue,
+                    };
+
+                    conflicts.push(ConflictReport {
+                        group: file.group_name.clone(),
+                        dotfile: file.path.clone(),
+                        target,
+                        reason,
+                    });
+                }
             }
-        };
+        }
 
-        let file_conflicts = get_conflicts_in_cache(&sym.not_symlinked);
+        for file in sym.not_symlinked.values().flatten() {
+            if !is_selected(&file.group_name) || !file.is_valid_target() {
+                continue;
+            }
 
-        println!("{}:", t!("table-column.not_symlinked"));
-        for group in &not_symlinked {
-            println!("\t{}", group.red());
-            print_conflicts(&file_conflicts, group, &t!("errors.already_exists"));
-            print_conflicts(&sym.not_owned, group, &t!("errors.symlinks_elsewhere"));
+            for (target, kind) in sym.target_kinds(file) {
+                if sym.lower_priority_group(&kind).is_some() {
+                    conflicts.push(ConflictReport {
+                        group: file.group_name.clone(),
+                        dotfile: file.path.clone(),
+                        target,
+                        reason: ConflictReason::LowerPriorityGroup,
+                    });
+                }
+            }
         }
 
-        println!();
-    }
+        for (target, files) in get_shared_targets(sym, |_| true) {
+            if !files.iter().any(|f| is_selected(&f.group_name)) {
+                continue;
+            }
 
-    if !symlinked.is_empty() {
-        println!("{}:", t!("table-column.symlinked"));
-        for group in symlinked {
-            println!("\t{}", group.green());
+            for file in files {
+                conflicts.push(ConflictReport {
+                    group: file.group_name,
+                    dotfile: file.path,
+                    target: target.clone(),
+                    reason: ConflictReason::SharedTarget,
+                });
+            }
         }
-        println!();
-    }
-
-    if !unsupported.is_empty() {
-        println!("{}:", t!("errors.not_supported_on_this_platform"));
-        for group in unsupported {
-            println!("\t{}", group.yellow());
+        conflicts.sort_by(|a, b| a.target.cmp(&b.target));
+
+        StatusReport {
+            symlinked,
+            not_symlinked,
+            not_owned,
+            conflicts,
+            unsupported,
+            broken,
+            warnings: sym.warnings.clone(),
         }
-        println!();
     }
 
-    let invalid_groups = dotfiles::check_invalid_groups(profile, DotfileType::Configs, &groups);
-    if let Some(invalid_groups) = &invalid_groups {
-        eprintln!("{}:", t!("errors.following_groups_dont_exist"));
-        for group in invalid_groups {
-            eprintln!("\t{}", group.red());
-        }
-        println!();
+    /// Checks whether every group is symlinked without any conflicts
+    pub fn is_ok(&self) -> bool {
+        self.not_symlinked.is_empty() && self.not_owned.is_empty() && self.conflicts.is_empty()
     }
 
-    if !not_symlinked.is_empty() {
-        println!(
-            "{}",
-            t!("info.learn_how_to_fix_symlinks", cmd = "tuckr help add")
-        );
+    /// Returns the groups, or target paths, that fall under a category one per line
+    fn filtered_lines(self, filter: StatusFilter) -> Vec<String> {
+        let mut lines: Vec<_> = match filter {
+            StatusFilter::Symlinked => self.symlinked,
+            StatusFilter::NotSymlinked => self
+                .not_symlinked
+                .into_iter()
+                .chain(self.not_owned)
+                .collect(),
+            StatusFilter::Conflicts => self
+                .conflicts
+                .into_iter()
+                .map(|conflict| conflict.target.display().to_string())
+                .collect(),
+            StatusFilter::Broken => self
+                .broken
+                .into_iter()
+                .map(|target| target.display().to_string())
+                .collect(),
+        };
+
+        lines.sort();
+        lines.dedup();
+        lines
     }
+}
 
-    if invalid_groups.is_none() {
-        return Err(ReturnCode::NoSetupFolder.into());
+/// Scans the dotfiles and reports the status of the given groups, or of every group if there are none
+pub fn status(
+    ctx: &Context,
+    profile: Option<String>,
+    groups: Option<&[String]>,
+) -> Result<StatusReport, SymlinkError> {
+    if let Some(invalid_groups) = groups.and_then(|groups| {
+        dotfiles::check_invalid_groups(ctx, profile.clone(), DotfileType::Configs, groups)
+    }) {
+        return Err(SymlinkError::NoSuchGroups(invalid_groups));
     }
 
-    Ok(())
+    let sym = SymlinkHandler::new(ctx, profile)?;
+    Ok(StatusReport::new(&sym, groups))
 }
 
 /// Prints symlinking status
-pub fn status_cmd(profile: Option<String>, groups: Option<Vec<String>>) -> Result<(), ExitCode> {
-    let sym = SymlinkHandler::try_new(profile.clone())?;
+///
+/// filter: only prints a single category, one entry per line.
+/// An error is returned if the category is empty
+///
+/// count: shows how many files each group has
+pub(crate) fn status_cmd(
+    ctx: &Context,
+    profile: Option<String>,
+    groups: Option<Vec<String>>,
+    orphans: bool,
+    json: bool,
+    filter: Option<StatusFilter>,
+    count: bool,
+) -> Result<(), ExitCode> {
+    let sym = SymlinkHandler::try_new(ctx, profile.clone())?;
+
+    if orphans {
+        return print_orphans_status(&sym);
+    }
+
+    if json || filter.is_some() {
+        if let Some(invalid_groups) = groups.as_ref().and_then(|groups| {
+            dotfiles::check_invalid_groups(ctx, profile, DotfileType::Configs, groups)
+        }) {
+            for group in invalid_groups {
+                eprintln!("{}", t!("errors.x_doesnt_exist", x = group).red());
+            }
+            return Err(ReturnCode::NoSuchFileOrDir.into());
+        }
+
+        let report = StatusReport::new(&sym, groups.as_deref());
+
+        if let Some(filter) = filter {
+            let lines = report.filtered_lines(filter);
+            for line in &lines {
+                println!("{line}");
+            }
+
+            return if lines.is_empty() {
+                Err(ExitCode::FAILURE)
+            } else {
+                Ok(())
+            };
+        }
+
+        println!("{}", serde_json::to_string(&report).unwrap());
+
+        return if report.is_ok() {
+            Ok(())
+        } else {
+            Err(ExitCode::FAILURE)
+        };
+    }
 
     if sym.is_empty() {
         println!("{}", t!("errors.no_x_setup_yet", x = "dotfiles").yellow());
@@ -863,7 +3551,7 @@ pub fn status_cmd(profile: Option<String>, groups: Option<Vec<String>>) -> Resul
                 })
                 .collect();
 
-            let ret = print_groups_status(profile, &sym, groups);
+            let ret = print_groups_status(profile, &sym, groups, count);
 
             if !invalid_group_errs.is_empty() {
                 for err in invalid_group_errs {
@@ -874,9 +3562,183 @@ pub fn status_cmd(profile: Option<String>, groups: Option<Vec<String>>) -> Resul
             return ret;
         }
 
-        None => print_global_status(&sym)?,
+        None => print_global_status(&sym, count)?,
+    }
+
+    Ok(())
+}
+
+/// Prints the symlinking status of every profile that has a dotfiles directory
+///
+/// When groups are supplied, profiles that have none of them are skipped.
+/// An error is returned if the status of any profile is an error
+pub(crate) fn status_all_profiles_cmd(
+    ctx: &Context,
+    groups: Option<Vec<String>>,
+    orphans: bool,
+    count: bool,
+) -> Result<(), ExitCode> {
+    let mut profiles = list_profiles(ctx)?;
+
+    if let Some(groups) = &groups {
+        profiles.retain(|profile| {
+            dotfiles::check_invalid_groups(ctx, profile.clone(), DotfileType::Configs, groups)
+                .is_none_or(|invalid_groups| invalid_groups.len() < groups.len())
+        });
+
+        if profiles.is_empty() {
+            for group in groups {
+                eprintln!("{}", t!("errors.x_doesnt_exist", x = group).red());
+            }
+            return Err(ReturnCode::NoSuchFileOrDir.into());
+        }
+    }
+
+    let mut result = Ok(());
+    for (i, profile) in profiles.into_iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+
+        let name = profile.clone().unwrap_or_else(|| "default".into());
+        println!("{}", t!("info.profile_x", profile = name).bold());
+
+        let ret = status_cmd(ctx, profile, groups.clone(), orphans, false, None, count);
+        if result.is_ok() {
+            result = ret;
+        }
+    }
+
+    result
+}
+
+/// Generates a synthetic dotfiles repo and times how long the main operations take on it
+///
+/// Everything is created under a throwaway profile and target directory in the temp directory,
+/// they're deleted afterwards even if tuckr panics
+pub(crate) fn bench_cmd(ctx: &Context, groups: usize, files: usize) -> Result<(), ExitCode> {
+    use std::time::{Duration, Instant};
+
+    /// Deletes the benchmark's directory once it goes out of scope
+    struct BenchDir(PathBuf);
+
+    impl Drop for BenchDir {
+        fn drop(&mut self) {
+            _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    let profile = format!("tuckr-bench-{}", std::process::id());
+    let bench_dir = BenchDir(std::env::temp_dir().join(&profile));
+    let dotfiles_dir = bench_dir.0.join(format!("dotfiles_{profile}"));
+    let target_dir = bench_dir.0.join("target");
+
+    // nothing is read from or deployed to the user's own directories
+    let ctx = &Context {
+        target_dir: Some(target_dir.clone()),
+        dotfiles_home: Some(bench_dir.0.clone()),
+        ..ctx.clone()
+    };
+
+    let setup = || -> std::io::Result<()> {
+        for group in 0..groups {
+            let group_dir = dotfiles_dir
+                .join("Configs")
+                .join(format!("group{group}"))
+                .join("files");
+            // the target dir has to exist so that files are symlinked individually
+            fs::create_dir_all(target_dir.join("files"))?;
+            fs::create_dir_all(&group_dir)?;
+
+            for file in 0..files {
+                fs::write(group_dir.join(format!("group{group}_file{file}")), "")?;
+            }
+        }
+
+        Ok(())
+    };
+
+    if let Err(err) = setup() {
+        eprintln!("{}", err.red());
+        return Err(ExitCode::FAILURE);
+    }
+
+    let time = |op: &dyn Fn() -> Result<(), ExitCode>| -> Result<Duration, ExitCode> {
+        let start = Instant::now();
+        op()?;
+        Ok(start.elapsed())
+    };
+
+    let profile = Some(profile);
+    let all_groups = ["*".to_string()];
+
+    let results = (|| -> Result<Vec<(&str, Duration)>, ExitCode> {
+        Ok(vec![
+            (
+                "validate",
+                time(&|| SymlinkHandler::try_new(ctx, profile.clone()).map(|_| ()))?,
+            ),
+            (
+                "add",
+                time(&|| {
+                    add_cmd(
+                        ctx,
+                        profile.clone(),
+                        &all_groups,
+                        &[],
+                        &AddOptions {
+                            assume_yes: true,
+                            ..Default::default()
+                        },
+                    )
+                })?,
+            ),
+            (
+                "status",
+                time(&|| status_cmd(ctx, profile.clone(), None, false, false, None, true))?,
+            ),
+            (
+                "rm",
+                time(&|| {
+                    remove_cmd(
+                        ctx,
+                        profile.clone(),
+                        &all_groups,
+                        &[],
+                        false,
+                        false,
+                        false,
+                        false,
+                    )
+                })?,
+            ),
+        ])
+    })();
+    drop(bench_dir);
+
+    #[derive(Tabled)]
+    struct BenchRow<'a> {
+        #[tabled(rename = "Operation")]
+        operation: &'a str,
+        #[tabled(rename = "Time")]
+        time: String,
     }
 
+    let rows: Vec<_> = results?
+        .into_iter()
+        .map(|(operation, time)| BenchRow {
+            operation,
+            time: format!("{time:.2?}"),
+        })
+        .collect();
+
+    use tabled::{Margin, Style};
+
+    let mut table = Table::new(rows);
+    table.with(Style::rounded()).with(Margin::new(4, 4, 1, 1));
+    println!("{groups} groups with {files} files each:");
+    println!("{table}");
+
     Ok(())
 }
 
@@ -890,7 +3752,7 @@ mod tests {
 
     use owo_colors::OwoColorize;
 
-    use crate::dotfiles::{self, Dotfile};
+    use crate::dotfiles::{self, Context, Dotfile};
 
     use super::SymlinkHandler;
 
@@ -899,12 +3761,22 @@ mod tests {
     #[must_use = "must be initialized before every test"]
     struct Test {
         files_used: Vec<path::PathBuf>,
+        ctx: Context,
     }
 
     impl Test {
         fn start() -> Self {
-            crate::fileops::init_cmd(None).unwrap();
-            let dotfiles_dir = dotfiles::get_dotfiles_path(None).unwrap();
+            // files are deployed to a temporary target dir instead of the user's home
+            let ctx = Context {
+                target_dir: Some(std::env::temp_dir().join(format!(
+                    "tuckr-target-{}",
+                    std::thread::current().name().unwrap()
+                ))),
+                ..Default::default()
+            };
+            fs::create_dir_all(ctx.target_dir()).unwrap();
+            crate::fileops::init_cmd(&ctx, None, false).unwrap();
+            let dotfiles_dir = dotfiles::get_dotfiles_path(&ctx, None).unwrap();
             let group_dir = dotfiles_dir.join("Configs").join("Group1");
             let new_config_dir = group_dir.join(".config");
 
@@ -924,13 +3796,15 @@ mod tests {
 
             Self {
                 files_used: filepaths.to_vec(),
+                ctx,
             }
         }
     }
 
     impl Drop for Test {
         fn drop(&mut self) {
-            let Ok(dotfiles_dir) = dotfiles::get_dotfiles_path(None) else {
+            let ctx = &self.ctx;
+            let Ok(dotfiles_dir) = dotfiles::get_dotfiles_path(ctx, None) else {
                 eprintln!("{}", "Failed to clean up test.".red());
                 return;
             };
@@ -938,47 +3812,69 @@ mod tests {
             for file in &self.files_used {
                 // delete everything to ensure everything starts from a blank slate
                 if file.exists() {
-                    let dotfile_path = Dotfile::try_from(file.clone()).unwrap();
+                    let dotfile_path = Dotfile::new(ctx, file.clone()).unwrap();
                     _ = fs::remove_file(dotfile_path.to_target_path());
                 }
             }
 
             if dotfiles_dir.exists() {
-                _ = super::remove_cmd(None, &["*".to_string()], &[]);
+                _ = super::remove_cmd(
+                    ctx,
+                    None,
+                    &["*".to_string()],
+                    &[],
+                    false,
+                    false,
+                    false,
+                    false,
+                );
                 fs::remove_dir_all(dotfiles_dir).unwrap();
             }
+            _ = fs::remove_dir_all(ctx.target_dir());
         }
     }
 
     fn test_adding_symlink() {
-        let _test = Test::start();
+        let test = Test::start();
+        let ctx = &test.ctx;
 
-        let sym = SymlinkHandler::try_new(None).unwrap();
+        let sym = SymlinkHandler::try_new(ctx, None).unwrap();
         assert!(
             !sym.not_symlinked.is_empty() || !sym.symlinked.is_empty() || !sym.not_owned.is_empty()
         );
 
         assert!(!sym.symlinked.contains_key("Group1"));
-        super::add_cmd(None, &["Group1".to_string()], &[], false, false, false).unwrap();
+        super::add_cmd(ctx, None, &["Group1".to_string()], &[], &Default::default()).unwrap();
 
-        let sym = SymlinkHandler::try_new(None).unwrap();
+        let sym = SymlinkHandler::try_new(ctx, None).unwrap();
         assert!(sym.symlinked.contains_key("Group1"));
     }
 
     fn test_removing_symlink() {
-        let _test = Test::start();
+        let test = Test::start();
+        let ctx = &test.ctx;
 
-        super::add_cmd(None, &["Group1".to_string()], &[], false, false, false).unwrap();
+        super::add_cmd(ctx, None, &["Group1".to_string()], &[], &Default::default()).unwrap();
 
-        let sym = SymlinkHandler::try_new(None).unwrap();
+        let sym = SymlinkHandler::try_new(ctx, None).unwrap();
         assert!(
             !sym.not_symlinked.is_empty() || !sym.symlinked.is_empty() || !sym.not_owned.is_empty()
         );
 
         assert!(!sym.not_symlinked.contains_key("Group1"));
 
-        super::remove_cmd(None, &["Group1".to_string()], &[]).unwrap();
-        let sym = SymlinkHandler::try_new(None).unwrap();
+        super::remove_cmd(
+            ctx,
+            None,
+            &["Group1".to_string()],
+            &[],
+            false,
+            false,
+            false,
+            false,
+        )
+        .unwrap();
+        let sym = SymlinkHandler::try_new(ctx, None).unwrap();
         assert!(sym.not_symlinked.contains_key("Group1"));
     }
 
@@ -987,4 +3883,805 @@ mod tests {
         test_adding_symlink();
         test_removing_symlink();
     }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn elevated_commands() {
+        use super::{Action, ActionKind};
+
+        let action = |kind| Action {
+            action: kind,
+            group: "root".into(),
+            source: "/dotfiles/Configs/root/etc/conf".into(),
+            target: "/etc/conf".into(),
+            error: None,
+            needs_root: true,
+            backup: None,
+        };
+        let args = |kind| {
+            super::elevated_args(&action(kind)).map(|args| {
+                args.iter()
+                    .map(|arg| arg.to_str().unwrap().to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+        };
+
+        let source = "/dotfiles/Configs/root/etc/conf";
+        assert_eq!(
+            args(ActionKind::Symlink).unwrap(),
+            format!("ln -s {source} /etc/conf")
+        );
+        assert_eq!(
+            args(ActionKind::Relink).unwrap(),
+            format!("ln -sf {source} /etc/conf")
+        );
+        assert_eq!(
+            args(ActionKind::Copy).unwrap(),
+            format!("cp {source} /etc/conf")
+        );
+        assert_eq!(
+            args(ActionKind::Hardlink).unwrap(),
+            format!("ln {source} /etc/conf")
+        );
+        assert_eq!(args(ActionKind::CreateDir).unwrap(), "mkdir /etc/conf");
+        for kind in [
+            ActionKind::Remove,
+            ActionKind::Restore,
+            ActionKind::Conflict,
+            ActionKind::Trash,
+            ActionKind::Backup,
+            ActionKind::Prune,
+        ] {
+            assert!(args(kind).is_none());
+        }
+    }
+
+    #[test]
+    fn remove_and_restore_symlink() {
+        let test = Test::start();
+        let ctx = &test.ctx;
+        let dotfile = Dotfile::new(ctx, test.files_used[1].clone()).unwrap();
+        let target = dotfile.to_target_path();
+
+        super::add_cmd(ctx, None, &["Group1".into()], &[], &Default::default()).unwrap();
+        assert!(target.is_symlink());
+
+        super::remove_cmd(
+            ctx,
+            None,
+            &["Group1".into()],
+            &[],
+            true,
+            false,
+            false,
+            false,
+        )
+        .unwrap();
+
+        // the symlink is replaced with a copy and the dotfile is kept
+        assert!(!target.is_symlink());
+        assert_eq!(fs::read(&target).unwrap(), fs::read(&dotfile.path).unwrap());
+        assert!(dotfile.path.is_file());
+    }
+
+    #[test]
+    fn uninstall_symlinks() {
+        let test = Test::start();
+        let ctx = &test.ctx;
+
+        super::add_cmd(ctx, None, &["Group1".into()], &[], &Default::default()).unwrap();
+        let sym = SymlinkHandler::try_new(ctx, None).unwrap();
+        assert!(!sym.get_owned_symlinks().is_empty());
+
+        super::uninstall_cmd(ctx, None, true, true).unwrap();
+        let sym = SymlinkHandler::try_new(ctx, None).unwrap();
+        assert!(sym.get_owned_symlinks().is_empty());
+
+        // the symlinks are replaced with copies of the dotfiles
+        for file in &test.files_used {
+            let target = Dotfile::new(ctx, file.clone()).unwrap().to_target_path();
+            assert_ne!(fs::canonicalize(&target).unwrap(), *file);
+            assert_eq!(fs::read(target).unwrap(), fs::read(file).unwrap());
+        }
+    }
+
+    #[test]
+    fn status_report() {
+        let test = Test::start();
+        let ctx = &test.ctx;
+        let groups = ["Group1".to_string()];
+
+        let sym = SymlinkHandler::try_new(ctx, None).unwrap();
+        let report = super::StatusReport::new(&sym, Some(&groups));
+        assert_eq!(report.not_symlinked, groups);
+        assert!(report.symlinked.is_empty());
+        assert!(!report.is_ok());
+
+        super::add_cmd(ctx, None, &groups, &[], &Default::default()).unwrap();
+
+        let sym = SymlinkHandler::try_new(ctx, None).unwrap();
+        let report = super::StatusReport::new(&sym, Some(&groups));
+        assert_eq!(report.symlinked, groups);
+        assert!(report.conflicts.is_empty());
+        assert!(report.is_ok());
+    }
+
+    #[test]
+    fn library_add_and_remove() {
+        use super::{ActionKind, SymlinkError};
+
+        let test = Test::start();
+        let ctx = &test.ctx;
+        let groups = ["Group1".to_string()];
+
+        let report = super::add(ctx, None, &groups, &[], &Default::default()).unwrap();
+        assert!(report.errors.is_empty());
+        assert!(!report.actions.is_empty());
+        assert!(report
+            .actions
+            .iter()
+            .all(|action| action.action == ActionKind::Symlink && action.error.is_none()));
+        assert_eq!(
+            super::status(ctx, None, Some(&groups)).unwrap().symlinked,
+            groups
+        );
+
+        let report = super::remove(ctx, None, &groups, &[], false, false).unwrap();
+        assert!(report
+            .actions
+            .iter()
+            .all(|action| action.action == ActionKind::Remove && action.error.is_none()));
+        assert_eq!(
+            super::status(ctx, None, Some(&groups))
+                .unwrap()
+                .not_symlinked,
+            groups
+        );
+
+        assert!(matches!(
+            super::add(ctx, None, &["Missing".into()], &[], &Default::default()),
+            Err(SymlinkError::NoSuchGroups(_))
+        ));
+    }
+
+    #[test]
+    fn count_group_files() {
+        let test = Test::start();
+        let ctx = &test.ctx;
+        let sym = SymlinkHandler::new(ctx, None).unwrap();
+
+        // the .config directory itself isn't counted, only the files inside of it
+        assert_eq!(sym.file_count("Group1"), 2);
+        assert_eq!(sym.file_count("Missing"), 0);
+    }
+
+    #[test]
+    fn detect_shared_targets() {
+        let test = Test::start();
+        let ctx = &test.ctx;
+        let dotfiles_dir = dotfiles::get_dotfiles_path(ctx, None).unwrap();
+        let configs_dir = dotfiles_dir.join("Configs");
+
+        for group in ["Group2", "Group1_linux"] {
+            let group_dir = configs_dir.join(group);
+            fs::create_dir_all(&group_dir).unwrap();
+            File::create(group_dir.join("group_file_0")).unwrap();
+        }
+
+        let sym = SymlinkHandler::new(ctx, None).unwrap();
+        let shared = super::get_shared_targets(&sym, |_| true);
+        assert_eq!(shared.len(), 1);
+        let groups: Vec<_> = shared
+            .values()
+            .flatten()
+            .map(|f| dotfiles::group_without_target(&f.group_name))
+            .collect();
+        // only the conditional group that takes precedence on this platform is reported
+        assert_eq!(groups, ["Group1", "Group2"]);
+
+        let shared = super::get_shared_targets(&sym, |group| group != "Group2");
+        assert!(shared.is_empty());
+    }
+
+    #[test]
+    fn report_deploy_actions() {
+        use super::{ActionKind, DeployMode};
+
+        let test = Test::start();
+        let ctx = &test.ctx;
+        let file = test.files_used[1].clone();
+        let dotfile = Dotfile::new(ctx, file.clone()).unwrap();
+        let target = dotfile.to_target_path();
+
+        // planning doesn't touch the target
+        let plan = super::symlink_file(&dotfile, DeployMode::Symlink, false, false, &[]);
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].action, ActionKind::Symlink);
+        assert!(!target.is_symlink());
+
+        let actions = super::symlink_file(&dotfile, DeployMode::Symlink, false, true, &[]);
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].action, ActionKind::Symlink);
+        assert_eq!(actions[0].target, target);
+        assert!(actions[0].error.is_none());
+
+        // already deployed files are left alone
+        assert!(super::symlink_file(&dotfile, DeployMode::Symlink, false, true, &[]).is_empty());
+
+        let actions = super::remove_symlink(&dotfile, false, false, true);
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].action, ActionKind::Remove);
+
+        fs::write(&target, "not a dotfile").unwrap();
+        let actions = super::symlink_file(&dotfile, DeployMode::Symlink, false, true, &[]);
+        assert_eq!(actions[0].action, ActionKind::Conflict);
+        fs::remove_file(&target).unwrap();
+
+        // links to dotfiles that have since been moved are replaced
+        #[cfg(target_family = "unix")]
+        {
+            let moved = file.with_file_name("moved");
+            std::os::unix::fs::symlink(&moved, &target).unwrap();
+            let actions = super::symlink_file(&dotfile, DeployMode::Symlink, false, true, &[]);
+            assert_eq!(actions[0].action, ActionKind::Relink);
+            assert!(actions[0].error.is_none());
+            assert_eq!(fs::read_link(&target).unwrap(), file);
+            fs::remove_file(&target).unwrap();
+
+            // broken links to anything else are still conflicts
+            std::os::unix::fs::symlink(std::env::temp_dir().join("tuckr-missing"), &target)
+                .unwrap();
+            let actions = super::symlink_file(&dotfile, DeployMode::Symlink, false, true, &[]);
+            assert_eq!(actions[0].action, ActionKind::Conflict);
+            fs::remove_file(target).unwrap();
+        }
+    }
+
+    #[test]
+    fn unset_target_variable_is_an_error() {
+        let test = Test::start();
+        let ctx = &test.ctx;
+        let dotfiles_dir = dotfiles::get_dotfiles_path(ctx, None).unwrap();
+        fs::write(
+            dotfiles_dir.join("tuckr.toml"),
+            "[groups.Group1]\ntargets = [\"$TUCKR_TEST_UNSET_TARGET/app\"]\n",
+        )
+        .unwrap();
+
+        // the target can't be expanded so the group has nowhere to be deployed to
+        assert!(super::add_cmd(ctx, None, &["Group1".into()], &[], &Default::default()).is_err());
+        for file in &test.files_used {
+            let target = Dotfile::new(ctx, file.clone()).unwrap().to_target_path();
+            assert!(!target.is_symlink());
+        }
+        super::remove_cmd(
+            ctx,
+            None,
+            &["Group1".into()],
+            &[],
+            false,
+            false,
+            false,
+            false,
+        )
+        .unwrap();
+
+        // a dotfiles directory without Configs is reported instead of panicking
+        fs::remove_dir_all(dotfiles_dir.join("Configs")).unwrap();
+        assert!(SymlinkHandler::try_new(ctx, None).is_err());
+    }
+
+    #[test]
+    fn canonicalize_nested_groups() {
+        use super::HashCache;
+
+        let ctx = &Context::default();
+        // the canonicalization that was done inline by SymlinkHandler::validate
+        fn naive_canonicalize(
+            symlinked: &mut HashCache,
+            not_symlinked: &mut HashCache,
+            not_owned: &mut HashCache,
+        ) {
+            for (group, files) in symlinked.iter() {
+                if let Some(unsymlinked) = not_symlinked.get_mut(group) {
+                    unsymlinked.retain(|f2| !files.iter().any(|f1| f2.path.starts_with(&f1.path)));
+                }
+            }
+
+            for cache in [symlinked, not_symlinked, not_owned] {
+                for files in cache.values_mut() {
+                    let files_copy = files.clone();
+                    files.retain(|f2| {
+                        !files_copy
+                            .iter()
+                            .any(|f1| f2.path != f1.path && f2.path.starts_with(&f1.path))
+                    });
+                }
+            }
+        }
+
+        let configs_dir = dotfiles::get_dotfiles_path(ctx, None)
+            .unwrap()
+            .join("Configs");
+        let cache = |files: &[&str]| -> HashCache {
+            let mut cache = HashCache::new();
+            for file in files {
+                let dotfile = Dotfile::new(ctx, configs_dir.join(file)).unwrap();
+                cache
+                    .entry(dotfile.group_name.clone())
+                    .or_default()
+                    .insert(dotfile);
+            }
+            cache
+        };
+
+        let caches = || {
+            (
+                cache(&["Nvim/.config/nvim", "Bin/.local/bin/script"]),
+                cache(&[
+                    "Nvim/.config",
+                    "Nvim/.config/nvim/init.lua",
+                    "Nvim/.config/nvim/lua/plugins.lua",
+                    "Bin/.local/bin/other",
+                    "Bin/.local/share/tool",
+                    "Bin/.local/share/tool/data",
+                ]),
+                cache(&["Zsh/.zshrc", "Zsh/.zsh", "Zsh/.zsh/plugins/a.zsh"]),
+            )
+        };
+
+        let (mut symlinked, mut not_symlinked, mut not_owned) = caches();
+        naive_canonicalize(&mut symlinked, &mut not_symlinked, &mut not_owned);
+
+        let (mut tree_symlinked, mut tree_not_symlinked, mut tree_not_owned) = caches();
+        super::canonicalize_caches(
+            &configs_dir,
+            &mut tree_symlinked,
+            &mut tree_not_symlinked,
+            &mut tree_not_owned,
+        );
+
+        assert_eq!(symlinked, tree_symlinked);
+        assert_eq!(not_symlinked, tree_not_symlinked);
+        assert_eq!(not_owned, tree_not_owned);
+        assert_eq!(tree_not_symlinked["Nvim"].len(), 1);
+        assert_eq!(tree_not_owned["Zsh"].len(), 2);
+    }
+
+    #[test]
+    fn skip_dotfiles_linking_to_their_target() {
+        let test = Test::start();
+        let ctx = &test.ctx;
+        let group_dir = dotfiles::get_dotfiles_path(ctx, None)
+            .unwrap()
+            .join("Configs")
+            .join("Loop");
+        fs::create_dir_all(&group_dir).unwrap();
+
+        let source = group_dir.join("tuckr_loop_test_file");
+        let target = Dotfile::new(ctx, source.clone()).unwrap().to_target_path();
+        super::symlink(&target, &source).unwrap();
+
+        let source = Dotfile::new(ctx, source).unwrap();
+        let actions = super::symlink_file(&source, super::DeployMode::Symlink, false, true, &[]);
+        assert!(actions.is_empty());
+        assert!(!target.is_symlink());
+
+        let sym = SymlinkHandler::try_new(ctx, None).unwrap();
+        assert!(!sym.symlinked.contains_key("Loop"));
+        assert!(!sym.not_symlinked.contains_key("Loop"));
+        assert!(!sym.not_owned.contains_key("Loop"));
+        assert_eq!(sym.warnings().len(), 1);
+    }
+
+    #[test]
+    fn prune_stale_symlinks() {
+        let test = Test::start();
+        let ctx = &test.ctx;
+        let dotfiles_dir = dotfiles::get_dotfiles_path(ctx, None).unwrap();
+        let group_dir = dotfiles_dir.join("Configs").join("Prune");
+        fs::create_dir_all(&group_dir).unwrap();
+
+        let stale_file = group_dir.join("tuckr_prune_test_file");
+        File::create(&stale_file).unwrap();
+        let target = Dotfile::new(ctx, stale_file.clone())
+            .unwrap()
+            .to_target_path();
+
+        super::add_cmd(ctx, None, &["Prune".into()], &[], &Default::default()).unwrap();
+        assert!(target.is_symlink());
+
+        fs::remove_file(&stale_file).unwrap();
+        super::add_cmd(
+            ctx,
+            None,
+            &["Prune".into()],
+            &[],
+            &super::AddOptions {
+                prune: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(!target.is_symlink());
+    }
+
+    #[test]
+    fn backup_conflicting_targets() {
+        let ctx = &Context::default();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(ctx, None).unwrap();
+        let target = dotfiles_dir.join("target").join("conflict");
+        let backup_dir = dotfiles_dir.join("backup");
+        fs::create_dir_all(target.parent().unwrap()).unwrap();
+
+        fs::write(&target, "first").unwrap();
+        let first_backup = super::backup_target(ctx, &target, &backup_dir).unwrap();
+        assert!(!target.exists());
+        assert!(first_backup.starts_with(&backup_dir) && first_backup.ends_with("conflict"));
+
+        fs::write(&target, "second").unwrap();
+        let second_backup = super::backup_target(ctx, &target, &backup_dir).unwrap();
+        assert_eq!(second_backup, first_backup.with_file_name("conflict.1"));
+        assert_eq!(fs::read_to_string(first_backup).unwrap(), "first");
+        assert_eq!(fs::read_to_string(second_backup).unwrap(), "second");
+
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn create_dirs_up_to_depth() {
+        use super::{ActionKind, DeployMode};
+
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&Context::default(), None).unwrap();
+        let target_dir = dotfiles_dir.with_file_name("target");
+        let ctx = &Context {
+            target_dir: Some(target_dir.clone()),
+            ..Default::default()
+        };
+        crate::fileops::init_cmd(ctx, None, false).unwrap();
+        fs::create_dir_all(&target_dir).unwrap();
+
+        let nvim_dir = dotfiles_dir.join("Configs/nvim/.config/nvim");
+        fs::create_dir_all(nvim_dir.join("lua")).unwrap();
+        fs::write(nvim_dir.join("init.lua"), "").unwrap();
+        fs::write(nvim_dir.join("lua").join("plugins.lua"), "").unwrap();
+
+        let sym = SymlinkHandler::try_new(ctx, None).unwrap();
+        let report = sym.add("nvim", DeployMode::Symlink, false, Some(2), false);
+        let sources = |kind| -> Vec<_> {
+            report
+                .actions
+                .iter()
+                .filter(|action| action.action == kind)
+                .map(|action| action.source.clone())
+                .collect()
+        };
+        assert_eq!(
+            sources(ActionKind::CreateDir),
+            [nvim_dir.parent().unwrap(), nvim_dir.as_path()]
+        );
+        // the entries below the depth come along with the symlinked directory
+        let symlinked = sources(ActionKind::Symlink);
+        assert!(symlinked.contains(&nvim_dir.join("lua")));
+        assert!(!symlinked.contains(&nvim_dir.join("lua").join("plugins.lua")));
+
+        sym.add("nvim", DeployMode::Symlink, false, Some(2), true);
+        let target_nvim = target_dir.join(".config/nvim");
+        assert!(target_nvim.is_dir() && !target_nvim.is_symlink());
+        assert!(target_nvim.join("lua").is_symlink());
+        assert!(!target_nvim.join("lua").join("plugins.lua").is_symlink());
+
+        fs::remove_dir_all(dotfiles_dir.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn add_root_group_with_missing_parents() {
+        use super::DeployMode;
+
+        let ctx = &Context::default();
+        crate::fileops::init_cmd(ctx, None, false).unwrap();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(ctx, None).unwrap();
+        let target_root = std::env::temp_dir().join(format!(
+            "tuckr-root-{}",
+            std::thread::current().name().unwrap()
+        ));
+        let root_file = dotfiles_dir
+            .join("Configs")
+            .join("Root")
+            .join(target_root.strip_prefix("/").unwrap())
+            .join("missing/parent/file");
+        fs::create_dir_all(root_file.parent().unwrap()).unwrap();
+        File::create(&root_file).unwrap();
+
+        let target = Dotfile::new(ctx, root_file.clone())
+            .unwrap()
+            .to_target_path();
+        assert_eq!(target, target_root.join("missing/parent/file"));
+
+        let sym = SymlinkHandler::try_new(ctx, None).unwrap();
+        sym.add("Root", DeployMode::Symlink, true, None, true);
+        assert!(target.parent().unwrap().is_dir());
+        assert_eq!(fs::read_link(&target).unwrap(), root_file);
+
+        fs::remove_dir_all(target_root).unwrap();
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+    }
+
+    #[test]
+    fn conditional_group_fallbacks() {
+        use super::DeployMode;
+
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&Context::default(), None).unwrap();
+        let target_dir = dotfiles_dir.with_file_name("target");
+        let ctx = &Context {
+            target_dir: Some(target_dir.clone()),
+            ..Default::default()
+        };
+        crate::fileops::init_cmd(ctx, None, false).unwrap();
+
+        let os_group = format!("app_{}", std::env::consts::OS);
+        for group in ["app", "app_#work", os_group.as_str()] {
+            let group_dir = dotfiles_dir.join("Configs").join(group);
+            fs::create_dir_all(&group_dir).unwrap();
+            fs::write(group_dir.join("config"), group).unwrap();
+        }
+        let deployed = || fs::read_to_string(target_dir.join("config")).unwrap();
+
+        // the OS group is used over the base group
+        let sym = SymlinkHandler::try_new(ctx, None).unwrap();
+        sym.add("app", DeployMode::Symlink, false, None, true);
+        assert_eq!(deployed(), os_group);
+
+        // the custom target takes precedence once enabled, replacing the OS group's symlink
+        let ctx = &Context {
+            custom_targets: vec!["work".into()],
+            ..ctx.clone()
+        };
+        let sym = SymlinkHandler::try_new(ctx, None).unwrap();
+        assert!(super::get_conflicts_in_cache(&sym, &sym.not_symlinked).is_empty());
+        let report = super::StatusReport::new(&sym, None);
+        assert_eq!(report.not_symlinked, vec!["app_#work"]);
+        assert!(matches!(
+            report.conflicts.as_slice(),
+            [conflict] if conflict.reason == super::ConflictReason::LowerPriorityGroup
+        ));
+        sym.add("app", DeployMode::Symlink, false, None, true);
+        assert_eq!(deployed(), "app_#work");
+
+        // the lower priority groups aren't reported as conflicting with it
+        let sym = SymlinkHandler::try_new(ctx, None).unwrap();
+        assert!(sym.not_symlinked.is_empty() && sym.not_owned.is_empty());
+        assert!(super::get_conflicts_in_cache(&sym, &sym.not_symlinked).is_empty());
+
+        fs::remove_dir_all(dotfiles_dir.parent().unwrap()).unwrap();
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn repoint_to_higher_priority_group() {
+        use super::DeployMode;
+
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&Context::default(), None).unwrap();
+        let target_dir = dotfiles_dir.with_file_name("target");
+        let ctx = &Context {
+            target_dir: Some(target_dir.clone()),
+            ..Default::default()
+        };
+        crate::fileops::init_cmd(ctx, None, false).unwrap();
+
+        let base_file = dotfiles_dir.join("Configs").join("app").join("config");
+        let variant_file = dotfiles_dir.join("Configs").join("app_unix").join("config");
+        for file in [&base_file, &variant_file] {
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, "").unwrap();
+        }
+
+        // the base group was deployed before the variant existed
+        fs::create_dir_all(&target_dir).unwrap();
+        let target = target_dir.join("config");
+        std::os::unix::fs::symlink(&base_file, &target).unwrap();
+
+        let sym = SymlinkHandler::try_new(ctx, None).unwrap();
+        assert!(sym.not_symlinked.contains_key("app_unix"));
+
+        // the plan shows the link being repointed instead of a conflict
+        let plan = sym.add("app", DeployMode::Symlink, false, None, false);
+        assert_eq!(plan.actions.len(), 1);
+        assert_eq!(plan.actions[0].action, super::ActionKind::Relink);
+        assert_eq!(fs::read_link(&target).unwrap(), base_file);
+
+        let report = sym.add("app", DeployMode::Symlink, false, None, true);
+        assert_eq!(report.actions[0].action, super::ActionKind::Relink);
+        assert!(report.actions[0].error.is_none());
+        assert_eq!(fs::read_link(&target).unwrap(), variant_file);
+
+        let sym = SymlinkHandler::try_new(ctx, None).unwrap();
+        assert!(sym.not_symlinked.is_empty() && sym.not_owned.is_empty());
+        assert!(sym.symlinked.contains_key("app_unix"));
+
+        fs::remove_dir_all(dotfiles_dir.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn find_symlinks_of_deleted_groups() {
+        use super::DeployMode;
+
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&Context::default(), None).unwrap();
+        let target_dir = dotfiles_dir.with_file_name("target");
+        let ctx = &Context {
+            target_dir: Some(target_dir.clone()),
+            ..Default::default()
+        };
+        crate::fileops::init_cmd(ctx, None, false).unwrap();
+
+        // the directory is symlinked as a whole since its parent already exists
+        let nvim_dir = dotfiles_dir.join("Configs/nvim/.config/nvim");
+        fs::create_dir_all(&nvim_dir).unwrap();
+        fs::write(nvim_dir.join("init.lua"), "").unwrap();
+        fs::create_dir_all(target_dir.join(".config")).unwrap();
+
+        let sym = SymlinkHandler::try_new(ctx, None).unwrap();
+        sym.add("nvim", DeployMode::Symlink, false, None, true);
+        let link = target_dir.join(".config/nvim");
+        assert!(link.is_symlink());
+
+        fs::remove_dir_all(dotfiles_dir.join("Configs/nvim")).unwrap();
+        let sym = SymlinkHandler::try_new(ctx, None).unwrap();
+        assert_eq!(sym.get_owned_symlinks(), vec![(link.clone(), nvim_dir)]);
+
+        super::uninstall_cmd(ctx, None, false, true).unwrap();
+        assert!(!link.is_symlink());
+        assert!(crate::fileops::DeployRecord::Symlinks
+            .targets()
+            .get(&link)
+            .is_none());
+
+        fs::remove_dir_all(dotfiles_dir.parent().unwrap()).unwrap();
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn rename_deployed_group() {
+        use super::DeployMode;
+
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&Context::default(), None).unwrap();
+        let target_dir = dotfiles_dir.with_file_name("target");
+        let ctx = &Context {
+            target_dir: Some(target_dir.clone()),
+            ..Default::default()
+        };
+        crate::fileops::init_cmd(ctx, None, false).unwrap();
+
+        let configs_dir = dotfiles_dir.join("Configs");
+        for (group, file) in [
+            ("zsh", ".zshrc"),
+            ("zsh_unix", ".zprofile"),
+            ("git", ".gitconfig"),
+        ] {
+            fs::create_dir_all(configs_dir.join(group)).unwrap();
+            fs::write(configs_dir.join(group).join(file), group).unwrap();
+        }
+        fs::write(
+            configs_dir.join("git").join(dotfiles::GROUP_CONFIG_FILE),
+            "depends = [\"zsh\"] # the shell\n",
+        )
+        .unwrap();
+        fs::write(
+            dotfiles_dir.join("tuckr.toml"),
+            format!(
+                "[groups.zsh_unix]\ntargets = [{:?}]\n",
+                target_dir.join("shell")
+            ),
+        )
+        .unwrap();
+
+        let sym = SymlinkHandler::try_new(ctx, None).unwrap();
+        sym.add("zsh_unix", DeployMode::Symlink, false, None, true);
+        sym.add("zsh", DeployMode::Copy, false, None, true);
+
+        super::rename_cmd(ctx, None, "zsh", "shell").unwrap();
+
+        // the conditional group is renamed along with the base group
+        assert!(!configs_dir.join("zsh").exists() && !configs_dir.join("zsh_unix").exists());
+        let zshrc = configs_dir.join("shell/.zshrc");
+        let zprofile = configs_dir.join("shell_unix/.zprofile");
+        assert!(zshrc.exists() && zprofile.exists());
+
+        // both are deployed again the same way they were before
+        let zshrc_target = target_dir.join(".zshrc");
+        assert!(!zshrc_target.is_symlink());
+        assert!(crate::fileops::DeployRecord::Copies.contains(&zshrc_target, &zshrc));
+        assert_eq!(
+            fs::read_link(target_dir.join("shell/.zprofile")).unwrap(),
+            zprofile
+        );
+
+        // references to the groups are renamed
+        assert_eq!(
+            fs::read_to_string(configs_dir.join("git").join(dotfiles::GROUP_CONFIG_FILE)).unwrap(),
+            "depends = [\"shell\"] # the shell\n"
+        );
+        assert!(dotfiles::Manifest::load(ctx, &dotfiles_dir)
+            .groups
+            .contains_key("shell_unix"));
+
+        // nothing is renamed if any of the new names are taken
+        fs::create_dir_all(dotfiles_dir.join("Hooks").join("zsh_unix")).unwrap();
+        fs::create_dir_all(configs_dir.join("zsh")).unwrap();
+        assert!(super::rename_cmd(ctx, None, "zsh", "shell").is_err());
+        assert!(zshrc.exists() && zprofile.exists());
+
+        super::uninstall_cmd(ctx, None, false, true).unwrap();
+        fs::remove_dir_all(dotfiles_dir.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn list_every_profile() {
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&Context::default(), None).unwrap();
+        let ctx = &Context {
+            dotfiles_home: Some(dotfiles_dir.parent().unwrap().to_path_buf()),
+            ..Default::default()
+        };
+        fs::create_dir_all(&dotfiles_dir).unwrap();
+        fs::create_dir_all(dotfiles_dir.with_file_name("dotfiles_work")).unwrap();
+
+        // the default profile comes first
+        assert_eq!(
+            super::list_profiles(ctx).unwrap(),
+            [None, Some("work".to_string())]
+        );
+
+        fs::remove_dir_all(dotfiles_dir.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn force_from_group_config() {
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&Context::default(), None).unwrap();
+        let target_dir = dotfiles_dir.with_file_name("target");
+        let ctx = &Context {
+            target_dir: Some(target_dir.clone()),
+            ..Default::default()
+        };
+        crate::fileops::init_cmd(ctx, None, false).unwrap();
+
+        let group_dir = dotfiles_dir.join("Configs").join("zsh");
+        fs::create_dir_all(&group_dir).unwrap();
+        fs::write(group_dir.join(".zshrc"), "dotfile").unwrap();
+        fs::write(
+            group_dir.join(dotfiles::GROUP_CONFIG_FILE),
+            "[defaults]\nforce = true\n",
+        )
+        .unwrap();
+        let target = target_dir.join(".zshrc");
+        fs::create_dir_all(&target_dir).unwrap();
+        fs::write(&target, "conflict").unwrap();
+
+        let sym = SymlinkHandler::try_new(ctx, None).unwrap();
+        let forced = |opts: super::AddOptions| super::group_is_forced(&sym, "zsh", &opts);
+        assert!(forced(Default::default()));
+        assert!(!forced(super::AddOptions {
+            no_force: true,
+            ..Default::default()
+        }));
+        // an interactive choice is never overridden
+        assert!(!forced(super::AddOptions {
+            interactive: true,
+            ..Default::default()
+        }));
+
+        let groups = ["zsh".to_string()];
+        let opts = super::AddOptions {
+            no_force: true,
+            ..Default::default()
+        };
+        super::add(ctx, None, &groups, &[], &opts).unwrap();
+        assert!(!target.is_symlink());
+
+        super::add(ctx, None, &groups, &[], &Default::default()).unwrap();
+        assert!(target.is_symlink());
+
+        fs::remove_dir_all(dotfiles_dir.parent().unwrap()).unwrap();
+    }
 }